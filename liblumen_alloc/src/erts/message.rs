@@ -8,7 +8,8 @@ pub enum Message {
     /// A message whose `data` is allocated inside the receiving process's heap.
     Process(Process),
     /// A message whose `message` `Term` had to be allocated in `heap` outside of the receiving
-    /// `Process` because the `Process`'s `Heap` was locked.
+    /// `Process` because the `Process`'s `Heap` was locked or the `Process` has an off-heap
+    /// message queue.
     HeapFragment(HeapFragment),
 }
 
@@ -4,6 +4,8 @@ mod flags;
 pub mod gc;
 mod heap;
 mod mailbox;
+mod max_heap_size;
+mod message_queue_data;
 mod monitor;
 mod priority;
 
@@ -17,7 +19,9 @@ use core::ops::DerefMut;
 use core::ptr::{self, NonNull};
 use core::str::Chars;
 use core::sync::atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
 
+use ::alloc::collections::VecDeque;
 use ::alloc::sync::Arc;
 
 use hashbrown::{HashMap, HashSet};
@@ -45,6 +49,8 @@ use self::gc::{GcError, RootSet};
 pub use self::flags::*;
 pub use self::heap::ProcessHeap;
 pub use self::mailbox::*;
+pub use self::max_heap_size::MaxHeapSize;
+pub use self::message_queue_data::MessageQueueData;
 pub use self::monitor::Monitor;
 pub use self::priority::Priority;
 
@@ -70,24 +76,34 @@ pub struct Process {
     /// ID of the scheduler that is running the process
     scheduler_id: Mutex<Option<scheduler::ID>>,
     /// The priority of the process in `scheduler`.
-    pub priority: Priority,
+    priority: RwLock<Priority>,
+    /// The number of outstanding promotions to each `Priority`
+    priority_promotions: Mutex<[usize; Priority::LENGTH]>,
     /// Process flags, e.g. `Process.flag/1`
     flags: AtomicProcessFlags,
     /// Minimum size of the heap that this process will start with
-    min_heap_size: usize,
+    min_heap_size: AtomicUsize,
     /// The maximum size of the heap allowed for this process
-    max_heap_size: usize,
+    max_heap_size: RwLock<MaxHeapSize>,
     /// Minimum virtual heap size for this process
-    min_vheap_size: usize,
+    min_vheap_size: AtomicUsize,
     /// The percentage of used to unused space at which a collection is triggered
     gc_threshold: f64,
     /// The maximum number of minor collections before a full sweep occurs
-    max_gen_gcs: usize,
+    max_gen_gcs: AtomicUsize,
+    /// The total time in microseconds spent collecting garbage for this process
+    gc_time: AtomicU64,
     /// off-heap allocations
     off_heap: SpinLock<LinkedList<HeapFragmentAdapter>>,
     off_heap_size: AtomicUsize,
     /// Process dictionary
     dictionary: Mutex<HashMap<Term, Term>>,
+    /// The module that handles calls to undefined functions
+    error_handler: RwLock<Atom>,
+    /// The maximum number of calls saved in `saved_calls`
+    save_calls: AtomicUsize,
+    /// The most recent calls, oldest first, when `save_calls` is greater than `0`
+    saved_calls: Mutex<VecDeque<Arc<ModuleFunctionArity>>>,
     /// The `pid` of the process that `spawn`ed this process.
     parent_pid: Option<Pid>,
     pid: Pid,
@@ -127,21 +143,26 @@ impl Process {
 
         Self {
             flags: AtomicProcessFlags::new(ProcessFlags::Default),
-            min_heap_size: heap_size,
-            max_heap_size: 0,
-            min_vheap_size: 0,
+            min_heap_size: AtomicUsize::new(heap_size),
+            max_heap_size: Default::default(),
+            min_vheap_size: AtomicUsize::new(gc::MIN_VHEAP_SIZE),
             gc_threshold: 0.75,
-            max_gen_gcs: 65535,
+            max_gen_gcs: AtomicUsize::new(gc::default_max_gen_gcs()),
+            gc_time: AtomicU64::new(0),
             off_heap,
             off_heap_size: AtomicUsize::new(0),
             dictionary: Default::default(),
+            error_handler: RwLock::new(Atom::try_from_str("error_handler").unwrap()),
+            save_calls: AtomicUsize::new(0),
+            saved_calls: Default::default(),
             pid,
             status: Default::default(),
             mailbox: Default::default(),
             heap: Mutex::new(heap),
             code_stack: Default::default(),
             scheduler_id: Mutex::new(None),
-            priority,
+            priority: RwLock::new(priority),
+            priority_promotions: Default::default(),
             parent_pid,
             initial_module_function_arity,
             run_reductions: Default::default(),
@@ -197,6 +218,139 @@ impl Process {
         self.are_flags_set(ProcessFlags::TrapExit)
     }
 
+    /// Sets whether the process is sensitive, returning the old value.
+    ///
+    /// The dictionary, messages and backtrace of a sensitive process are hidden from
+    /// `process_info`.
+    pub fn sensitive(&self, value: bool) -> bool {
+        let flag = ProcessFlags::Sensitive;
+
+        let old_flags = if value {
+            self.set_flags(flag)
+        } else {
+            self.clear_flags(flag)
+        };
+
+        old_flags.are_set(flag)
+    }
+
+    pub fn is_sensitive(&self) -> bool {
+        self.are_flags_set(ProcessFlags::Sensitive)
+    }
+
+    /// Sets where messages in the message queue are stored, returning the old value
+    pub fn set_message_queue_data(&self, message_queue_data: MessageQueueData) -> MessageQueueData {
+        let flag = ProcessFlags::OffHeapMessageQueue;
+
+        let old_flags = match message_queue_data {
+            MessageQueueData::OnHeap => self.clear_flags(flag),
+            MessageQueueData::OffHeap => self.set_flags(flag),
+        };
+
+        if old_flags.are_set(flag) {
+            MessageQueueData::OffHeap
+        } else {
+            MessageQueueData::OnHeap
+        }
+    }
+
+    pub fn message_queue_data(&self) -> MessageQueueData {
+        if self.are_flags_set(ProcessFlags::OffHeapMessageQueue) {
+            MessageQueueData::OffHeap
+        } else {
+            MessageQueueData::OnHeap
+        }
+    }
+
+    // Priority
+
+    /// The priority the process is scheduled with: the highest of its own priority and any
+    /// outstanding promotions.
+    pub fn priority(&self) -> Priority {
+        let priority = *self.priority.read();
+        let priority_promotions = self.priority_promotions.lock();
+
+        match Priority::ALL
+            .iter()
+            .rev()
+            .find(|promoted_priority| 0 < priority_promotions[**promoted_priority as usize])
+        {
+            Some(promoted_priority) => priority.max(*promoted_priority),
+            None => priority,
+        }
+    }
+
+    /// Sets the process's own priority, returning the old priority.  The new priority takes effect
+    /// the next time the process is queued by its scheduler.
+    pub fn set_priority(&self, priority: Priority) -> Priority {
+        mem::replace(&mut *self.priority.write(), priority)
+    }
+
+    /// Promotes the process to at least `priority` until a matching `demote_priority`.  The
+    /// process's scheduler needs to move it to the run queue for its new priority if it is
+    /// already queued, as `lumen_runtime::process::priority::PriorityPromotion` does.
+    pub fn promote_priority(&self, priority: Priority) {
+        self.priority_promotions.lock()[priority as usize] += 1;
+    }
+
+    /// Ends a promotion from `promote_priority`.
+    pub fn demote_priority(&self, priority: Priority) {
+        self.priority_promotions.lock()[priority as usize] -= 1;
+    }
+
+    // Error Handler
+
+    pub fn error_handler(&self) -> Atom {
+        *self.error_handler.read()
+    }
+
+    /// Sets the module that handles calls to undefined functions, returning the old module
+    pub fn set_error_handler(&self, error_handler: Atom) -> Atom {
+        mem::replace(&mut *self.error_handler.write(), error_handler)
+    }
+
+    // Saved Calls
+
+    pub fn save_calls(&self) -> usize {
+        self.save_calls.load(Ordering::Acquire)
+    }
+
+    /// Sets the number of calls to save, returning the old number.  Setting it to `0` stops saving
+    /// calls and clears the saved calls.
+    pub fn set_save_calls(&self, save_calls: usize) -> usize {
+        let mut saved_calls = self.saved_calls.lock();
+        let old_save_calls = self.save_calls.swap(save_calls, Ordering::AcqRel);
+
+        while save_calls < saved_calls.len() {
+            saved_calls.pop_front();
+        }
+
+        old_save_calls
+    }
+
+    /// The most recent calls, oldest first, if calls are being saved
+    pub fn saved_calls(&self) -> Option<Vec<Arc<ModuleFunctionArity>>> {
+        if 0 < self.save_calls() {
+            Some(self.saved_calls.lock().iter().cloned().collect())
+        } else {
+            None
+        }
+    }
+
+    fn save_call(&self, module_function_arity: Arc<ModuleFunctionArity>) {
+        let save_calls = self.save_calls();
+
+        if 0 < save_calls {
+            let mut saved_calls = self.saved_calls.lock();
+
+            while save_calls <= saved_calls.len() {
+                saved_calls.pop_front();
+            }
+
+            saved_calls.push_back(module_function_arity);
+        }
+    }
+
     // Alloc
 
     /// Acquires exclusive access to the process heap, blocking the current thread until it is able
@@ -389,18 +543,14 @@ impl Process {
 
     // Send
 
+    /// Sends `data` allocated in `heap_fragment`.  The mailbox owns `heap_fragment` until the
+    /// message is removed, when it is attached to this process's off-heap fragments, so that the
+    /// next garbage collection copies any terms still referenced from it to the heap.
     pub fn send_heap_message(&self, heap_fragment: NonNull<HeapFragment>, data: Term) {
-        let heap_fragment_ptr = heap_fragment.as_ptr();
-
-        let off_heap_unsafe_ref_heap_fragment = unsafe { UnsafeRef::from_raw(heap_fragment_ptr) };
-        self.off_heap
-            .lock()
-            .push_back(off_heap_unsafe_ref_heap_fragment);
-
-        let message_unsafe_ref_heap_fragment = unsafe { UnsafeRef::from_raw(heap_fragment_ptr) };
+        let unsafe_ref_heap_fragment = unsafe { UnsafeRef::from_raw(heap_fragment.as_ptr()) };
 
         self.send_message(Message::HeapFragment(message::HeapFragment {
-            unsafe_ref_heap_fragment: message_unsafe_ref_heap_fragment,
+            unsafe_ref_heap_fragment,
             data,
         }));
     }
@@ -410,9 +560,18 @@ impl Process {
     }
 
     /// Returns `true` if the process should stop waiting and be rescheduled as runnable.
+    ///
+    /// If the process has an off-heap message queue, `data` is always copied to a heap fragment,
+    /// so that senders never contend for the heap lock with the process or each other.
     pub fn send_from_other(&self, data: Term) -> AllocResult<bool> {
-        match self.heap.try_lock() {
-            Some(ref mut destination_heap) => match data.clone_to_heap(destination_heap) {
+        let option_destination_heap = if self.are_flags_set(ProcessFlags::OffHeapMessageQueue) {
+            None
+        } else {
+            self.heap.try_lock()
+        };
+
+        match option_destination_heap {
+            Some(mut destination_heap) => match data.clone_to_heap(&mut destination_heap) {
                 Ok(destination_data) => {
                     self.send_message(Message::Process(message::Process {
                         data: destination_data,
@@ -715,6 +874,16 @@ impl Process {
         }
     }
 
+    /// Returns a copy of the key/value pairs of the process dictionary without allocating on the
+    /// heap, so that other processes can read it.
+    pub fn dictionary_entries(&self) -> Vec<(Term, Term)> {
+        self.dictionary
+            .lock()
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect()
+    }
+
     /// Returns list of all keys from the process dictionary.
     pub fn get_keys(&self) -> AllocResult<Term> {
         let mut heap = self.heap.lock();
@@ -811,11 +980,96 @@ impl Process {
         heap.should_collect(self.gc_threshold)
     }
 
+    /// Determines if the binaries referenced by this process exceed its virtual binary heap, so
+    /// that a collection is needed to release those that are no longer referenced
+    #[inline]
+    pub fn should_collect_virtual_heap(&self) -> bool {
+        self.heap.lock().should_collect_virtual_heap()
+    }
+
+    /// The size in words of the heap fragments attached to this process
     #[inline(always)]
-    fn off_heap_size(&self) -> usize {
+    pub fn off_heap_size(&self) -> usize {
         self.off_heap_size.load(Ordering::Acquire)
     }
 
+    /// The size in words of the young generation of the heap
+    pub fn heap_size(&self) -> usize {
+        self.heap.lock().heap_size()
+    }
+
+    /// The size in words of the old generation of the heap
+    pub fn old_heap_size(&self) -> usize {
+        self.heap.lock().old_heap_size()
+    }
+
+    /// The number of minor collections since the last full sweep
+    pub fn minor_gcs(&self) -> usize {
+        self.heap.lock().gen_gc_count
+    }
+
+    /// The number of full sweeps
+    pub fn major_gcs(&self) -> usize {
+        self.heap.lock().full_sweep_count
+    }
+
+    /// The total time spent collecting garbage for this process
+    pub fn gc_time(&self) -> Duration {
+        Duration::from_micros(self.gc_time.load(Ordering::Acquire))
+    }
+
+    /// Adds the time taken by a collection to `gc_time`.  Collections are timed by the caller
+    /// because timers are not available on all targets.
+    pub fn add_gc_time(&self, duration: Duration) {
+        self.gc_time
+            .fetch_add(duration.as_micros() as u64, Ordering::AcqRel);
+    }
+
+    /// The size in words that the heap starts with and is never shrunk below
+    pub fn min_heap_size(&self) -> usize {
+        self.min_heap_size.load(Ordering::Acquire)
+    }
+
+    /// Sets the size in words that the heap is never shrunk below, returning the old size.  The
+    /// heap grows to the new size at the next full sweep.
+    pub fn set_min_heap_size(&self, min_heap_size: usize) -> usize {
+        self.min_heap_size.swap(min_heap_size, Ordering::AcqRel)
+    }
+
+    pub fn max_heap_size(&self) -> MaxHeapSize {
+        *self.max_heap_size.read()
+    }
+
+    /// Sets the maximum size of the heap, returning the old maximum
+    pub fn set_max_heap_size(&self, max_heap_size: MaxHeapSize) -> MaxHeapSize {
+        mem::replace(&mut *self.max_heap_size.write(), max_heap_size)
+    }
+
+    /// The size in words that the virtual binary heap starts with and is never shrunk below
+    pub fn min_vheap_size(&self) -> usize {
+        self.min_vheap_size.load(Ordering::Acquire)
+    }
+
+    /// Sets the size in words that the virtual binary heap is never shrunk below, returning the old
+    /// size.  The virtual binary heap of the young generation grows to the new size immediately,
+    /// while a smaller size only shrinks it at the next collection.
+    pub fn set_min_vheap_size(&self, min_vheap_size: usize) -> usize {
+        self.acquire_heap().grow_virtual_heap(min_vheap_size);
+
+        self.min_vheap_size.swap(min_vheap_size, Ordering::AcqRel)
+    }
+
+    /// The maximum number of minor collections before a full sweep
+    pub fn max_gen_gcs(&self) -> usize {
+        self.max_gen_gcs.load(Ordering::Acquire)
+    }
+
+    /// Sets the maximum number of minor collections before a full sweep, returning the old maximum.
+    /// `0` makes every collection a full sweep.
+    pub fn set_max_gen_gcs(&self, max_gen_gcs: usize) -> usize {
+        self.max_gen_gcs.swap(max_gen_gcs, Ordering::AcqRel)
+    }
+
     #[inline]
     fn is_gc_forced(&self) -> bool {
         self.flags.are_set(ProcessFlags::ForceGC)
@@ -887,6 +1141,7 @@ impl Process {
             let fragment_ptr = UnsafeRef::into_raw(fragment_ref);
             unsafe { ptr::drop_in_place(fragment_ptr) };
         }
+        self.off_heap_size.store(0, Ordering::Release);
     }
 
     /// Determines if we should try and grow the heap even when not necessary
@@ -1040,10 +1295,12 @@ impl Process {
     }
 
     pub fn push_frame(&self, frame: Frame) {
+        self.save_call(frame.module_function_arity());
         self.code_stack.lock().push(frame)
     }
 
     pub fn replace_frame(&self, frame: Frame) {
+        self.save_call(frame.module_function_arity());
         let mut locked_code_stack = self.code_stack.lock();
 
         // unwrap to ensure there is a frame to replace
@@ -121,6 +121,18 @@ impl VirtualBinaryHeap {
         }
     }
 
+    /// Sets the size in bytes of the binaries that can be referenced before a collection is needed
+    #[inline]
+    pub fn set_virtual_size(&mut self, size: usize) {
+        self.size = size;
+    }
+
+    /// The binaries referenced from this heap
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &ProcBin> {
+        self.bins.iter()
+    }
+
     #[inline]
     unsafe fn unlink_raw(&mut self, raw: *mut ProcBin) {
         // Remove from the list
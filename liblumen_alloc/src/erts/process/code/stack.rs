@@ -62,6 +62,13 @@ impl Debug for Stack {
 
 pub struct Trace(Vec<Arc<ModuleFunctionArity>>);
 
+impl Trace {
+    /// The frames from the most recent call
+    pub fn iter(&self) -> core::slice::Iter<Arc<ModuleFunctionArity>> {
+        self.0.iter()
+    }
+}
+
 impl Debug for Trace {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for module_function_arity in self.0.iter() {
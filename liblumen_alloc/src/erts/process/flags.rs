@@ -26,6 +26,11 @@ impl ProcessFlags {
     /// This flag indicates the processes linked to this process should send exit messages instead
     /// of causing this process to exit when they exit
     pub const TrapExit: Self = Self(1 << 6);
+    /// This flag indicates that the dictionary, messages and backtrace of the process should be
+    /// hidden from introspection
+    pub const Sensitive: Self = Self(1 << 7);
+    /// This flag indicates that messages should be left in heap fragments until they are received
+    pub const OffHeapMessageQueue: Self = Self(1 << 8);
 
     pub fn are_set(&self, flags: ProcessFlags) -> bool {
         (*self & flags) == flags
@@ -17,6 +17,8 @@ pub use self::rootset::RootSet;
 pub use self::sweep::{Sweep, Sweepable, Sweeper};
 pub use self::young_heap::YoungHeap;
 
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
 use super::alloc::SemispaceHeap;
 use crate::erts::exception;
 use thiserror::Error;
@@ -31,10 +33,10 @@ pub enum GcError {
     /// but panic, however this choice is left up to the caller
     #[error("unable to allocate memory for garbage collection")]
     Alloc(#[from] exception::Alloc),
-    /// Occurs when a process is configured with a maximum heap size,
-    /// and a projected heap growth is found to exceed the limit. In
-    /// this situation the only meaningful thing to do is to kill the
-    /// process
+    /// Occurs when a process is configured with a maximum heap size that
+    /// kills the process, and a projected heap growth is found to exceed
+    /// the limit. In this situation the only meaningful thing to do is to
+    /// kill the process with reason `killed`
     #[error("maximum heap size exceeded")]
     MaxHeapSizeExceeded,
     /// Indicates that an allocation could not be filled without first
@@ -77,3 +79,64 @@ pub fn estimate_cost(moved_live_words: usize, resize_moved_words: usize) -> usiz
         reds
     }
 }
+
+/// The size in words of binary data that the young generation of a process can reference before it
+/// is collected, which is `min_bin_vheap_size` in `erlang:spawn_opt/4`
+///
+/// The same as `BIN_VH_MIN_SIZE` in BEAM.
+pub const MIN_VHEAP_SIZE: usize = 46_422;
+
+/// Calculates the size in bytes of the virtual binary heap after a collection, given the bytes of
+/// binaries that are still `used` after the collection and the `size` before the collection.
+///
+/// Like BEAM, the size doubles until it holds `used`, shrinks by a quarter if less than half of it
+/// is used, and is never less than `min_size`.
+pub fn next_vheap_size(used: usize, size: usize, min_size: usize) -> usize {
+    let next_size = if used > size {
+        let mut next_size = size.max(1);
+
+        while used > next_size {
+            next_size *= 2;
+        }
+
+        next_size
+    } else if used < size / 2 {
+        size / 4 * 3
+    } else {
+        size
+    };
+
+    next_size.max(min_size)
+}
+
+static DEFAULT_MAX_GEN_GCS: AtomicUsize = AtomicUsize::new(65535);
+
+/// The maximum number of minor collections before a full sweep that new processes start with,
+/// which is `fullsweep_after` in `erlang:system_flag/2` and `erlang:spawn_opt/4`
+pub fn default_max_gen_gcs() -> usize {
+    DEFAULT_MAX_GEN_GCS.load(Ordering::SeqCst)
+}
+
+/// Sets the maximum number of minor collections before a full sweep that new processes start with,
+/// returning the old default.  Existing processes are not affected.
+pub fn set_default_max_gen_gcs(max_gen_gcs: usize) -> usize {
+    DEFAULT_MAX_GEN_GCS.swap(max_gen_gcs, Ordering::SeqCst)
+}
+
+static COLLECTIONS: AtomicU64 = AtomicU64::new(0);
+static WORDS_RECLAIMED: AtomicU64 = AtomicU64::new(0);
+
+/// The number of garbage collections performed by all processes
+pub fn collections() -> u64 {
+    COLLECTIONS.load(Ordering::SeqCst)
+}
+
+/// The number of words reclaimed by garbage collections of all processes
+pub fn words_reclaimed() -> u64 {
+    WORDS_RECLAIMED.load(Ordering::SeqCst)
+}
+
+pub(super) fn record_collection(words_reclaimed: usize) {
+    COLLECTIONS.fetch_add(1, Ordering::SeqCst);
+    WORDS_RECLAIMED.fetch_add(words_reclaimed as u64, Ordering::SeqCst);
+}
@@ -55,6 +55,19 @@ impl OldHeap {
         }
     }
 
+    /// Sets the size in bytes of the binaries that can be tenured to this heap before a full sweep
+    /// is needed
+    #[inline]
+    pub fn set_virtual_size(&mut self, size: usize) {
+        self.vheap.set_virtual_size(size)
+    }
+
+    /// The reference-counted binaries tenured to this heap
+    #[inline]
+    pub fn virtual_binaries(&self) -> impl Iterator<Item = &ProcBin> {
+        self.vheap.iter()
+    }
+
     /// Returns true if this heap has been allocated memory,
     /// otherwise returns false. Being inactive implies that
     /// the owning process has not yet undergone tenuring of
@@ -5,12 +5,14 @@ use core::ops::Deref;
 
 use ::alloc::sync::Arc;
 
-use crate::erts::process::alloc::TermAlloc;
+use crate::erts::process::alloc::{GenerationalHeap, TermAlloc, VirtualHeap};
+use crate::erts::process::gc::{self, GcError};
 use crate::erts::process::test::process;
+use crate::erts::process::MaxHeapSize;
 use crate::erts::term::closure::*;
 use crate::erts::term::prelude::*;
 use crate::erts::*;
-use crate::{atom, fixnum};
+use crate::{atom, fixnum, CloneToProcess};
 
 // This test ensures that after a full collection, expected garbage was cleaned up
 #[test]
@@ -49,6 +51,201 @@ fn gc_fullsweep_after_tenuring_test() {
     tenuring_gc_test(process, true);
 }
 
+// This test ensures that a process with `fullsweep_after` of `0` only does full sweeps
+#[test]
+fn gc_with_max_gen_gcs_zero_is_fullsweep_test() {
+    let process = process();
+    process.set_max_gen_gcs(0);
+
+    assert!(process.garbage_collect(0, &mut []).is_ok());
+    assert_eq!(process.minor_gcs(), 0);
+}
+
+// This test ensures that a full sweep happens once `max_gen_gcs` minor collections have happened
+#[test]
+fn gc_after_max_gen_gcs_minor_collections_is_fullsweep_test() {
+    let process = process();
+    process.set_max_gen_gcs(1);
+
+    assert!(process.garbage_collect(0, &mut []).is_ok());
+    assert_eq!(process.minor_gcs(), 1);
+
+    assert!(process.garbage_collect(0, &mut []).is_ok());
+    assert_eq!(process.minor_gcs(), 0);
+}
+
+// This test ensures that a full collection that would grow the heap past a max heap size that
+// kills the process fails, so that the caller can kill the process
+#[test]
+fn gc_fullsweep_exceeding_max_heap_size_with_kill_test() {
+    let process = process();
+    process.set_flags(ProcessFlags::NeedFullSweep);
+    process.set_max_heap_size(MaxHeapSize {
+        size: 1,
+        kill: true,
+        error_logger: false,
+    });
+
+    assert_eq!(
+        process.garbage_collect(0, &mut []),
+        Err(GcError::MaxHeapSizeExceeded)
+    );
+}
+
+// This test ensures that exceeding a max heap size that does not kill the process still collects
+#[test]
+fn gc_fullsweep_exceeding_max_heap_size_without_kill_test() {
+    let process = process();
+    process.set_flags(ProcessFlags::NeedFullSweep);
+    process.set_max_heap_size(MaxHeapSize {
+        size: 1,
+        kill: false,
+        error_logger: false,
+    });
+
+    assert!(process.garbage_collect(0, &mut []).is_ok());
+}
+
+// This test ensures that an exiting process can finish its allocation even if it exceeds its max
+// heap size
+#[test]
+fn gc_fullsweep_exceeding_max_heap_size_while_exiting_test() {
+    let process = process();
+    process.set_flags(ProcessFlags::NeedFullSweep);
+    process.set_max_heap_size(MaxHeapSize {
+        size: 1,
+        kill: true,
+        error_logger: false,
+    });
+    process.exit(atom!("killed"));
+
+    assert!(process.garbage_collect(0, &mut []).is_ok());
+}
+
+// This test ensures that a minor collection that may grow the heap past the max heap size asks for
+// a full sweep instead, as only a full sweep can determine if the max heap size is exceeded
+#[test]
+fn gc_minor_exceeding_max_heap_size_requires_fullsweep_test() {
+    let process = process();
+    process.set_max_heap_size(MaxHeapSize {
+        size: 1,
+        kill: true,
+        error_logger: false,
+    });
+
+    assert_eq!(
+        process.garbage_collect(0, &mut []),
+        Err(GcError::FullsweepRequired)
+    );
+    assert!(process.needs_fullsweep());
+}
+
+// This test ensures that reference-counted binaries that exceed the virtual binary heap trigger a
+// collection, and that the collection frees the binaries that are no longer referenced
+#[test]
+fn gc_virtual_heap_exceeded_frees_unreferenced_binaries_test() {
+    let process = process();
+    let bytes = vec![0; 64 * 1024];
+
+    let mut binary = process.binary_from_bytes(&bytes).unwrap();
+    match binary.decode().unwrap() {
+        TypedTerm::ProcBin(_) => (),
+        typed_term => panic!("binary is not reference-counted: {:?}", typed_term),
+    }
+
+    while !process.should_collect_virtual_heap() {
+        binary = process.binary_from_bytes(&bytes).unwrap();
+    }
+
+    assert!(process.acquire_heap().virtual_heap_used() > bytes.len());
+
+    let mut roots = [binary];
+    assert!(process.garbage_collect(0, &mut roots).is_ok());
+
+    assert!(!process.should_collect_virtual_heap());
+    // Only the rooted binary is still referenced
+    assert_eq!(process.acquire_heap().virtual_heap_used(), bytes.len());
+    assert_eq!(process.bytes_from_binary(roots[0]).unwrap(), &bytes[..]);
+}
+
+// This test ensures that a binary surviving collections does not exceed the virtual binary heap of
+// the old generation, which keeps its size across collections, so it does not force full sweeps
+#[test]
+fn gc_surviving_binary_does_not_force_fullsweep_test() {
+    let process = process();
+    let bytes = vec![0; 64 * 1024];
+
+    let mut roots = [process.binary_from_bytes(&bytes).unwrap()];
+
+    for _ in 0..3 {
+        assert!(process.garbage_collect(0, &mut roots).is_ok());
+        assert!(!process.needs_fullsweep());
+    }
+
+    let min_vheap_size = process.min_vheap_size() * mem::size_of::<Term>();
+    assert!(
+        process
+            .acquire_heap()
+            .heap()
+            .old_generation()
+            .virtual_size()
+            >= min_vheap_size
+    );
+    assert_eq!(process.bytes_from_binary(roots[0]).unwrap(), &bytes[..]);
+}
+
+// This test ensures that a min virtual binary heap size set after the process is created, such as
+// by `min_bin_vheap_size` in `erlang:spawn_opt/4`, applies before the first collection
+#[test]
+fn set_min_vheap_size_grows_virtual_heap_test() {
+    let process = process();
+    let min_vheap_size = gc::MIN_VHEAP_SIZE * 2;
+
+    process.set_min_vheap_size(min_vheap_size);
+
+    assert_eq!(
+        process.acquire_heap().virtual_size(),
+        min_vheap_size * mem::size_of::<Term>()
+    );
+}
+
+// This test ensures that a message removed from the mailbox stays valid until the next collection,
+// which copies it from its heap fragment to the heap and frees the fragment
+#[test]
+fn gc_copies_removed_heap_fragment_message_test() {
+    let sender = process();
+    let process = process();
+    let data = sender
+        .tuple_from_slice(&[
+            atom!("ok"),
+            sender.list_from_slice(&[fixnum!(1), fixnum!(2)]).unwrap(),
+        ])
+        .unwrap();
+    let (heap_fragment_data, heap_fragment) = data.clone_to_fragment().unwrap();
+
+    process.send_heap_message(heap_fragment, heap_fragment_data);
+    process.mailbox.lock().borrow_mut().remove(0, &process);
+    assert!(process.off_heap_size() > 0);
+
+    let mut roots = [heap_fragment_data];
+    assert!(process.garbage_collect(0, &mut roots).is_ok());
+
+    assert_eq!(roots[0], data);
+    let ptr: *mut Term = roots[0].dyn_cast();
+    assert!(process.is_owner(ptr));
+    assert_eq!(process.off_heap_size(), 0);
+}
+
+// This test ensures that the virtual binary heap grows to fit the binaries that survive a
+// collection, shrinks when they are released, and never shrinks below the minimum
+#[test]
+fn next_vheap_size_test() {
+    assert_eq!(gc::next_vheap_size(0, 100, 10), 75);
+    assert_eq!(gc::next_vheap_size(50, 100, 10), 100);
+    assert_eq!(gc::next_vheap_size(250, 100, 10), 400);
+    assert_eq!(gc::next_vheap_size(0, 10, 10), 10);
+}
+
 fn simple_gc_test(process: Process) {
     // Allocate an `{:ok, "hello world"}` tuple
     // First, the `ok` atom, an immediate, is super easy
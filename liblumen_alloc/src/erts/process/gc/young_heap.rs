@@ -109,6 +109,19 @@ impl YoungHeap {
         self.heap_available()
     }
 
+    /// Sets the size in bytes of the binaries that can be referenced from this heap before a
+    /// collection is needed
+    #[inline]
+    pub fn set_virtual_size(&mut self, size: usize) {
+        self.vheap.set_virtual_size(size)
+    }
+
+    /// The reference-counted binaries referenced from this heap
+    #[inline]
+    pub fn virtual_binaries(&self) -> impl Iterator<Item = &ProcBin> {
+        self.vheap.iter()
+    }
+
     /// Returns the size of the mature region, i.e. terms below the high water mark
     #[inline]
     pub(crate) fn mature_size(&self) -> usize {
@@ -1,7 +1,8 @@
 use core::alloc::Layout;
+use core::mem;
 use core::ptr::NonNull;
 
-use log::trace;
+use log::{error, trace};
 
 use liblumen_core::util::pointer::distance_absolute;
 
@@ -20,16 +21,20 @@ use super::{Process, ProcessFlags};
 pub struct ProcessHeap {
     // the number of minor collections
     pub(super) gen_gc_count: usize,
+    // the number of full sweeps
+    pub(super) full_sweep_count: usize,
     // The semi-space generational heap
     heap: SemispaceProcessHeap,
 }
 impl ProcessHeap {
     pub fn new(heap: *mut Term, heap_size: usize) -> Self {
-        let young = YoungHeap::new(heap, heap_size);
+        let mut young = YoungHeap::new(heap, heap_size);
+        young.set_virtual_size(gc::MIN_VHEAP_SIZE * mem::size_of::<Term>());
         let old = OldHeap::default();
         let heap = SemispaceHeap::new(young, old);
         Self {
             gen_gc_count: 0,
+            full_sweep_count: 0,
             heap,
         }
     }
@@ -40,11 +45,41 @@ impl ProcessHeap {
         self.heap.should_collect(gc_threshold)
     }
 
+    /// Returns true if the binaries referenced from the young generation exceed the size of its
+    /// virtual binary heap.  Binaries are allocated off-heap, so they never fill the heap to
+    /// trigger a collection that would release the binaries that are no longer referenced.
+    #[inline]
+    pub fn should_collect_virtual_heap(&self) -> bool {
+        let young = self.heap.young_generation();
+
+        young.virtual_heap_used() > young.virtual_size()
+    }
+
     #[cfg(test)]
     pub(super) fn heap(&self) -> &SemispaceProcessHeap {
         &self.heap
     }
 
+    /// The size in words of the old generation
+    #[inline]
+    pub fn old_heap_size(&self) -> usize {
+        self.heap.old_generation().heap_size()
+    }
+
+    /// The words used in the old generation
+    #[inline]
+    pub fn old_heap_used(&self) -> usize {
+        self.heap.old_generation().heap_used()
+    }
+
+    /// The reference-counted binaries referenced from the young and old generations
+    pub fn binaries(&self) -> impl Iterator<Item = &ProcBin> {
+        self.heap
+            .young_generation()
+            .virtual_binaries()
+            .chain(self.heap.old_generation().virtual_binaries())
+    }
+
     /// Runs garbage collection against the current heap
     ///
     /// This function and its helpers handle the following concerns:
@@ -76,12 +111,65 @@ impl ProcessHeap {
         let stack_size = young.stack_size();
         roots.push_range(sp, stack_size);
 
+        let used_before = self.heap_used() + self.old_heap_used();
+        let vheap_size_before = self.heap.young_generation().virtual_size();
+        // The old generation is replaced when it is first tenured to or swept, so its size is
+        // carried over like `bin_old_vheap_sz` in BEAM.
+        let old_vheap_size_before = self.heap.old_generation().virtual_size();
+
         // Initialize the collector
         // Determine if the current collection requires a full sweep or not
-        if process.needs_fullsweep() || self.gen_gc_count >= process.max_gen_gcs {
+        let result = if process.needs_fullsweep() || self.gen_gc_count >= process.max_gen_gcs() {
             self.collect_full(process, needed, roots)
         } else {
             self.collect_minor(process, needed, roots)
+        };
+
+        if result.is_ok() {
+            let used_after = self.heap_used() + self.old_heap_used();
+            gc::record_collection(used_before.saturating_sub(used_after));
+
+            self.resize_virtual_heaps(process, vheap_size_before, old_vheap_size_before);
+        }
+
+        result
+    }
+
+    /// Sizes the virtual binary heaps of the young and old generations for the binaries that
+    /// survived the collection, and schedules a full sweep if the binaries tenured to the old
+    /// generation exceed its virtual binary heap, as only a full sweep releases them.
+    fn resize_virtual_heaps(
+        &mut self,
+        process: &Process,
+        vheap_size_before: usize,
+        old_vheap_size_before: usize,
+    ) {
+        let min_vheap_size = process.min_vheap_size() * mem::size_of::<Term>();
+        let young = self.heap.young_generation_mut();
+        let vheap_size =
+            gc::next_vheap_size(young.virtual_heap_used(), vheap_size_before, min_vheap_size);
+        young.set_virtual_size(vheap_size);
+
+        let old = self.heap.old_generation_mut();
+        let old_vheap_used = old.virtual_heap_used();
+        let old_vheap_size_before = old_vheap_size_before.max(min_vheap_size);
+        if old_vheap_used > old_vheap_size_before {
+            process.flags.set(ProcessFlags::NeedFullSweep);
+        }
+
+        let old_vheap_size =
+            gc::next_vheap_size(old_vheap_used, old_vheap_size_before, min_vheap_size);
+        old.set_virtual_size(old_vheap_size);
+    }
+
+    /// Grows the virtual binary heap of the young generation to `min_vheap_size` words, so that a
+    /// `min_bin_vheap_size` set when spawning applies before the first collection.
+    pub fn grow_virtual_heap(&mut self, min_vheap_size: usize) {
+        let min_vheap_size = min_vheap_size * mem::size_of::<Term>();
+        let young = self.heap.young_generation_mut();
+
+        if young.virtual_size() < min_vheap_size {
+            young.set_virtual_size(min_vheap_size);
         }
     }
 
@@ -108,7 +196,9 @@ impl ProcessHeap {
         // If we already have a large enough heap, we don't need to grow it, but if the GROW flag is
         // set, then we should do it anyway, since it will prevent us from doing another full
         // collection for awhile (assuming one is not forced)
-        let baseline_size = alloc::next_heap_size(padded_estimate);
+        // The heap is never smaller than the min heap size, which may have been raised since the
+        // heap was allocated
+        let baseline_size = alloc::next_heap_size(padded_estimate.max(process.min_heap_size()));
         let new_heap_size =
             if baseline_size == young.heap_size() && process.should_force_heap_growth() {
                 alloc::next_heap_size(baseline_size)
@@ -117,9 +207,33 @@ impl ProcessHeap {
             };
 
         // Verify that our projected heap size is not going to blow the max heap size, if set
-        // NOTE: When this happens, we will be left with no choice but to kill the process
-        if process.max_heap_size > 0 && process.max_heap_size < new_heap_size {
-            return Err(GcError::MaxHeapSizeExceeded);
+        // NOTE: When this happens and `kill` is set, we will be left with no choice but to kill the
+        // process.  A process that is already exiting is allowed to grow, so that it can finish
+        // the allocation that triggered this collection before it exits.
+        let max_heap_size = process.max_heap_size();
+        if !max_heap_size.is_unlimited()
+            && max_heap_size.size < new_heap_size
+            && !process.is_exiting()
+        {
+            if max_heap_size.error_logger {
+                error!(
+                    "Process: {}\n\
+                     Context: maximum heap size reached\n\
+                     Max Heap Size: {}\n\
+                     Total Heap Size: {}\n\
+                     Kill: {}\n\
+                     Error Logger: {}",
+                    process,
+                    max_heap_size.size,
+                    new_heap_size,
+                    max_heap_size.kill,
+                    max_heap_size.error_logger
+                );
+            }
+
+            if max_heap_size.kill {
+                return Err(GcError::MaxHeapSizeExceeded);
+            }
         }
 
         // Unset heap_grow and need_fullsweep flags, because we are doing both
@@ -147,6 +261,7 @@ impl ProcessHeap {
 
         // Reset the generational GC counter
         self.gen_gc_count = 0;
+        self.full_sweep_count += 1;
 
         // Calculate reclamation for tracing
         let young = self.heap.young_generation();
@@ -198,13 +313,14 @@ impl ProcessHeap {
 
         // Check if the needed space consumes less than 25% of the new heap,
         // and if so, shrink the new heap immediately to free the unused space
-        if total_size > needed_after * 4 && process.min_heap_size < total_size {
+        let min_heap_size = process.min_heap_size();
+        if total_size > needed_after * 4 && min_heap_size < total_size {
             // Shrink to double our estimated need
             let mut estimate = needed_after * 2;
             // If our estimated need is too low, round up to the min heap size;
             // otherwise, calculate the next heap size bucket our need falls in
-            if estimate < process.min_heap_size {
-                estimate = process.min_heap_size;
+            if estimate < min_heap_size {
+                estimate = min_heap_size;
             } else {
                 estimate = alloc::next_heap_size(estimate);
             }
@@ -248,7 +364,8 @@ impl ProcessHeap {
         // the max heap size, if one was configured.
         //
         // If a max heap size is set, make sure we're not going to exceed it
-        if process.max_heap_size > 0 {
+        let max_heap_size = process.max_heap_size();
+        if !max_heap_size.is_unlimited() {
             // First, check if we have exceeded the max heap size
             let mut heap_size = size_before;
             // In this estimate, our stack size includes unused area between stack and heap
@@ -268,9 +385,12 @@ impl ProcessHeap {
             let baseline_size = stack_size + size_before + needed;
             heap_size += alloc::next_heap_size(baseline_size);
 
-            // When this error type is returned, a full sweep will be triggered
-            if heap_size > process.max_heap_size {
-                return Err(GcError::MaxHeapSizeExceeded);
+            // A full sweep may free enough to stay under the max heap size, so only it decides
+            // whether the max heap size is exceeded
+            if heap_size > max_heap_size.size {
+                process.flags.set(ProcessFlags::NeedFullSweep);
+
+                return Err(GcError::FullsweepRequired);
             }
         }
 
@@ -356,8 +476,9 @@ impl ProcessHeap {
 
             // If the new estimate is less than the min heap size, then round up;
             // otherwise, round the estimate up to the nearest heap size bucket
-            if estimate < process.min_heap_size {
-                estimate = process.min_heap_size;
+            let min_heap_size = process.min_heap_size();
+            if estimate < min_heap_size {
+                estimate = min_heap_size;
             } else {
                 estimate = alloc::next_heap_size(estimate);
             }
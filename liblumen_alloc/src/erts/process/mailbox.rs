@@ -1,13 +1,17 @@
 use core::default::Default;
+use core::ptr;
 
 use alloc::collections::vec_deque::Iter;
 use alloc::collections::VecDeque;
 
 use crate::borrow::CloneToProcess;
 use crate::erts::exception::AllocResult;
+use crate::erts::fragment::HeapFragment;
 use crate::erts::message::{self, Message};
 use crate::erts::process::Process;
-use crate::erts::term::prelude::Term;
+use crate::erts::term::prelude::{Reference, ReferenceNumber, Term};
+
+use intrusive_collections::UnsafeRef;
 
 #[derive(Debug)]
 pub struct Mailbox {
@@ -15,12 +19,40 @@ pub struct Mailbox {
     seen: isize,
 
     cursor: usize,
+    recv_timeout: ReceiveTimeout,
 }
 
 impl Mailbox {
     // Start receive implementation for the eir interpreter
-    pub fn recv_start(&self) {
+    pub fn recv_start(&mut self, timeout: ReceiveTimeout) {
         debug_assert!(self.cursor == 0);
+        self.recv_timeout = timeout;
+    }
+    /// Returns `true` if the `after` clause of the current receive should be run because no
+    /// message was matched before the timeout.
+    pub fn recv_timed_out(&self) -> bool {
+        match self.recv_timeout {
+            ReceiveTimeout::Immediate | ReceiveTimeout::TimedOut => true,
+            ReceiveTimeout::Infinity | ReceiveTimeout::Timer(_) => false,
+        }
+    }
+    /// Called when the timer with `reference_number` times out.  Returns `true` if the timer is
+    /// for the current receive and the process needs to be woken up to run the `after` clause.
+    pub fn recv_timeout(&mut self, reference_number: ReferenceNumber) -> bool {
+        match self.recv_timeout {
+            ReceiveTimeout::Timer(reference) if reference.number() == reference_number => {
+                self.recv_timeout = ReceiveTimeout::TimedOut;
+
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Ends the current receive without removing a message because the `after` clause is run.
+    pub fn recv_after(&mut self) {
+        debug_assert!(self.recv_timed_out());
+        self.cursor = 0;
+        self.recv_timeout = ReceiveTimeout::Infinity;
     }
     /// Important to remember that this might return a term in a heap
     /// fragment, and that it needs to be copied over to the process
@@ -32,6 +64,8 @@ impl Mailbox {
             Some(Message::HeapFragment(message::HeapFragment { data, .. })) => Some(*data),
         }
     }
+    /// Returns `true` if the message matched by the current receive is in a heap fragment, so that
+    /// the terms bound from it are copied to the process heap when the receive is done.
     pub fn recv_last_off_heap(&self) -> bool {
         match &self.messages[self.cursor - 1] {
             Message::Process(_) => false,
@@ -41,9 +75,17 @@ impl Mailbox {
     pub fn recv_increment(&mut self) {
         self.cursor += 1;
     }
-    pub fn recv_finish(&mut self, proc: &Process) {
+    /// Removes the matched message.  Returns the timer reference of the receive if it still needs
+    /// to be cancelled.
+    #[must_use]
+    pub fn recv_finish(&mut self, proc: &Process) -> Option<Reference> {
         self.remove(self.cursor - 1, proc);
         self.cursor = 0;
+
+        match core::mem::replace(&mut self.recv_timeout, ReceiveTimeout::Infinity) {
+            ReceiveTimeout::Timer(reference) => Some(reference),
+            _ => None,
+        }
     }
     // End receive implementation for the eir interpreter
 
@@ -104,15 +146,7 @@ impl Mailbox {
                 data,
             }) => match data.clone_to_heap(&mut process.acquire_heap()) {
                 Ok(heap_data) => {
-                    let mut off_heap = process.off_heap.lock();
-
-                    unsafe {
-                        let mut cursor =
-                            off_heap.cursor_mut_from_ptr(unsafe_ref_heap_fragment.as_ref());
-                        cursor
-                            .remove()
-                            .expect("HeapFragment was not in process's off_heap");
-                    }
+                    unsafe { free(unsafe_ref_heap_fragment.clone()) };
 
                     self.decrement_seen();
 
@@ -127,6 +161,9 @@ impl Mailbox {
         })
     }
 
+    /// Removes the message at `index`.  If the message is in a heap fragment, the fragment is
+    /// attached to `process`'s off-heap fragments, so that terms still referenced from the message
+    /// stay valid until the next garbage collection copies them to the heap and frees the fragment.
     pub fn remove(&mut self, index: usize, process: &Process) {
         let message = self.messages.remove(index).unwrap();
 
@@ -135,14 +172,7 @@ impl Mailbox {
             ..
         }) = message
         {
-            let mut off_heap = process.off_heap.lock();
-
-            unsafe {
-                let mut cursor = off_heap.cursor_mut_from_ptr(unsafe_ref_heap_fragment.as_ref());
-                cursor
-                    .remove()
-                    .expect("HeapFragment was not in process's off_heap");
-            }
+            process.attach_fragment(unsafe { &mut *UnsafeRef::into_raw(unsafe_ref_heap_fragment) });
         }
 
         if (index as isize) <= self.seen {
@@ -167,12 +197,49 @@ impl Mailbox {
     }
 }
 
+impl Drop for Mailbox {
+    fn drop(&mut self) {
+        for message in self.messages.drain(..) {
+            if let Message::HeapFragment(message::HeapFragment {
+                unsafe_ref_heap_fragment,
+                ..
+            }) = message
+            {
+                unsafe { free(unsafe_ref_heap_fragment) };
+            }
+        }
+    }
+}
+
 impl Default for Mailbox {
     fn default() -> Mailbox {
         Mailbox {
             messages: Default::default(),
             seen: -1,
             cursor: 0,
+            recv_timeout: ReceiveTimeout::Infinity,
         }
     }
 }
+
+/// Frees the heap fragment of a message whose data is no longer referenced.
+///
+/// Message heap fragments are owned by the mailbox and only attached to the process's off-heap
+/// fragments when the message is removed, so that garbage collections, which free the attached
+/// fragments, don't free messages that have not been received yet.
+unsafe fn free(unsafe_ref_heap_fragment: UnsafeRef<HeapFragment>) {
+    ptr::drop_in_place(UnsafeRef::into_raw(unsafe_ref_heap_fragment));
+}
+
+/// The `after` timeout of the current receive in the eir interpreter.
+#[derive(Debug, Clone, Copy)]
+pub enum ReceiveTimeout {
+    /// `after infinity` or no `after` clause.
+    Infinity,
+    /// `after 0`: the `after` clause runs as soon as no message matches.
+    Immediate,
+    /// A timer was started for the timeout and has not timed out yet.
+    Timer(Reference),
+    /// The timer timed out, so the `after` clause runs when no message matches.
+    TimedOut,
+}
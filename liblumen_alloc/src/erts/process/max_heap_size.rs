@@ -0,0 +1,73 @@
+use core::convert::{TryFrom, TryInto};
+
+use crate::erts::exception::Exception;
+use crate::erts::term::prelude::{Atom, Term, TypedTerm};
+
+/// The `max_heap_size` of a process, as set by `spawn_opt` or `process_flag`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MaxHeapSize {
+    /// The maximum size in words of the heap, or `0` if it is unlimited
+    pub size: usize,
+    /// Whether the process is killed when it exceeds `size`
+    pub kill: bool,
+    /// Whether an error report is logged when the process exceeds `size`
+    pub error_logger: bool,
+}
+
+impl MaxHeapSize {
+    pub fn is_unlimited(&self) -> bool {
+        self.size == 0
+    }
+
+    fn put_option(&mut self, key: Term, value: Term) -> Result<(), Exception> {
+        let key_atom: Atom = key.try_into()?;
+
+        match key_atom.name() {
+            "size" => {
+                self.size = value.try_into()?;
+            }
+            "kill" => {
+                self.kill = value.try_into()?;
+            }
+            "error_logger" => {
+                self.error_logger = value.try_into()?;
+            }
+            _ => return Err(badarg!().into()),
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MaxHeapSize {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            kill: true,
+            error_logger: true,
+        }
+    }
+}
+
+/// Either a size in words or a map with optional `size`, `kill` and `error_logger` keys.
+impl TryFrom<Term> for MaxHeapSize {
+    type Error = Exception;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        let mut max_heap_size: Self = Default::default();
+
+        match term.decode()? {
+            TypedTerm::SmallInteger(_) | TypedTerm::BigInteger(_) => {
+                max_heap_size.size = term.try_into()?;
+            }
+            TypedTerm::Map(map) => {
+                for (key, value) in map.iter() {
+                    max_heap_size.put_option(*key, *value)?;
+                }
+            }
+            _ => return Err(badarg!().into()),
+        }
+
+        Ok(max_heap_size)
+    }
+}
@@ -0,0 +1,44 @@
+use core::convert::{TryFrom, TryInto};
+
+use crate::erts::exception::Exception;
+use crate::erts::term::prelude::{Atom, Term};
+
+/// Where messages in the message queue of a process are stored
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageQueueData {
+    /// Messages are copied onto the process heap when they are sent if the heap is not locked
+    OnHeap,
+    /// Messages stay in heap fragments until they are received
+    OffHeap,
+}
+
+impl Default for MessageQueueData {
+    fn default() -> Self {
+        MessageQueueData::OnHeap
+    }
+}
+
+impl From<MessageQueueData> for Atom {
+    fn from(message_queue_data: MessageQueueData) -> Self {
+        let name = match message_queue_data {
+            MessageQueueData::OnHeap => "on_heap",
+            MessageQueueData::OffHeap => "off_heap",
+        };
+
+        Atom::try_from_str(name).unwrap()
+    }
+}
+
+impl TryFrom<Term> for MessageQueueData {
+    type Error = Exception;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        let atom: Atom = term.try_into()?;
+
+        match atom.name() {
+            "off_heap" => Ok(Self::OffHeap),
+            "on_heap" => Ok(Self::OnHeap),
+            _ => Err(badarg!().into()),
+        }
+    }
+}
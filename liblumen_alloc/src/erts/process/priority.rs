@@ -3,7 +3,8 @@ use core::convert::{TryFrom, TryInto};
 use crate::erts::exception::Exception;
 use crate::erts::term::prelude::{Atom, Encoded, Term, TypedTerm};
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// Ordered from lowest to highest, so that the higher of two priorities is their `max`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Priority {
     Low,
     Normal,
@@ -11,12 +12,35 @@ pub enum Priority {
     Max,
 }
 
+impl Priority {
+    pub const LENGTH: usize = 4;
+    pub const ALL: [Priority; Self::LENGTH] = [
+        Priority::Low,
+        Priority::Normal,
+        Priority::High,
+        Priority::Max,
+    ];
+}
+
 impl Default for Priority {
     fn default() -> Priority {
         Priority::Normal
     }
 }
 
+impl From<Priority> for Atom {
+    fn from(priority: Priority) -> Self {
+        let name = match priority {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Max => "max",
+        };
+
+        Atom::try_from_str(name).unwrap()
+    }
+}
+
 impl TryFrom<Atom> for Priority {
     type Error = Exception;
 
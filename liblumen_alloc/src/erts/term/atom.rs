@@ -38,6 +38,14 @@ lazy_static! {
 pub struct Atom(usize);
 impl Atom {
     pub const SIZE_IN_WORDS: usize = 1;
+    /// The maximum number of atoms that can be in the atom table, as IDs `0` through `MAX_ATOMS`
+    /// can be allocated.
+    pub const LIMIT: usize = MAX_ATOMS + 1;
+
+    /// The number of atoms in the atom table
+    pub fn count() -> usize {
+        ATOMS.read().names.len()
+    }
 
     /// Gets the identifier associated with this atom
     #[inline(always)]
@@ -13,7 +13,7 @@ use crate::erts::exception::AllocResult;
 use crate::erts::process::alloc::TermAlloc;
 use crate::erts::term::prelude::*;
 
-use super::prelude::{byte_offset, num_bytes};
+use super::prelude::{bit_offset, byte_offset, num_bytes};
 
 /// Represents a binary being matched
 ///
@@ -39,27 +39,30 @@ impl MatchBuffer {
     pub fn start_match(original: Term) -> Self {
         assert!(original.is_boxed());
 
-        let (base, full_byte_bit_len, byte_offset, bit_offset, partial_byte_bit_len) =
+        let (original, base, full_byte_bit_len, byte_offset, bit_offset, partial_byte_bit_len) =
             match original.decode().unwrap() {
                 TypedTerm::ProcBin(bin_ptr) => {
                     let bin = bin_ptr.as_ref();
                     let ptr = unsafe { bin.as_byte_ptr() };
-                    (ptr, bin.full_byte_len() * 8, 0, 0, 0)
+                    (original, ptr, bin.full_byte_len() * 8, 0, 0, 0)
                 }
                 TypedTerm::BinaryLiteral(bin_ptr) => {
                     let bin = bin_ptr.as_ref();
                     let ptr = unsafe { bin.as_byte_ptr() };
-                    (ptr, bin.full_byte_len() * 8, 0, 0, 0)
+                    (original, ptr, bin.full_byte_len() * 8, 0, 0, 0)
                 }
                 TypedTerm::HeapBinary(bin_ptr) => {
                     let bin = bin_ptr.as_ref();
                     let ptr = unsafe { bin.as_byte_ptr() };
-                    (ptr, bin.full_byte_len() * 8, 0, 0, 0)
+                    (original, ptr, bin.full_byte_len() * 8, 0, 0, 0)
                 }
                 TypedTerm::SubBinary(bin_ptr) => {
                     let bin = bin_ptr.as_ref();
                     let ptr = unsafe { bin.as_byte_ptr() };
+                    // The offsets are relative to the original binary of the sub binary, so
+                    // match on that instead of the sub binary.
                     (
+                        bin.original(),
                         ptr,
                         bin.full_byte_len() * 8,
                         bin.byte_offset(),
@@ -134,6 +137,26 @@ impl MatchContext {
         self.buffer.original
     }
 
+    /// The number of bits that have not been matched yet
+    #[inline]
+    pub fn remaining_bit_len(&self) -> usize {
+        self.buffer.bit_len - self.buffer.bit_offset
+    }
+
+    /// Matches the next bit, returning `None` if all bits have been matched.
+    #[inline]
+    pub fn match_bit(&mut self) -> Option<u8> {
+        if 0 < self.remaining_bit_len() {
+            let offset = self.buffer.bit_offset;
+            let byte = unsafe { *self.buffer.base.add(byte_offset(offset)) };
+            self.buffer.bit_offset += 1;
+
+            Some((byte >> (7 - bit_offset(offset))) & 1)
+        } else {
+            None
+        }
+    }
+
     /// Used by garbage collection to get a pointer to the original
     /// term in order to place/modify move markers
     #[inline]
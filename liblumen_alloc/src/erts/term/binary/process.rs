@@ -157,6 +157,12 @@ impl ProcBin {
         }
     }
 
+    /// The number of references to the binary, including those from other processes
+    #[inline]
+    pub fn ref_count(&self) -> usize {
+        self.inner().refc.load(atomic::Ordering::Acquire)
+    }
+
     #[inline]
     fn inner(&self) -> &ProcBinInner {
         unsafe { self.inner.as_ref() }
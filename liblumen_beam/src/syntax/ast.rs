@@ -16,6 +16,7 @@
 pub mod ast;
 pub mod error;
 pub mod format;
+pub mod source;
 
 #[cfg(test)]
 mod test;
@@ -41,4 +42,9 @@ impl AST {
             module: ast::ModuleDecl { forms },
         })
     }
+
+    /// Prints the module as Erlang source
+    pub fn to_erlang_source(&self) -> String {
+        source::module_to_source(&self.module)
+    }
 }
@@ -42,10 +42,25 @@ pub struct AbstractCode {
 impl AbstractCode {
     pub fn from_beam_file<P: AsRef<Path>>(path: P) -> FromBeamResult<Self> {
         let beam = crate::beam::reader::RawBeamFile::from_file(path)?;
-        let chunk = beam
-            .chunks()
+        let chunks = beam.chunks();
+
+        // Since OTP 20, `erlc +debug_info` writes the abstract code to `Dbgi` instead of `Abst`
+        if let Some(chunk) = chunks.iter().find(|c| c.id() == b"Dbgi") {
+            let debug_info = etf::Term::decode(std::io::Cursor::new(&chunk.data))?;
+            if let Ok((_, _, (forms, _))) =
+                debug_info.as_match(("debug_info_v1", "erl_abstract_code", (any(), any())))
+            {
+                let code = etf::Tuple::from(vec![
+                    etf::Term::from(etf::Atom::from("raw_abstract_v1")),
+                    forms.clone(),
+                ]);
+                return Ok(AbstractCode { code: code.into() });
+            }
+        }
+
+        let chunk = chunks
             .into_iter()
-            .find(|c| c.id() == b"Abst")
+            .find(|c| c.id() == b"Abst" && !c.data.is_empty())
             .ok_or(FromBeamError::NoDebugInfo)?;
         let code = etf::Term::decode(std::io::Cursor::new(&chunk.data))?;
         Ok(AbstractCode { code })
@@ -0,0 +1,691 @@
+//! Prints the AST as Erlang source, so that BEAM files with debug_info can be compiled by
+//! compilers that only parse Erlang source.
+//!
+//! Only the forms that affect the behaviour of the module are printed: types, specs, behaviours
+//! and custom attributes are left out.  Line numbers are not preserved.
+use std::fmt::Write;
+
+use super::ast::clause::Clause;
+use super::ast::common;
+use super::ast::expr::{self, Expression, Qualifier};
+use super::ast::form::{self, Form};
+use super::ast::guard::{Guard, OrGuard};
+use super::ast::literal;
+use super::ast::pat::Pattern;
+use super::ast::ModuleDecl;
+
+pub fn module_to_source(module: &ModuleDecl) -> String {
+    let mut out = String::new();
+
+    for form in module.forms.iter() {
+        write_form(&mut out, form);
+    }
+
+    out
+}
+
+/// A node that is printed the same way whether it is in an expression, pattern or guard.
+trait Source: Sized {
+    /// Patterns can't be parenthesized, so operators in patterns are printed as is.
+    const PARENTHESIZE: bool;
+
+    fn write_source(&self, out: &mut String);
+
+    /// Atoms and variables can be printed without parentheses anywhere.
+    fn is_simple(&self) -> bool;
+
+    /// Lists are printed as `[A, B | T]` instead of nested cons cells.
+    fn as_cons(&self) -> Option<&common::Cons<Self>>;
+
+    fn is_nil(&self) -> bool;
+
+    fn write_simple(&self, out: &mut String) {
+        if self.is_simple() {
+            self.write_source(out);
+        } else {
+            out.push('(');
+            self.write_source(out);
+            out.push(')');
+        }
+    }
+}
+
+impl Source for Expression {
+    const PARENTHESIZE: bool = true;
+
+    fn write_source(&self, out: &mut String) {
+        match self {
+            Expression::Integer(x) => write_integer(out, x),
+            Expression::Float(x) => write_float(out, x),
+            Expression::String(x) => write_string(out, &x.value),
+            Expression::Char(x) => write_char(out, x),
+            Expression::Atom(x) => write_atom(out, &x.value),
+            Expression::Match(x) => {
+                out.push('(');
+                x.left.write_source(out);
+                out.push_str(" = ");
+                x.right.write_source(out);
+                out.push(')');
+            }
+            Expression::Var(x) => out.push_str(&x.name),
+            Expression::Tuple(x) => write_tuple(out, x),
+            Expression::Nil(_) => out.push_str("[]"),
+            Expression::Cons(x) => write_cons(out, x),
+            Expression::Binary(x) => write_binary(out, x),
+            Expression::UnaryOp(x) => write_unary_op(out, x),
+            Expression::BinaryOp(x) => write_binary_op(out, x),
+            Expression::Record(x) => write_record(out, x),
+            Expression::RecordIndex(x) => write_record_index(out, x),
+            Expression::Map(x) => write_map(out, x),
+            Expression::Catch(x) => {
+                out.push_str("(catch ");
+                x.expr.write_source(out);
+                out.push(')');
+            }
+            Expression::LocalCall(x) => write_local_call(out, x),
+            Expression::RemoteCall(x) => write_remote_call(out, x),
+            Expression::Comprehension(x) => write_comprehension(out, x),
+            Expression::Block(x) => {
+                out.push_str("begin ");
+                write_body(out, &x.body);
+                out.push_str(" end");
+            }
+            Expression::If(x) => {
+                out.push_str("if ");
+                for (index, clause) in x.clauses.iter().enumerate() {
+                    if 0 < index {
+                        out.push_str("; ");
+                    }
+                    write_or_guards(out, &clause.guards);
+                    out.push_str(" -> ");
+                    write_body(out, &clause.body);
+                }
+                out.push_str(" end");
+            }
+            Expression::Case(x) => {
+                out.push_str("case ");
+                x.expr.write_source(out);
+                out.push_str(" of ");
+                write_clauses(out, &x.clauses);
+                out.push_str(" end");
+            }
+            Expression::Try(x) => write_try(out, x),
+            Expression::Receive(x) => write_receive(out, x),
+            Expression::InternalFun(x) => {
+                out.push_str("fun ");
+                write_atom(out, &x.function);
+                write!(out, "/{}", x.arity).unwrap();
+            }
+            Expression::ExternalFun(x) => {
+                out.push_str("fun ");
+                x.module.write_simple(out);
+                out.push(':');
+                x.function.write_simple(out);
+                out.push('/');
+                x.arity.write_simple(out);
+            }
+            Expression::AnonymousFun(x) => write_anonymous_fun(out, x),
+        }
+    }
+
+    fn is_simple(&self) -> bool {
+        match self {
+            Expression::Atom(_) | Expression::Var(_) => true,
+            _ => false,
+        }
+    }
+
+    fn as_cons(&self) -> Option<&common::Cons<Self>> {
+        match self {
+            Expression::Cons(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    fn is_nil(&self) -> bool {
+        match self {
+            Expression::Nil(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Source for Pattern {
+    const PARENTHESIZE: bool = false;
+
+    fn write_source(&self, out: &mut String) {
+        match self {
+            Pattern::Integer(x) => write_integer(out, x),
+            Pattern::Float(x) => write_float(out, x),
+            Pattern::String(x) => write_string(out, &x.value),
+            Pattern::Char(x) => write_char(out, x),
+            Pattern::Atom(x) => write_atom(out, &x.value),
+            Pattern::Var(x) => out.push_str(&x.name),
+            Pattern::Match(x) => {
+                x.left.write_source(out);
+                out.push_str(" = ");
+                x.right.write_source(out);
+            }
+            Pattern::Tuple(x) => write_tuple(out, x),
+            Pattern::Nil(_) => out.push_str("[]"),
+            Pattern::Cons(x) => write_cons(out, x),
+            Pattern::Binary(x) => write_binary(out, x),
+            Pattern::UnaryOp(x) => write_unary_op(out, x),
+            Pattern::BinaryOp(x) => write_binary_op(out, x),
+            Pattern::Record(x) => write_record(out, x),
+            Pattern::RecordIndex(x) => write_record_index(out, x),
+            Pattern::Map(x) => write_map(out, x),
+        }
+    }
+
+    fn is_simple(&self) -> bool {
+        match self {
+            Pattern::Atom(_) | Pattern::Var(_) => true,
+            _ => false,
+        }
+    }
+
+    fn as_cons(&self) -> Option<&common::Cons<Self>> {
+        match self {
+            Pattern::Cons(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    fn is_nil(&self) -> bool {
+        match self {
+            Pattern::Nil(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Source for Guard {
+    const PARENTHESIZE: bool = true;
+
+    fn write_source(&self, out: &mut String) {
+        match self {
+            Guard::Integer(x) => write_integer(out, x),
+            Guard::Float(x) => write_float(out, x),
+            Guard::String(x) => write_string(out, &x.value),
+            Guard::Char(x) => write_char(out, x),
+            Guard::Atom(x) => write_atom(out, &x.value),
+            Guard::Var(x) => out.push_str(&x.name),
+            Guard::Tuple(x) => write_tuple(out, x),
+            Guard::Nil(_) => out.push_str("[]"),
+            Guard::Cons(x) => write_cons(out, x),
+            Guard::Binary(x) => write_binary(out, x),
+            Guard::UnaryOp(x) => write_unary_op(out, x),
+            Guard::BinaryOp(x) => write_binary_op(out, x),
+            Guard::Record(x) => write_record(out, x),
+            Guard::RecordIndex(x) => write_record_index(out, x),
+            Guard::LocalCall(x) => write_local_call(out, x),
+            Guard::RemoteCall(x) => write_remote_call(out, x),
+        }
+    }
+
+    fn is_simple(&self) -> bool {
+        match self {
+            Guard::Atom(_) | Guard::Var(_) => true,
+            _ => false,
+        }
+    }
+
+    fn as_cons(&self) -> Option<&common::Cons<Self>> {
+        match self {
+            Guard::Cons(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    fn is_nil(&self) -> bool {
+        match self {
+            Guard::Nil(_) => true,
+            _ => false,
+        }
+    }
+}
+
+fn write_form(out: &mut String, form: &Form) {
+    match form {
+        Form::Module(x) => {
+            out.push_str("-module(");
+            write_atom(out, &x.name);
+            out.push_str(").\n");
+        }
+        Form::Export(x) => {
+            out.push_str("-export([");
+            for (index, export) in x.funs.iter().enumerate() {
+                if 0 < index {
+                    out.push_str(", ");
+                }
+                write_atom(out, &export.fun);
+                write!(out, "/{}", export.arity).unwrap();
+            }
+            out.push_str("]).\n");
+        }
+        Form::Import(x) => {
+            out.push_str("-import(");
+            write_atom(out, &x.module);
+            out.push_str(", [");
+            for (index, import) in x.funs.iter().enumerate() {
+                if 0 < index {
+                    out.push_str(", ");
+                }
+                write_atom(out, &import.fun);
+                write!(out, "/{}", import.arity).unwrap();
+            }
+            out.push_str("]).\n");
+        }
+        Form::Compile(x) => {
+            writeln!(out, "-compile({}).", x.options).unwrap();
+        }
+        Form::Record(x) => write_record_decl(out, x),
+        Form::Fun(x) => {
+            for (index, clause) in x.clauses.iter().enumerate() {
+                if 0 < index {
+                    out.push_str(";\n");
+                }
+                write_atom(out, &x.name);
+                write_clause(out, clause, true);
+            }
+            out.push_str(".\n");
+        }
+        Form::Behaviour(_)
+        | Form::ExportType(_)
+        | Form::File(_)
+        | Form::Type(_)
+        | Form::Spec(_)
+        | Form::Attr(_)
+        | Form::Eof(_) => (),
+    }
+}
+
+fn write_record_decl(out: &mut String, record: &form::RecordDecl) {
+    out.push_str("-record(");
+    write_atom(out, &record.name);
+    out.push_str(", {");
+    for (index, field) in record.fields.iter().enumerate() {
+        if 0 < index {
+            out.push_str(", ");
+        }
+        write_atom(out, &field.name);
+        out.push_str(" = ");
+        field.default_value.write_source(out);
+    }
+    out.push_str("}).\n");
+}
+
+/// Writes `(Patterns) when Guards -> Body` if `parenthesize`, otherwise the single pattern of a
+/// `case`, `receive` or `try` clause without the parentheses.
+fn write_clause(out: &mut String, clause: &Clause, parenthesize: bool) {
+    if parenthesize {
+        out.push('(');
+    }
+    write_comma_separated(out, &clause.patterns);
+    if parenthesize {
+        out.push(')');
+    }
+
+    if !clause.guards.is_empty() {
+        out.push_str(" when ");
+        write_or_guards(out, &clause.guards);
+    }
+
+    out.push_str(" -> ");
+    write_body(out, &clause.body);
+}
+
+fn write_clauses(out: &mut String, clauses: &[Clause]) {
+    for (index, clause) in clauses.iter().enumerate() {
+        if 0 < index {
+            out.push_str("; ");
+        }
+        write_clause(out, clause, false);
+    }
+}
+
+fn write_or_guards(out: &mut String, or_guards: &[OrGuard]) {
+    for (index, or_guard) in or_guards.iter().enumerate() {
+        if 0 < index {
+            out.push_str("; ");
+        }
+        write_comma_separated(out, &or_guard.and_guards);
+    }
+}
+
+fn write_body(out: &mut String, body: &[Expression]) {
+    write_comma_separated(out, body);
+}
+
+fn write_comma_separated<T: Source>(out: &mut String, nodes: &[T]) {
+    for (index, node) in nodes.iter().enumerate() {
+        if 0 < index {
+            out.push_str(", ");
+        }
+        node.write_source(out);
+    }
+}
+
+fn write_try(out: &mut String, x: &expr::Try) {
+    out.push_str("try ");
+    write_body(out, &x.body);
+
+    if !x.case_clauses.is_empty() {
+        out.push_str(" of ");
+        write_clauses(out, &x.case_clauses);
+    }
+
+    if !x.catch_clauses.is_empty() {
+        out.push_str(" catch ");
+        for (index, clause) in x.catch_clauses.iter().enumerate() {
+            if 0 < index {
+                out.push_str("; ");
+            }
+            write_catch_clause(out, clause);
+        }
+    }
+
+    if !x.after.is_empty() {
+        out.push_str(" after ");
+        write_body(out, &x.after);
+    }
+
+    out.push_str(" end");
+}
+
+/// The pattern of a catch clause is `{Class, Reason, Stacktrace}` in the abstract format, but
+/// `Class:Reason:Stacktrace` in source.
+fn write_catch_clause(out: &mut String, clause: &Clause) {
+    match clause.patterns.as_slice() {
+        [Pattern::Tuple(tuple)] if tuple.elements.len() == 3 => {
+            tuple.elements[0].write_source(out);
+            out.push(':');
+            tuple.elements[1].write_source(out);
+
+            match &tuple.elements[2] {
+                Pattern::Var(var) if var.is_anonymous() => (),
+                stacktrace => {
+                    out.push(':');
+                    stacktrace.write_source(out);
+                }
+            }
+        }
+        patterns => write_comma_separated(out, patterns),
+    }
+
+    if !clause.guards.is_empty() {
+        out.push_str(" when ");
+        write_or_guards(out, &clause.guards);
+    }
+
+    out.push_str(" -> ");
+    write_body(out, &clause.body);
+}
+
+fn write_receive(out: &mut String, x: &expr::Receive) {
+    out.push_str("receive ");
+    write_clauses(out, &x.clauses);
+
+    if let Some(timeout) = &x.timeout {
+        out.push_str(" after ");
+        timeout.write_source(out);
+        out.push_str(" -> ");
+        write_body(out, &x.after);
+    }
+
+    out.push_str(" end");
+}
+
+fn write_anonymous_fun(out: &mut String, x: &expr::AnonymousFun) {
+    out.push_str("fun ");
+    for (index, clause) in x.clauses.iter().enumerate() {
+        if 0 < index {
+            out.push_str("; ");
+        }
+        if let Some(name) = &x.name {
+            out.push_str(name);
+        }
+        write_clause(out, clause, true);
+    }
+    out.push_str(" end");
+}
+
+fn write_comprehension(out: &mut String, x: &expr::Comprehension) {
+    out.push_str(if x.is_list { "[" } else { "<< " });
+    x.expr.write_source(out);
+    out.push_str(" || ");
+
+    for (index, qualifier) in x.qualifiers.iter().enumerate() {
+        if 0 < index {
+            out.push_str(", ");
+        }
+        match qualifier {
+            Qualifier::Generator(generator) => {
+                generator.pattern.write_source(out);
+                out.push_str(" <- ");
+                generator.expr.write_source(out);
+            }
+            Qualifier::BitStringGenerator(generator) => {
+                generator.pattern.write_source(out);
+                out.push_str(" <= ");
+                generator.expr.write_source(out);
+            }
+            Qualifier::Filter(filter) => filter.write_source(out),
+        }
+    }
+
+    out.push_str(if x.is_list { "]" } else { " >>" });
+}
+
+fn write_tuple<T: Source>(out: &mut String, x: &common::Tuple<T>) {
+    out.push('{');
+    write_comma_separated(out, &x.elements);
+    out.push('}');
+}
+
+fn write_cons<T: Source>(out: &mut String, x: &common::Cons<T>) {
+    out.push('[');
+    x.head.write_source(out);
+
+    let mut tail = &x.tail;
+    while let Some(cons) = tail.as_cons() {
+        out.push_str(", ");
+        cons.head.write_source(out);
+        tail = &cons.tail;
+    }
+
+    if !tail.is_nil() {
+        out.push_str(" | ");
+        tail.write_source(out);
+    }
+
+    out.push(']');
+}
+
+fn write_binary<T: Source>(out: &mut String, x: &common::Binary<T>) {
+    out.push_str("<<");
+    for (index, element) in x.elements.iter().enumerate() {
+        if 0 < index {
+            out.push_str(", ");
+        }
+
+        element.element.write_source(out);
+
+        if let Some(size) = &element.size {
+            out.push(':');
+            size.write_source(out);
+        }
+
+        if let Some(type_specs) = &element.tsl {
+            for (index, type_spec) in type_specs.iter().enumerate() {
+                out.push(if index == 0 { '/' } else { '-' });
+                out.push_str(&type_spec.name);
+                if let Some(value) = type_spec.value {
+                    write!(out, ":{}", value).unwrap();
+                }
+            }
+        }
+    }
+    out.push_str(">>");
+}
+
+fn write_unary_op<T: Source>(out: &mut String, x: &common::UnaryOp<T>) {
+    if T::PARENTHESIZE {
+        out.push('(');
+    }
+    out.push_str(&x.operator);
+    out.push(' ');
+    x.operand.write_source(out);
+    if T::PARENTHESIZE {
+        out.push(')');
+    }
+}
+
+fn write_binary_op<T: Source>(out: &mut String, x: &common::BinaryOp<T>) {
+    if T::PARENTHESIZE {
+        out.push('(');
+    }
+    x.left_operand.write_source(out);
+    write!(out, " {} ", x.operator).unwrap();
+    x.right_operand.write_source(out);
+    if T::PARENTHESIZE {
+        out.push(')');
+    }
+}
+
+fn write_record<T: Source>(out: &mut String, x: &common::Record<T>) {
+    if let Some(base) = &x.base {
+        base.write_simple(out);
+    }
+    out.push('#');
+    write_atom(out, &x.name);
+    out.push('{');
+    for (index, field) in x.fields.iter().enumerate() {
+        if 0 < index {
+            out.push_str(", ");
+        }
+        match &field.name {
+            Some(name) => write_atom(out, name),
+            None => out.push('_'),
+        }
+        out.push_str(" = ");
+        field.value.write_source(out);
+    }
+    out.push('}');
+}
+
+fn write_record_index<T: Source>(out: &mut String, x: &common::RecordIndex<T>) {
+    if let Some(base) = &x.base {
+        base.write_simple(out);
+    }
+    out.push('#');
+    write_atom(out, &x.record);
+    out.push('.');
+    write_atom(out, &x.field);
+}
+
+fn write_map<T: Source>(out: &mut String, x: &common::Map<T>) {
+    if let Some(base) = &x.base {
+        base.write_simple(out);
+    }
+    out.push_str("#{");
+    for (index, pair) in x.pairs.iter().enumerate() {
+        if 0 < index {
+            out.push_str(", ");
+        }
+        pair.key.write_source(out);
+        out.push_str(if pair.is_assoc { " => " } else { " := " });
+        pair.value.write_source(out);
+    }
+    out.push('}');
+}
+
+fn write_local_call<T: Source>(out: &mut String, x: &common::LocalCall<T>) {
+    x.function.write_simple(out);
+    out.push('(');
+    write_comma_separated(out, &x.args);
+    out.push(')');
+}
+
+fn write_remote_call<T: Source>(out: &mut String, x: &common::RemoteCall<T>) {
+    x.module.write_simple(out);
+    out.push(':');
+    x.function.write_simple(out);
+    out.push('(');
+    write_comma_separated(out, &x.args);
+    out.push(')');
+}
+
+fn write_integer(out: &mut String, x: &literal::Integer) {
+    write!(out, "{}", x.value).unwrap();
+}
+
+fn write_float(out: &mut String, x: &literal::Float) {
+    // Rust's shortest round-trip representation, but Erlang requires a fraction before any
+    // exponent: `1e300` is `1.0e300`.
+    let string = format!("{:?}", x.value);
+
+    match string.find('e') {
+        Some(index) if !string[..index].contains('.') => {
+            write!(out, "{}.0{}", &string[..index], &string[index..]).unwrap()
+        }
+        _ => out.push_str(&string),
+    }
+}
+
+/// Characters are integers, so `$a` is written as `97` to avoid escaping.
+fn write_char(out: &mut String, x: &literal::Char) {
+    write!(out, "{}", x.value as u32).unwrap();
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        write_escaped(out, c, '"');
+    }
+    out.push('"');
+}
+
+const RESERVED_WORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "end", "fun", "if", "let", "not", "of", "or", "orelse", "receive",
+    "rem", "try", "when", "xor",
+];
+
+fn write_atom(out: &mut String, name: &str) {
+    let mut chars = name.chars();
+    let unquoted = match chars.next() {
+        Some(first) => {
+            first.is_ascii_lowercase()
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+                && !RESERVED_WORDS.contains(&name)
+        }
+        None => false,
+    };
+
+    if unquoted {
+        out.push_str(name);
+    } else {
+        out.push('\'');
+        for c in name.chars() {
+            write_escaped(out, c, '\'');
+        }
+        out.push('\'');
+    }
+}
+
+fn write_escaped(out: &mut String, c: char, quote: char) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        c if c == quote => {
+            out.push('\\');
+            out.push(c);
+        }
+        c if c.is_control() => write!(out, "\\x{{{:X}}}", c as u32).unwrap(),
+        c => out.push(c),
+    }
+}
@@ -9,3 +9,14 @@ fn it_works() {
         })
         .unwrap();
 }
+
+#[test]
+fn to_erlang_source() {
+    let source = AST::from_beam_file("tests/testdata/ast/test.beam")
+        .unwrap()
+        .to_erlang_source();
+
+    assert!(source.starts_with("-module(test).\n"));
+    assert!(source.contains("cons(H, T) -> {H, T}.\n"));
+    assert!(source.contains("to_my_list([H | T]) -> cons(H, to_my_list(T)).\n"));
+}
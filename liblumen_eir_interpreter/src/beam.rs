@@ -0,0 +1,27 @@
+//! Loads `.beam` files compiled with `debug_info`.
+//!
+//! The abstract code in the `Dbgi` (or pre-OTP 20 `Abst`) chunk is printed back as Erlang source,
+//! so that it goes through the same frontend and passes as `.erl` files.
+
+use std::ffi::OsStr;
+use std::path::Path;
+
+use liblumen_beam::syntax::ast::AST;
+
+pub fn is_beam_file<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref().extension() == Some(OsStr::new("beam"))
+}
+
+/// Reads the abstract code of the `.beam` file at `path` as Erlang source.
+///
+/// Prints why the abstract code can't be read, such as the file being compiled without
+/// `debug_info`, to stderr.
+pub fn erlang_source<P: AsRef<Path>>(path: P) -> Result<String, ()> {
+    match AST::from_beam_file(path.as_ref()) {
+        Ok(ast) => Ok(ast.to_erlang_source()),
+        Err(err) => {
+            eprintln!("{}: {}", path.as_ref().display(), err);
+            Err(())
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use std::io::{self, Read};
 use std::path::Path;
+use std::process::exit;
 
 use clap::{App, Arg};
 
@@ -12,21 +14,23 @@ use libeir_syntax_erl::ast::Module as ErlAstModule;
 use libeir_syntax_erl::lower_module;
 use libeir_syntax_erl::{Parse, ParseConfig, Parser};
 
-use liblumen_eir_interpreter::call_result::call_run_erlang;
+use liblumen_eir_interpreter::beam;
+use liblumen_eir_interpreter::call_result::{Budget, ProcessResult};
+use liblumen_eir_interpreter::format::ErlangFormat;
+use liblumen_eir_interpreter::literal;
+use liblumen_eir_interpreter::qualified::mark_qualified_calls;
 use liblumen_eir_interpreter::VM;
 
-use liblumen_alloc::erts::term::prelude::Atom;
+use liblumen_alloc::erts::term::prelude::*;
 
-use lumen_runtime::scheduler::Scheduler;
-
-fn parse_file<T, P>(path: P, config: ParseConfig) -> (T, Parser)
+fn parse_file<T, P>(path: P, config: ParseConfig) -> Result<(T, Parser), ()>
 where
     T: Parse<T>,
     P: AsRef<Path>,
 {
     let parser = Parser::new(config);
     let errs = match parser.parse_file::<_, T>(path) {
-        Ok(ast) => return (ast, parser),
+        Ok(ast) => return Ok((ast, parser)),
         Err(errs) => errs,
     };
     let emitter =
@@ -34,14 +38,28 @@ where
     for err in errs.iter() {
         emitter.diagnostic(&err.to_diagnostic()).unwrap();
     }
-    panic!("parse failed");
+    Err(())
 }
 
-fn lower_file<P>(path: P, config: ParseConfig) -> Result<Module, ()>
+fn parse_string<T>(input: &str, config: ParseConfig) -> Result<(T, Parser), ()>
 where
-    P: AsRef<Path>,
+    T: Parse<T>,
 {
-    let (parsed, parser): (ErlAstModule, _) = parse_file(path, config);
+    let parser = Parser::new(config);
+    let errs = match parser.parse_string::<&str, T>(input) {
+        Ok(ast) => return Ok((ast, parser)),
+        Err(errs) => errs,
+    };
+    let emitter =
+        StandardStreamEmitter::new(ColorChoice::Auto).set_codemap(parser.config.codemap.clone());
+    for err in errs.iter() {
+        emitter.diagnostic(&err.to_diagnostic()).unwrap();
+    }
+    Err(())
+}
+
+fn lower((mut parsed, parser): (ErlAstModule, Parser)) -> Result<Module, ()> {
+    mark_qualified_calls(&mut parsed);
     let (res, messages) = lower_module(&parsed);
 
     let emitter =
@@ -53,6 +71,29 @@ where
     res
 }
 
+fn lower_file<P>(path: P, config: ParseConfig) -> Result<Module, ()>
+where
+    P: AsRef<Path>,
+{
+    lower(parse_file(path, config)?)
+}
+
+fn lower_string(input: &str, config: ParseConfig) -> Result<Module, ()> {
+    lower(parse_string(input, config)?)
+}
+
+fn register(mut eir_mod: Module) {
+    for fun_def in eir_mod.function_iter() {
+        let fun = fun_def.function();
+        fun.graph_validate_global();
+    }
+
+    let mut pass_manager = PassManager::default();
+    pass_manager.run(&mut eir_mod);
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+}
+
 fn main() {
     let matches = App::new("Lumen Eir Interpreter CLI")
         .version("alpha")
@@ -65,34 +106,89 @@ fn main() {
             Arg::from_usage("<FUN_IDENT> -i,--ident <IDENT> 'select single function'")
                 .required(true),
         )
+        .arg(
+            Arg::from_usage("[ARGS] -a,--arg <TERM> 'pass an Erlang term literal as the next argument'")
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(Arg::from_usage(
+            "[STDIN] --stdin 'read comma-separated Erlang term literals from stdin as the arguments \
+             after any --arg'",
+        ))
         .get_matches();
 
     let ident = FunctionIdent::parse(matches.value_of("FUN_IDENT").unwrap()).unwrap();
 
     &*VM;
 
-    let arc_scheduler = Scheduler::current();
-    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
-
     let module = Atom::try_from_str(&ident.module.as_str()).unwrap();
     let function = Atom::try_from_str(&ident.name.as_str()).unwrap();
-    assert!(ident.arity == 0);
 
-    for file in matches.values_of("LOAD_ERL_FILES").unwrap() {
+    for file in matches.values_of("LOAD_ERL_FILES").into_iter().flatten() {
         let config = ParseConfig::default();
-        let mut eir_mod = lower_file(file, config).unwrap();
-
-        for fun_def in eir_mod.function_iter() {
-            let fun = fun_def.function();
-            fun.graph_validate_global();
+        let eir_mod = if beam::is_beam_file(file) {
+            beam::erlang_source(file).and_then(|source| lower_string(&source, config))
+        } else {
+            lower_file(file, config)
         }
+        .unwrap_or_else(|()| exit(2));
 
-        let mut pass_manager = PassManager::default();
-        pass_manager.run(&mut eir_mod);
+        register(eir_mod);
+    }
+
+    let mut sources: Vec<String> = matches
+        .values_of("ARGS")
+        .into_iter()
+        .flatten()
+        .map(String::from)
+        .collect();
+
+    if matches.is_present("STDIN") {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input).unwrap();
+
+        // Allow the input to be terminated like an Erlang expression
+        let input = input.trim().trim_end_matches('.').trim_end();
+
+        if !input.is_empty() {
+            sources.push(input.to_string());
+        }
+    }
 
-        VM.modules.write().unwrap().register_erlang_module(eir_mod);
+    // Arguments are only parsed, never evaluated, so they cannot run code
+    let arguments = literal::parse_terms(&VM.init, &sources.join(", ")).unwrap_or_else(|err| {
+        eprintln!(
+            "Arguments are not valid Erlang term literals ({}): {}",
+            err,
+            sources.join(", ")
+        );
+        exit(2)
+    });
+
+    if arguments.len() != ident.arity {
+        eprintln!("{} called with {} argument(s)", ident, arguments.len());
+        exit(2);
     }
 
-    let res = call_run_erlang(init_arc_process, module, function, &[]);
-    println!("Returned with {:?}", res.result);
+    match VM.call(module, function, &arguments, Budget::Unlimited) {
+        Ok(ProcessResult {
+            result: Ok(value), ..
+        }) => println!("{}", ErlangFormat(value)),
+        Ok(ProcessResult {
+            result: Err((class, reason, stacktrace)),
+            ..
+        }) => {
+            eprintln!(
+                "{{{},{},{}}}",
+                ErlangFormat(class),
+                ErlangFormat(reason),
+                ErlangFormat(stacktrace)
+            );
+            exit(1);
+        }
+        Err(err) => {
+            eprintln!("{:?}", err);
+            exit(1);
+        }
+    }
 }
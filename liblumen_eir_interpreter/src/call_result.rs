@@ -1,11 +1,14 @@
 use core::ptr::NonNull;
 
 use std::convert::TryInto;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use liblumen_alloc::atom;
 use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
-use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::exception::{self, RuntimeException};
 use liblumen_alloc::erts::process::code;
 use liblumen_alloc::erts::process::{Process, Status};
 use liblumen_alloc::erts::term::prelude::*;
@@ -13,7 +16,6 @@ use liblumen_alloc::erts::HeapFragment;
 
 use lumen_runtime::process::spawn::options::Options;
 use lumen_runtime::scheduler::{Scheduler, Spawned};
-use lumen_runtime::system;
 
 /// A sort of ghetto-future used to get the result from a process
 /// spawn.
@@ -30,6 +32,9 @@ impl ProcessResultReceiver {
 
 pub struct ProcessResult {
     pub heap: NonNull<HeapFragment>,
+    /// The returned value, or the class, reason and stacktrace of the exception.  The
+    /// interpreter does not build stacktraces yet, so the stacktrace is the atom `trace` when
+    /// the function raised and `[]` when the process exited without returning or raising.
     pub result: Result<Term, (Term, Term, Term)>,
 }
 
@@ -37,6 +42,37 @@ struct ProcessResultSender {
     tx: Sender<ProcessResult>,
 }
 
+/// Limits how long `run_erlang` runs a process before killing it.
+#[derive(Clone, Copy, Debug)]
+pub enum Budget {
+    Unlimited,
+    /// The maximum `Process::total_reductions` of the process.
+    Reductions(u64),
+    /// The maximum wall-clock time.
+    Time(Duration),
+}
+
+impl Budget {
+    fn is_exhausted(&self, process: &Process, start: Instant) -> bool {
+        match self {
+            Budget::Unlimited => false,
+            Budget::Reductions(reductions) => {
+                *reductions <= process.total_reductions.load(Ordering::SeqCst)
+            }
+            Budget::Time(duration) => *duration <= start.elapsed(),
+        }
+    }
+}
+
+/// Why `run_erlang` could not get a `ProcessResult`.
+#[derive(Debug)]
+pub enum CallError {
+    /// The process is waiting, but nothing, such as a message or timer, can wake it.
+    Deadlock,
+    /// The process did not finish within its `Budget`, so it was killed.
+    BudgetExhausted,
+}
+
 pub fn call_run_erlang(
     proc: Arc<Process>,
     module: Atom,
@@ -44,45 +80,59 @@ pub fn call_run_erlang(
     args: &[Term],
 ) -> ProcessResult {
     let recv = call_erlang(proc, module, function, args);
+
+    run_erlang(recv, Budget::Unlimited).unwrap()
+}
+
+/// Runs the process of `recv` until it returns, raises or exits.
+pub fn run_erlang(recv: ProcessResultReceiver, budget: Budget) -> Result<ProcessResult, CallError> {
     let run_arc_process = recv.process.clone();
+    let start = Instant::now();
 
     loop {
         let ran = Scheduler::current().run_through(&run_arc_process);
 
         match *run_arc_process.status.read() {
-            Status::Exiting(_) => {
-                return recv.try_get().unwrap();
+            Status::Exiting(ref exception) => {
+                return Ok(recv.try_get().unwrap_or_else(|| exited(exception)));
             }
             Status::Waiting => {
-                if ran {
-                    system::io::puts(&format!(
-                        "WAITING Run queues len = {:?}",
-                        Scheduler::current().run_queues_len()
-                    ));
-                } else {
-                    panic!(
-                        "{:?} did not run.  Deadlock likely in {:#?}",
-                        run_arc_process,
-                        Scheduler::current()
-                    );
+                // A timer, such as a receive timeout, will wake a process
+                if !ran && Scheduler::current().hierarchy.read().is_empty() {
+                    return Err(CallError::Deadlock);
                 }
             }
-            Status::Runnable => {
-                system::io::puts(&format!(
-                    "RUNNABLE Run queues len = {:?}",
-                    Scheduler::current().run_queues_len()
-                ));
-            }
-            Status::Running => {
-                system::io::puts(&format!(
-                    "RUNNING Run queues len = {:?}",
-                    Scheduler::current().run_queues_len()
-                ));
-            }
+            Status::Runnable | Status::Running => (),
+        }
+
+        if budget.is_exhausted(&run_arc_process, start) {
+            run_arc_process.exit(atom!("killed"));
+            // Exiting processes are only cleaned up from the run queues
+            Scheduler::current().stop_waiting(&run_arc_process);
+
+            return Err(CallError::BudgetExhausted);
         }
     }
 }
 
+/// The result of a process that exited without returning or raising, such as from an exit signal.
+fn exited(exception: &RuntimeException) -> ProcessResult {
+    let class = match exception.class() {
+        Some(class) => Atom::str_to_term(&class.to_string()),
+        None => atom!("error"),
+    };
+    let reason = exception.reason().unwrap_or_else(|| atom!("unknown"));
+
+    let mut fragment = fragment_for(&[reason]);
+    let frag_mut = unsafe { fragment.as_mut() };
+    let reason = reason.clone_to_heap(frag_mut).unwrap();
+
+    ProcessResult {
+        heap: fragment,
+        result: Err((class, reason, Term::NIL)),
+    }
+}
+
 pub fn call_erlang(
     proc: Arc<Process>,
     module: Atom,
@@ -176,7 +226,7 @@ fn return_ok(arc_process: &Arc<Process>) -> code::Result {
     let sender_any: Resource = sender_resource.into();
     let sender: &ProcessResultSender = sender_any.downcast_ref().unwrap();
 
-    let mut fragment = HeapFragment::new_from_word_size(100).unwrap();
+    let mut fragment = fragment_for(&argument_vec);
     let frag_mut = unsafe { fragment.as_mut() };
     let ret = argument_vec[0].clone_to_heap(frag_mut).unwrap();
 
@@ -213,7 +263,7 @@ fn return_throw(arc_process: &Arc<Process>) -> code::Result {
     let sender_any: Resource = sender_resource.into();
     let sender: &ProcessResultSender = sender_any.downcast_ref().unwrap();
 
-    let mut fragment = HeapFragment::new_from_word_size(100).unwrap();
+    let mut fragment = fragment_for(&argument_vec[..3]);
     let frag_mut = unsafe { fragment.as_mut() };
 
     let ret_type = argument_vec[0].clone_to_heap(frag_mut).unwrap();
@@ -236,3 +286,10 @@ fn return_throw(arc_process: &Arc<Process>) -> code::Result {
 
     code::result_from_exception(arc_process, exc.into())
 }
+
+/// A fragment large enough to hold clones of all `terms`.
+fn fragment_for(terms: &[Term]) -> NonNull<HeapFragment> {
+    let word_size = terms.iter().map(|term| term.size_in_words()).sum();
+
+    HeapFragment::new_from_word_size(word_size).unwrap()
+}
@@ -1,10 +1,8 @@
 use std::convert::TryInto;
 use std::sync::Arc;
 
-use cranelift_entity::EntityRef;
-use libeir_ir::{Block, FunctionIndex};
-
-use liblumen_alloc::erts::exception;
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception::{self, Exception, RuntimeException};
 use liblumen_alloc::erts::process::code;
 use liblumen_alloc::erts::process::code::stack::frame::Frame;
 use liblumen_alloc::erts::process::Process;
@@ -12,7 +10,10 @@ use liblumen_alloc::erts::term::closure::Definition;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::ModuleFunctionArity;
 
-use crate::exec::CallExecutor;
+use lumen_runtime::otp::erlang::garbage_collect_2::{self, Requested};
+use lumen_runtime::process::garbage_collection;
+
+use crate::exec::{call_closure, CallExecutor};
 
 fn module() -> Atom {
     Atom::try_from_str("lumen_eir_interpreter_intrinsics").unwrap()
@@ -140,26 +141,20 @@ pub fn interpreter_closure_code(arc_process: &Arc<Process>) -> code::Result {
     let closure_term = arc_process.stack_pop().unwrap();
 
     let closure: Boxed<Closure> = closure_term.try_into().unwrap();
-    println!("{:?}", closure);
 
     let mfa = arc_process.current_module_function_arity().unwrap();
     let definition = arc_process.current_definition().unwrap();
 
-    let block_id;
-    let function_index;
+    let closure_index;
+    let module_unique;
     match definition {
-        Definition::Anonymous {
-            index, old_unique, ..
-        } => {
-            block_id = index;
-            function_index = old_unique;
+        Definition::Anonymous { index, unique, .. } => {
+            closure_index = index;
+            module_unique = unique;
         }
         _ => unreachable!(),
     }
 
-    //let block_id: usize = closure.env_slice()[0].try_into().unwrap();
-    let block = Block::new(block_id as usize);
-
     let mut argument_vec: Vec<Term> = Vec::new();
     match argument_list.decode().unwrap() {
         TypedTerm::Nil => (),
@@ -179,12 +174,13 @@ pub fn interpreter_closure_code(arc_process: &Arc<Process>) -> code::Result {
     exec.call_block(
         &crate::VM,
         arc_process,
+        closure_term,
         mfa.module,
-        FunctionIndex::new(function_index as usize),
+        module_unique,
+        closure_index,
         //mfa.function,
         //arity as usize,
         &mut argument_vec,
-        block,
         &mut environment_vec,
     );
 
@@ -219,3 +215,52 @@ pub fn apply(arc_process: &Arc<Process>) -> code::Result {
 
     Process::call_code(arc_process)
 }
+
+/// `erlang:garbage_collect/0,1,2` with the continuations of the call in `args`.  A synchronous
+/// collection returns once it ran, with whether it did.
+pub fn garbage_collect(
+    arc_process: &Arc<Process>,
+    args: &[Term],
+    pid: Term,
+    options: Term,
+) -> code::Result {
+    match garbage_collect_2::request(arc_process, pid, options) {
+        Ok(Requested::Async) => call_closure(arc_process, args[0], &mut [atom!("async")]),
+        Ok(Requested::Sync { request_id }) => {
+            let module_function_arity = arc_process.current_module_function_arity().unwrap();
+
+            arc_process.stack_push(args[0])?;
+            arc_process.stack_push(request_id)?;
+            arc_process.replace_frame(Frame::new(module_function_arity, wait_for_garbage_collect));
+        }
+        Err(Exception::Runtime(RuntimeException::Error(error))) => call_closure(
+            arc_process,
+            args[1],
+            &mut [atom!("error"), error.reason(), atom!("trace")],
+        ),
+        Err(Exception::Runtime(exception)) => {
+            unreachable!("garbage_collect only raises errors: {:?}", exception)
+        }
+        Err(Exception::System(err)) => return Err(err),
+    }
+
+    Ok(())
+}
+
+/// Expects the following on stack:
+/// * request ID of the synchronous `garbage_collect` request
+/// * return continuation
+fn wait_for_garbage_collect(arc_process: &Arc<Process>) -> code::Result {
+    arc_process.reduce();
+
+    let request_id = arc_process.stack_top().unwrap();
+
+    if let Some(collected) = garbage_collection::receive_reply(arc_process, request_id) {
+        arc_process.stack_pop();
+        let return_continuation = arc_process.stack_pop().unwrap();
+
+        call_closure(arc_process, return_continuation, &mut [collected.into()]);
+    }
+
+    Ok(())
+}
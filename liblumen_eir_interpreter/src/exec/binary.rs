@@ -0,0 +1,599 @@
+use std::convert::TryInto;
+
+use num_bigint::BigInt;
+
+use libeir_ir::{BinaryEntrySpecifier, Endianness};
+
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+/// The bits of a bitstring under construction by `OpKind::BinaryPush`.
+pub struct BitVec {
+    bytes: Vec<u8>,
+    bit_len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        BitVec {
+            bytes: Vec::new(),
+            bit_len: 0,
+        }
+    }
+
+    /// Copies all the bits of `term`.  Returns `None` if `term` is not a bitstring.
+    pub fn from_bitstring(term: Term) -> Option<Self> {
+        let mut bit_vec = Self::new();
+        bit_vec.push_bitstring(term, None)?;
+
+        Some(bit_vec)
+    }
+
+    /// Appends the segment `value` encoded according to `specifier`.  `size` is the already
+    /// decoded `Size` of the segment, if one was given.
+    ///
+    /// Returns `None` if `value` or `size` is not valid for `specifier`, which is a `badarg`.
+    pub fn push_segment(
+        &mut self,
+        specifier: &BinaryEntrySpecifier,
+        value: Term,
+        size: Option<usize>,
+    ) -> Option<()> {
+        match specifier {
+            BinaryEntrySpecifier::Integer {
+                endianness, unit, ..
+            } => {
+                let bit_len = size.unwrap_or(8) * (*unit as usize);
+                let bytes = signed_bytes_le(value)?;
+
+                self.push_integer(&bytes, bit_len, *endianness);
+
+                Some(())
+            }
+            BinaryEntrySpecifier::Float { endianness, unit } => {
+                let bit_len = size.unwrap_or(64) * (*unit as usize);
+                let f = float(value)?;
+
+                let bytes = match bit_len {
+                    32 => match endianness {
+                        Endianness::Big => (f as f32).to_be_bytes().to_vec(),
+                        Endianness::Little => (f as f32).to_le_bytes().to_vec(),
+                        Endianness::Native => (f as f32).to_ne_bytes().to_vec(),
+                    },
+                    64 => match endianness {
+                        Endianness::Big => f.to_be_bytes().to_vec(),
+                        Endianness::Little => f.to_le_bytes().to_vec(),
+                        Endianness::Native => f.to_ne_bytes().to_vec(),
+                    },
+                    _ => return None,
+                };
+
+                self.push_bytes(&bytes);
+
+                Some(())
+            }
+            BinaryEntrySpecifier::Bytes { unit } => {
+                let bit_len = size.map(|size| size * (*unit as usize) * 8);
+
+                self.push_bitstring(value, bit_len)
+                    .filter(|pushed_bit_len| pushed_bit_len % 8 == 0)
+                    .map(|_| ())
+            }
+            BinaryEntrySpecifier::Bits { unit } => {
+                let bit_len = size.map(|size| size * (*unit as usize));
+
+                self.push_bitstring(value, bit_len).map(|_| ())
+            }
+            BinaryEntrySpecifier::Utf8 => {
+                let c = code_point(value)?;
+                let mut buffer = [0; 4];
+
+                self.push_bytes(c.encode_utf8(&mut buffer).as_bytes());
+
+                Some(())
+            }
+            BinaryEntrySpecifier::Utf16 { endianness } => {
+                let c = code_point(value)?;
+                let mut buffer = [0; 2];
+
+                for unit in c.encode_utf16(&mut buffer) {
+                    let bytes = match endianness {
+                        Endianness::Big => unit.to_be_bytes(),
+                        Endianness::Little => unit.to_le_bytes(),
+                        Endianness::Native => unit.to_ne_bytes(),
+                    };
+
+                    self.push_bytes(&bytes);
+                }
+
+                Some(())
+            }
+            BinaryEntrySpecifier::Utf32 { endianness } => {
+                let c = code_point(value)? as u32;
+                let bytes = match endianness {
+                    Endianness::Big => c.to_be_bytes(),
+                    Endianness::Little => c.to_le_bytes(),
+                    Endianness::Native => c.to_ne_bytes(),
+                };
+
+                self.push_bytes(&bytes);
+
+                Some(())
+            }
+        }
+    }
+
+    /// Allocates the bits as a binary if they are a whole number of bytes; otherwise, as a
+    /// bitstring `SubBinary` of a binary holding the partial byte.
+    pub fn to_term(&self, process: &Process) -> AllocResult<Term> {
+        let partial_byte_bit_len = (self.bit_len % 8) as u8;
+
+        if partial_byte_bit_len == 0 {
+            process.binary_from_bytes(&self.bytes)
+        } else {
+            let full_byte_len = self.bit_len / 8;
+            let original = process.binary_from_bytes(&self.bytes)?;
+
+            process.subbinary_from_original(original, 0, 0, full_byte_len, partial_byte_bit_len)
+        }
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        let bit_offset = self.bit_len % 8;
+
+        if bit_offset == 0 {
+            self.bytes.push(0);
+        }
+
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0b1000_0000 >> bit_offset;
+        }
+
+        self.bit_len += 1;
+    }
+
+    /// Pushes the low `bit_len` bits of `byte`, most significant bit first.
+    fn push_bits(&mut self, byte: u8, bit_len: usize) {
+        for shift in (0..bit_len).rev() {
+            self.push_bit((byte >> shift) & 1 == 1);
+        }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        if self.bit_len % 8 == 0 {
+            self.bytes.extend_from_slice(bytes);
+            self.bit_len += bytes.len() * 8;
+        } else {
+            for byte in bytes {
+                self.push_bits(*byte, 8);
+            }
+        }
+    }
+
+    /// Pushes the low `bit_len` bits of the two's complement little-endian `bytes`.  `bytes` are
+    /// sign-extended if they are shorter than `bit_len`.
+    fn push_integer(&mut self, bytes: &[u8], bit_len: usize, endianness: Endianness) {
+        let sign_byte = match bytes.last() {
+            Some(last) if last & 0b1000_0000 != 0 => 0xFF,
+            _ => 0x00,
+        };
+        let byte_at = |index: usize| bytes.get(index).cloned().unwrap_or(sign_byte);
+
+        let full_byte_len = bit_len / 8;
+        let partial_byte_bit_len = bit_len % 8;
+
+        let little = match endianness {
+            Endianness::Big => false,
+            Endianness::Little => true,
+            Endianness::Native => cfg!(target_endian = "little"),
+        };
+
+        if little {
+            // Least significant bytes first with the remaining most significant bits last, so
+            // that `<<1:12/little>>` is `<<1, 0:4>>` like BEAM.
+            for index in 0..full_byte_len {
+                self.push_bits(byte_at(index), 8);
+            }
+
+            self.push_bits(byte_at(full_byte_len), partial_byte_bit_len);
+        } else {
+            self.push_bits(byte_at(full_byte_len), partial_byte_bit_len);
+
+            for index in (0..full_byte_len).rev() {
+                self.push_bits(byte_at(index), 8);
+            }
+        }
+    }
+
+    /// Pushes the first `bit_len` bits of the bitstring `term` or all bits if `bit_len` is `None`.
+    /// Returns the number of bits pushed or `None` if `term` is not a bitstring or is shorter
+    /// than `bit_len`.
+    fn push_bitstring(&mut self, term: Term, bit_len: Option<usize>) -> Option<usize> {
+        let mut bits: Vec<u8> = Vec::new();
+        let mut bytes: Vec<u8> = Vec::new();
+
+        match term.decode().ok()? {
+            TypedTerm::HeapBinary(heap_binary) => bytes.extend_from_slice(heap_binary.as_bytes()),
+            TypedTerm::ProcBin(process_binary) => {
+                bytes.extend_from_slice(process_binary.as_bytes())
+            }
+            TypedTerm::BinaryLiteral(binary_literal) => {
+                bytes.extend_from_slice(binary_literal.as_bytes())
+            }
+            TypedTerm::SubBinary(subbinary) => {
+                bytes.extend(subbinary.full_byte_iter());
+
+                if !subbinary.is_binary() {
+                    bits.extend(subbinary.partial_byte_bit_iter());
+                }
+            }
+            TypedTerm::MatchContext(match_context) => {
+                bytes.extend(match_context.full_byte_iter());
+                bits.extend(match_context.partial_byte_bit_iter());
+            }
+            _ => return None,
+        }
+
+        let total_bit_len = bytes.len() * 8 + bits.len();
+        let bit_len = bit_len.unwrap_or(total_bit_len);
+
+        if total_bit_len < bit_len {
+            return None;
+        }
+
+        let full_byte_len = bit_len / 8;
+        self.push_bytes(&bytes[..full_byte_len]);
+
+        let remaining_bits = bytes
+            .iter()
+            .skip(full_byte_len)
+            .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) & 1))
+            .chain(bits.into_iter())
+            .take(bit_len % 8);
+
+        for bit in remaining_bits {
+            self.push_bit(bit == 1);
+        }
+
+        Some(bit_len)
+    }
+}
+
+/// Starts matching the bitstring `term`.  Returns `None` if `term` is not a bitstring.
+pub fn start_match(term: Term) -> Option<MatchContext> {
+    match term.decode().ok()? {
+        TypedTerm::HeapBinary(_)
+        | TypedTerm::ProcBin(_)
+        | TypedTerm::BinaryLiteral(_)
+        | TypedTerm::SubBinary(_) => Some(MatchContext::new(term)),
+        TypedTerm::MatchContext(match_context) => Some(*match_context.as_ref()),
+        _ => None,
+    }
+}
+
+/// Matches the segment described by `specifier` from the unmatched bits of `match_context`.
+/// `size` is the already decoded `Size` of the segment, if one was given.
+///
+/// Returns `Ok(None)` if the unmatched bits do not start with such a segment.
+pub fn match_segment(
+    process: &Process,
+    match_context: &mut MatchContext,
+    specifier: &BinaryEntrySpecifier,
+    size: Option<usize>,
+) -> AllocResult<Option<Term>> {
+    match specifier {
+        BinaryEntrySpecifier::Integer {
+            signed,
+            endianness,
+            unit,
+        } => {
+            let bit_len = size.unwrap_or(8) * (*unit as usize);
+
+            match match_integer(match_context, bit_len, *signed, *endianness) {
+                Some(big_int) => process.integer(big_int).map(Some),
+                None => Ok(None),
+            }
+        }
+        BinaryEntrySpecifier::Float { endianness, unit } => {
+            let bit_len = size.unwrap_or(64) * (*unit as usize);
+
+            match match_float(match_context, bit_len, *endianness) {
+                Some(f) => process.float(f).map(Some),
+                None => Ok(None),
+            }
+        }
+        BinaryEntrySpecifier::Bytes { unit } => {
+            let bit_len = match size {
+                Some(size) => size * (*unit as usize) * 8,
+                None => match_context.remaining_bit_len(),
+            };
+
+            if bit_len % 8 == 0 {
+                match_bitstring(process, match_context, bit_len)
+            } else {
+                Ok(None)
+            }
+        }
+        BinaryEntrySpecifier::Bits { unit } => {
+            let bit_len = match size {
+                Some(size) => size * (*unit as usize),
+                None => match_context.remaining_bit_len(),
+            };
+
+            match_bitstring(process, match_context, bit_len)
+        }
+        BinaryEntrySpecifier::Utf8 => match match_utf8(match_context) {
+            Some(c) => process.integer(c).map(Some),
+            None => Ok(None),
+        },
+        BinaryEntrySpecifier::Utf16 { endianness } => {
+            match match_utf16(match_context, *endianness) {
+                Some(c) => process.integer(c).map(Some),
+                None => Ok(None),
+            }
+        }
+        BinaryEntrySpecifier::Utf32 { endianness } => {
+            let mut attempt = *match_context;
+            let c = match_u32(&mut attempt, 32, *endianness).and_then(std::char::from_u32);
+
+            if c.is_some() {
+                *match_context = attempt;
+            }
+
+            match c {
+                Some(c) => process.integer(c).map(Some),
+                None => Ok(None),
+            }
+        }
+    }
+}
+
+/// The unmatched bits of `match_context` as a sub binary of the original binary.
+pub fn rest(process: &Process, match_context: &mut MatchContext) -> AllocResult<Term> {
+    let bit_len = match_context.remaining_bit_len();
+
+    match_bitstring(process, match_context, bit_len).map(Option::unwrap)
+}
+
+/// Matches `bit_len` bits as a sub binary, so that the bytes are not copied.
+fn match_bitstring(
+    process: &Process,
+    match_context: &mut MatchContext,
+    bit_len: usize,
+) -> AllocResult<Option<Term>> {
+    if match_context.remaining_bit_len() < bit_len {
+        return Ok(None);
+    }
+
+    let subbinary = SubBinary::from_match(match_context, bit_len);
+
+    process
+        .subbinary_from_original(
+            subbinary.original(),
+            subbinary.byte_offset(),
+            subbinary.bit_offset(),
+            subbinary.full_byte_len(),
+            subbinary.partial_byte_bit_len(),
+        )
+        .map(Some)
+}
+
+/// Matches `bit_len` bits, most significant bit first, as the low bits of a byte.
+fn match_bits(match_context: &mut MatchContext, bit_len: usize) -> u8 {
+    (0..bit_len).fold(0, |byte, _| {
+        (byte << 1) | match_context.match_bit().unwrap()
+    })
+}
+
+/// The inverse of `BitVec::push_integer`.
+fn match_integer(
+    match_context: &mut MatchContext,
+    bit_len: usize,
+    signed: bool,
+    endianness: Endianness,
+) -> Option<BigInt> {
+    match_integer_bytes(match_context, bit_len, signed, endianness)
+        .map(|bytes| BigInt::from_signed_bytes_le(&bytes))
+}
+
+/// Matches an unsigned integer of at most 32 bits.
+fn match_u32(
+    match_context: &mut MatchContext,
+    bit_len: usize,
+    endianness: Endianness,
+) -> Option<u32> {
+    match_integer_bytes(match_context, bit_len, false, endianness).map(|bytes| {
+        bytes
+            .iter()
+            .take(4)
+            .rev()
+            .fold(0, |acc, byte| (acc << 8) | (*byte as u32))
+    })
+}
+
+/// Matches `bit_len` bits as the two's complement little-endian bytes of an integer.
+fn match_integer_bytes(
+    match_context: &mut MatchContext,
+    bit_len: usize,
+    signed: bool,
+    endianness: Endianness,
+) -> Option<Vec<u8>> {
+    if match_context.remaining_bit_len() < bit_len {
+        return None;
+    }
+
+    let full_byte_len = bit_len / 8;
+    let partial_byte_bit_len = bit_len % 8;
+
+    let little = match endianness {
+        Endianness::Big => false,
+        Endianness::Little => true,
+        Endianness::Native => cfg!(target_endian = "little"),
+    };
+
+    let mut bytes = vec![0; full_byte_len];
+
+    if little {
+        for byte in bytes.iter_mut() {
+            *byte = match_bits(match_context, 8);
+        }
+
+        bytes.push(match_bits(match_context, partial_byte_bit_len));
+    } else {
+        let partial_byte = match_bits(match_context, partial_byte_bit_len);
+
+        for byte in bytes.iter_mut().rev() {
+            *byte = match_bits(match_context, 8);
+        }
+
+        bytes.push(partial_byte);
+    }
+
+    // Sign-extend from the most significant bit of the segment
+    let most_significant_index = bytes.len() - 1;
+    let negative = signed
+        && 0 < bit_len
+        && if partial_byte_bit_len == 0 {
+            bytes[most_significant_index - 1] & 0b1000_0000 != 0
+        } else {
+            (bytes[most_significant_index] >> (partial_byte_bit_len - 1)) & 1 == 1
+        };
+
+    if negative {
+        bytes[most_significant_index] |= 0xFF << partial_byte_bit_len;
+        bytes.push(0xFF);
+    } else {
+        bytes.push(0x00);
+    }
+
+    Some(bytes)
+}
+
+fn match_float(
+    match_context: &mut MatchContext,
+    bit_len: usize,
+    endianness: Endianness,
+) -> Option<f64> {
+    if !(bit_len == 32 || bit_len == 64) || match_context.remaining_bit_len() < bit_len {
+        return None;
+    }
+
+    let mut bytes = [0; 8];
+
+    for byte in bytes[..bit_len / 8].iter_mut() {
+        *byte = match_bits(match_context, 8);
+    }
+
+    let f = match bit_len {
+        32 => {
+            let mut float_bytes = [0; 4];
+            float_bytes.copy_from_slice(&bytes[..4]);
+
+            (match endianness {
+                Endianness::Big => f32::from_be_bytes(float_bytes),
+                Endianness::Little => f32::from_le_bytes(float_bytes),
+                Endianness::Native => f32::from_ne_bytes(float_bytes),
+            }) as f64
+        }
+        _ => match endianness {
+            Endianness::Big => f64::from_be_bytes(bytes),
+            Endianness::Little => f64::from_le_bytes(bytes),
+            Endianness::Native => f64::from_ne_bytes(bytes),
+        },
+    };
+
+    // Like BEAM, infinities and NaNs do not match
+    if f.is_finite() {
+        Some(f)
+    } else {
+        None
+    }
+}
+
+fn match_utf8(match_context: &mut MatchContext) -> Option<char> {
+    let mut attempt = *match_context;
+
+    if attempt.remaining_bit_len() < 8 {
+        return None;
+    }
+
+    let first = match_bits(&mut attempt, 8);
+    let len = match (!first).leading_zeros() {
+        0 => 1,
+        2 => 2,
+        3 => 3,
+        4 => 4,
+        _ => return None,
+    };
+
+    if attempt.remaining_bit_len() < (len - 1) * 8 {
+        return None;
+    }
+
+    let mut bytes = vec![first];
+
+    for _ in 1..len {
+        bytes.push(match_bits(&mut attempt, 8));
+    }
+
+    let c = std::str::from_utf8(&bytes).ok()?.chars().next()?;
+    *match_context = attempt;
+
+    Some(c)
+}
+
+fn match_utf16(match_context: &mut MatchContext, endianness: Endianness) -> Option<char> {
+    let mut attempt = *match_context;
+
+    let match_unit =
+        |attempt: &mut MatchContext| match_u32(attempt, 16, endianness).map(|unit| unit as u16);
+
+    let mut units = vec![match_unit(&mut attempt)?];
+
+    if (0xD800..0xDC00).contains(&units[0]) {
+        units.push(match_unit(&mut attempt)?);
+    }
+
+    let c = std::char::decode_utf16(units).next()?.ok()?;
+    *match_context = attempt;
+
+    Some(c)
+}
+
+/// Decodes the `Size` of a segment.  Returns `None` if it is not a non-negative integer.
+pub fn size(term: Term) -> Option<usize> {
+    term.try_into().ok()
+}
+
+fn signed_bytes_le(term: Term) -> Option<Vec<u8>> {
+    match term.decode().ok()? {
+        TypedTerm::SmallInteger(small_integer) => {
+            let i: i64 = small_integer.into();
+
+            Some(i.to_le_bytes().to_vec())
+        }
+        TypedTerm::BigInteger(big_integer) => Some(big_integer.as_ref().to_signed_bytes_le()),
+        _ => None,
+    }
+}
+
+fn float(term: Term) -> Option<f64> {
+    match term.decode().ok()? {
+        TypedTerm::SmallInteger(small_integer) => Some(small_integer.into()),
+        TypedTerm::BigInteger(big_integer) => Some(big_integer.into()),
+        TypedTerm::Float(float) => Some(float.into()),
+        _ => None,
+    }
+}
+
+fn code_point(term: Term) -> Option<char> {
+    match term.decode().ok()? {
+        TypedTerm::SmallInteger(small_integer) => {
+            let i: i64 = small_integer.into();
+
+            i.try_into().ok().and_then(std::char::from_u32)
+        }
+        _ => None,
+    }
+}
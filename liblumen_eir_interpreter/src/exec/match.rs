@@ -2,11 +2,11 @@ use std::sync::Arc;
 
 use libeir_ir::{BasicType, Block, MatchKind};
 
-use liblumen_alloc::erts::exception::SystemException;
+use liblumen_alloc::erts::exception::Exception;
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::{Encoded, ExactEq, TypedTerm};
 
-use super::{CallExecutor, OpResult};
+use super::{binary, CallExecutor, OpResult};
 use crate::module::ErlangFunction;
 
 pub fn match_op(
@@ -15,16 +15,13 @@ pub fn match_op(
     fun: &ErlangFunction,
     branches: &[MatchKind],
     block: Block,
-) -> std::result::Result<OpResult, SystemException> {
+) -> std::result::Result<OpResult, Exception> {
     let reads = fun.fun.block_reads(block);
 
     let branches_dests = reads[0];
 
-    let unpack_term = exec
-        .make_term(proc, fun, reads[1])
-        .unwrap()
-        .decode()
-        .unwrap();
+    let unpack_raw_term = exec.make_term(proc, fun, reads[1])?;
+    let unpack_term = unpack_raw_term.decode().unwrap();
 
     for (idx, kind) in branches.iter().enumerate() {
         let branch = fun.fun.value_list_get_n(branches_dests, idx).unwrap();
@@ -36,7 +33,7 @@ pub fn match_op(
             MatchKind::Value => {
                 assert!(branch_args_len == 1);
                 let arg = fun.fun.value_list_get_n(branch_args_val, 0).unwrap();
-                let rhs = exec.make_term(proc, fun, arg).unwrap();
+                let rhs = exec.make_term(proc, fun, arg)?;
 
                 if unpack_term.exact_eq(&rhs.decode().unwrap()) {
                     return exec.val_call(proc, fun, branch);
@@ -51,7 +48,7 @@ pub fn match_op(
             MatchKind::MapItem => {
                 assert!(branch_args_len == 1);
                 let arg = fun.fun.value_list_get_n(branch_args_val, 0).unwrap();
-                let key = exec.make_term(proc, fun, arg).unwrap();
+                let key = exec.make_term(proc, fun, arg)?;
 
                 match unpack_term {
                     TypedTerm::Map(map) => {
@@ -88,6 +85,33 @@ pub fn match_op(
                     _ => (),
                 }
             }
+            MatchKind::Binary(specifier) => {
+                let size = match branch_args_len {
+                    0 => None,
+                    1 => {
+                        let arg = fun.fun.value_list_get_n(branch_args_val, 0).unwrap();
+                        let size_term = exec.make_term(proc, fun, arg)?;
+
+                        match binary::size(size_term) {
+                            Some(size) => Some(size),
+                            None => continue,
+                        }
+                    }
+                    _ => unreachable!(),
+                };
+
+                if let Some(mut match_context) = binary::start_match(unpack_raw_term) {
+                    if let Some(value) =
+                        binary::match_segment(proc, &mut match_context, specifier, size)?
+                    {
+                        let rest = binary::rest(proc, &mut match_context)?;
+
+                        exec.next_args.push(value);
+                        exec.next_args.push(rest);
+                        return exec.val_call(proc, fun, branch);
+                    }
+                }
+            }
             MatchKind::Wildcard => {
                 assert!(branch_args_len == 0);
                 return exec.val_call(proc, fun, branch);
@@ -4,30 +4,28 @@ use std::sync::Arc;
 
 use hashbrown::HashMap;
 
-use cranelift_entity::EntityRef;
 use libeir_intern::Symbol;
 use libeir_ir::constant::{AtomicTerm, Const, ConstKind};
-use libeir_ir::{
-    BinOp, BinaryEntrySpecifier, Block, FunctionIndex, LogicOp, MapPutUpdate, OpKind, PrimOpKind,
-    Value, ValueKind,
-};
+use libeir_ir::{BinOp, Block, LogicOp, MapPutUpdate, OpKind, PrimOpKind, Value, ValueKind};
 
-use liblumen_alloc::atom;
 use liblumen_alloc::erts::exception::{Exception, RuntimeException, SystemException};
 use liblumen_alloc::erts::process::code;
-use liblumen_alloc::erts::process::gc::RootSet;
-use liblumen_alloc::erts::process::{Process, ProcessFlags};
+use liblumen_alloc::erts::process::gc::{GcError, RootSet};
+use liblumen_alloc::erts::process::{Process, ProcessFlags, ReceiveTimeout};
 use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, CloneToProcess};
 
-use crate::module::{ErlangFunction, NativeFunctionKind, ResolvedFunction};
+use lumen_runtime::otp::erlang;
+use lumen_runtime::time::{monotonic, Milliseconds};
+
+use crate::module::{ErlangFunction, LocalFunction, NativeFunctionKind, ResolvedFunction};
+use crate::qualified;
+use crate::trace::TraceEventKind;
 use crate::vm::VMState;
 
+mod binary;
 mod r#match;
 
-macro_rules! trace {
-    ($($t:tt)*) => (lumen_runtime::system::io::puts(&format_args!($($t)*).to_string()))
-}
-
 const VALUE_LIST_MARKER: &str = "eir_value_list_marker_df8gy43h";
 
 pub struct CallExecutor {
@@ -39,6 +37,8 @@ pub enum OpResult {
     Block(Block),
     Term(Term),
     TermYield(Term),
+    /// The process is exiting, so no more code runs.
+    Exit,
 }
 
 trait TermCollection {
@@ -87,7 +87,10 @@ where
 
 /// Will keep trying to execute the inner function and performing GC until
 /// we succeed without alloc error.
-fn try_gc<T, F, R>(proc: &Arc<Process>, terms: &mut T, fun: &mut F) -> R
+///
+/// Returns `None` without retrying if the process is killed for exceeding its max heap size, so
+/// that it runs no more Erlang code.
+fn try_gc<T, F, R>(proc: &Arc<Process>, terms: &mut T, fun: &mut F) -> Option<R>
 where
     T: TermCollection,
     F: FnMut(&mut T) -> Result<R, SystemException>,
@@ -95,8 +98,9 @@ where
     // Loop, keep trying the inner function until we succeed
     loop {
         match fun(terms) {
-            Ok(inner) => break inner,
+            Ok(inner) => break Some(inner),
             Err(SystemException::Alloc(_)) => {
+                let start = monotonic::time_since_start();
                 let mut heap = proc.acquire_heap();
 
                 let mut rootset = RootSet::new(&mut []);
@@ -105,11 +109,9 @@ where
                 // Terms are in root set
                 unsafe { terms.add(&mut rootset) };
 
-                lumen_runtime::system::io::puts(
-                    "=================================================== GC",
-                );
-                match heap.garbage_collect(proc, 0, rootset) {
-                    Ok(_) => (),
+                let within_max_heap_size = match heap.garbage_collect(proc, 0, rootset) {
+                    Ok(_) => true,
+                    Err(GcError::MaxHeapSizeExceeded) => false,
                     Err(_) => {
                         proc.set_flags(ProcessFlags::NeedFullSweep);
 
@@ -119,32 +121,62 @@ where
                         // Terms are in root set
                         unsafe { terms.add(&mut rootset) };
 
-                        lumen_runtime::system::io::puts(
-                            "=================================================== FULL GC",
-                        );
                         match heap.garbage_collect(proc, 0, rootset) {
-                            Ok(_) => (),
+                            Ok(_) => true,
+                            Err(GcError::MaxHeapSizeExceeded) => false,
                             Err(_) => panic!(),
                         }
                     }
+                };
+
+                drop(heap);
+                proc.add_gc_time(monotonic::time_since_start() - start);
+
+                if !within_max_heap_size {
+                    proc.exit(atom!("killed"));
+
+                    break None;
                 }
             }
             Err(fatal_err) => {
-                lumen_runtime::system::io::puts(&format!("FATAL_ERROR!\n\n{:?}", fatal_err));
+                eprintln!("FATAL_ERROR!\n\n{:?}", fatal_err);
                 abort();
             }
         }
     }
 }
 
+/// Converts the `after` timeout of a receive to a `ReceiveTimeout`, starting a
+/// timer if the timeout is finite and non-zero.  Returns `None` if `timeout` is neither
+/// `infinity` nor a non-negative integer.
+fn receive_timeout(proc: &Arc<Process>, timeout: Term) -> Option<ReceiveTimeout> {
+    if timeout == atom!("infinity") {
+        return Some(ReceiveTimeout::Infinity);
+    }
+
+    let milliseconds: Milliseconds = timeout.try_into().ok()?;
+
+    let receive_timeout = if milliseconds == 0 {
+        ReceiveTimeout::Immediate
+    } else {
+        let monotonic_time_milliseconds = monotonic::time_in_milliseconds() + milliseconds;
+        let timer_reference =
+            lumen_runtime::timer::start_receive_timeout(monotonic_time_milliseconds, proc);
+
+        ReceiveTimeout::Timer(timer_reference)
+    };
+
+    Some(receive_timeout)
+}
+
 /// Sets up the current stack frame of `proc` to call `closure` with `args`.
-fn call_closure(proc: &Arc<Process>, mut closure: Term, args: &mut [Term]) {
+pub(crate) fn call_closure(proc: &Arc<Process>, mut closure: Term, args: &mut [Term]) {
     try_gc(proc, &mut (&mut closure, args), &mut |(
         closure_term,
         args,
     )| {
         call_closure_inner(proc, **closure_term, closure_term.decode().unwrap(), args)
-    })
+    });
 }
 
 fn call_closure_inner(
@@ -189,7 +221,6 @@ impl CallExecutor {
         arity: usize,
         args: &mut [Term],
     ) {
-        trace!("======== RUN {} ========", proc.pid());
         let modules = vm.modules.read().unwrap();
 
         // Make sure no non-heap terms make it into the process
@@ -199,22 +230,65 @@ impl CallExecutor {
                 if arg.is_boxed() {
                     use liblumen_alloc::erts::process::alloc::Heap;
                     let ptr: *const Term = arg.dyn_cast();
-                    if !heap.is_owner(ptr) {
-                        println!("NON HEAP BOXED: {:?}", arg);
-                        panic!();
-                    }
+                    assert!(heap.is_owner(ptr), "Non-heap boxed argument: {:?}", arg);
                 }
             }
         }
 
-        match modules.lookup_function(module, function, arity) {
+        vm.tracer.read().unwrap().trace(
+            proc,
+            module,
+            function,
+            arity,
+            TraceEventKind::Call {
+                arguments: &args[2..],
+            },
+        );
+
+        let resolved = modules.lookup_function(module, function, arity);
+
+        match resolved {
             None => {
-                self.fun_not_found(proc, args[1], module, function, arity)
-                    .unwrap();
+                // Like BEAM, calls to undefined functions are passed to
+                // `ErrorHandler:undefined_function(Module, Function, Arguments)` when the
+                // process's error handler is loaded.
+                let error_handler = proc.error_handler();
+                let undefined_function = Atom::try_from_str("undefined_function").unwrap();
+
+                if error_handler != module
+                    && modules
+                        .lookup_function(error_handler, undefined_function, 3)
+                        .is_some()
+                {
+                    drop(modules);
+
+                    let arguments = proc.list_from_slice(&args[2..]).unwrap();
+                    let mut handler_args = [
+                        args[0],
+                        args[1],
+                        module.encode().unwrap(),
+                        function.encode().unwrap(),
+                        arguments,
+                    ];
+
+                    self.call(
+                        vm,
+                        proc,
+                        error_handler,
+                        undefined_function,
+                        3,
+                        &mut handler_args,
+                    );
+                } else {
+                    self.fun_not_found(proc, args[1], module, function, arity)
+                        .unwrap();
+                }
             }
             Some(ResolvedFunction::Native(native)) => {
                 assert!(arity + 2 == args.len());
-                self.run_native(vm, proc, native, args);
+                // Natives may load or purge modules, so they run without the registry lock
+                drop(modules);
+                self.run_native(vm, proc, module, function, arity, native, args);
             }
             Some(ResolvedFunction::Erlang(fun)) => {
                 let entry = fun.fun.block_entry();
@@ -223,22 +297,44 @@ impl CallExecutor {
         }
     }
 
-    /// Calls a block in the given MFA with an environment.
+    /// Calls the block of the fun with `index` in the given module version with an environment.
     pub fn call_block(
         &mut self,
         vm: &VMState,
         proc: &Arc<Process>,
+        closure: Term,
         module: Atom,
-        fun_idx: FunctionIndex,
+        unique: [u8; 16],
+        index: u32,
         args: &mut [Term],
-        block: Block,
         env: &mut [Term],
     ) {
-        trace!("======== RUN {} ========", proc.pid());
         let modules = vm.modules.read().unwrap();
-        match modules.lookup_function_idx(module, fun_idx) {
-            None => unreachable!(),
-            Some(fun) => {
+        match modules.lookup_closure(module, unique, index) {
+            // The version of the fun was purged
+            None => {
+                drop(modules);
+
+                let reason = proc.tuple_from_slice(&[atom!("badfun"), closure]).unwrap();
+                call_closure(proc, args[1], &mut [atom!("error"), reason, atom!("trace")]);
+            }
+            Some((fun, block)) => {
+                // Funs and local calls enter a function, while continuations resume one
+                if block == fun.fun.block_entry() {
+                    let ident = fun.fun.ident();
+                    let function = Atom::try_from_str(ident.name.as_str()).unwrap();
+
+                    vm.tracer.read().unwrap().trace(
+                        proc,
+                        module,
+                        function,
+                        ident.arity,
+                        TraceEventKind::Call {
+                            arguments: &args[2..],
+                        },
+                    );
+                }
+
                 let live = &fun.live.live_at(block);
                 assert_eq!(live.size(), env.len());
 
@@ -268,37 +364,62 @@ impl CallExecutor {
 
     fn run_native(
         &mut self,
-        _vm: &VMState,
+        vm: &VMState,
         proc: &Arc<Process>,
+        module: Atom,
+        function: Atom,
+        arity: usize,
         native: NativeFunctionKind,
         mut args: &mut [Term],
     ) {
         try_gc(proc, &mut args, &mut |args| match native {
             NativeFunctionKind::Simple(ptr) => match ptr(proc, &args[2..]) {
-                Ok(ret) => Ok(call_closure(proc, args[0], &mut [ret])),
-                Err(err) => match err {
-                    Exception::Runtime(RuntimeException::Throw(err)) => Ok(call_closure(
+                Ok(ret) => {
+                    vm.tracer.read().unwrap().trace(
                         proc,
-                        args[1],
-                        &mut [atom!("throw"), err.reason(), atom!("trace")],
-                    )),
-                    Exception::Runtime(RuntimeException::Exit(err)) => Ok(call_closure(
+                        module,
+                        function,
+                        arity,
+                        TraceEventKind::Return { value: ret },
+                    );
+
+                    Ok(call_closure(proc, args[0], &mut [ret]))
+                }
+                Err(err) => {
+                    let (class, reason) = match err {
+                        Exception::Runtime(RuntimeException::Throw(err)) => {
+                            (atom!("throw"), err.reason())
+                        }
+                        Exception::Runtime(RuntimeException::Exit(err)) => {
+                            (atom!("EXIT"), err.reason())
+                        }
+                        Exception::Runtime(RuntimeException::Error(err)) => {
+                            (atom!("error"), err.reason())
+                        }
+                        // Promote unknown runtime errors to SystemException
+                        Exception::Runtime(RuntimeException::Unknown(err)) => {
+                            return Err(err.into())
+                        }
+                        Exception::System(err) => return Err(err),
+                    };
+
+                    vm.tracer.read().unwrap().trace(
                         proc,
-                        args[1],
-                        &mut [atom!("EXIT"), err.reason(), atom!("trace")],
-                    )),
-                    Exception::Runtime(RuntimeException::Error(err)) => Ok(call_closure(
+                        module,
+                        function,
+                        arity,
+                        TraceEventKind::Exception { class, reason },
+                    );
+
+                    Ok(call_closure(
                         proc,
                         args[1],
-                        &mut [atom!("error"), err.reason(), atom!("trace")],
-                    )),
-                    // Promote unknown runtime errors to SystemException
-                    Exception::Runtime(RuntimeException::Unknown(err)) => return Err(err.into()),
-                    Exception::System(err) => return Err(err),
-                },
+                        &mut [class, reason, atom!("trace")],
+                    ))
+                }
             },
             NativeFunctionKind::Yielding(ptr) => ptr(proc, args),
-        })
+        });
     }
 
     fn run_erlang(
@@ -314,9 +435,14 @@ impl CallExecutor {
         let mut exec = self;
         // Outer loop for optimized execution within the current function
         'outer: loop {
+            // An exiting process, such as one killed for exceeding its max heap size or by an
+            // exit signal from a native function, runs no more Erlang code.
+            if proc.is_exiting() {
+                break;
+            }
+
             // Insert block argument into environment
             let block_arg_vals = fun.fun.block_args(block);
-            //trace!("{:?} {:?}", &block_arg_vals, &exec.next_args);
             assert!(
                 block_arg_vals.len() == exec.next_args.len(),
                 "{} == {}",
@@ -327,20 +453,83 @@ impl CallExecutor {
                 exec.binds.insert(*v, t.clone());
             }
 
-            match try_gc(proc, &mut exec, &mut |exec| {
+            let op_result = try_gc(proc, &mut exec, &mut |exec| {
                 exec.next_args.clear();
-                exec.run_erlang_op(vm, proc, fun, block)
-            }) {
+
+                match exec.run_erlang_op(vm, proc, fun, block) {
+                    Ok(op_result) => Ok(op_result),
+                    // Operands of the wrong type raise an error, such as `badarg`
+                    Err(Exception::Runtime(RuntimeException::Error(error))) => {
+                        exec.next_args.clear();
+
+                        Ok(exec.raise(proc, fun, error.reason()))
+                    }
+                    Err(Exception::Runtime(exception)) => {
+                        unreachable!("{:?} raised by an operation", exception)
+                    }
+                    Err(Exception::System(err)) => Err(err),
+                }
+            })
+            .unwrap_or(OpResult::Exit);
+
+            match op_result {
                 OpResult::Block(b) => {
                     block = b;
                     continue;
                 }
-                OpResult::Term(t) => break call_closure(proc, t, &mut exec.next_args),
-                OpResult::TermYield(t) => break call_closure(proc, t, &mut exec.next_args),
+                OpResult::Term(_) | OpResult::TermYield(_) if proc.is_exiting() => break,
+                OpResult::Term(t) | OpResult::TermYield(t) => {
+                    exec.trace_continuation(vm, proc, fun, t);
+
+                    break call_closure(proc, t, &mut exec.next_args);
+                }
+                OpResult::Exit => break,
             }
         }
     }
 
+    /// Traces the return or exception of `fun` if `continuation` is the return or throw
+    /// continuation `fun` was called with.
+    fn trace_continuation(
+        &self,
+        vm: &VMState,
+        proc: &Arc<Process>,
+        fun: &ErlangFunction,
+        continuation: Term,
+    ) {
+        let tracer = vm.tracer.read().unwrap();
+
+        if !tracer.is_enabled() {
+            return;
+        }
+
+        let entry_args = fun.fun.block_args(fun.fun.block_entry());
+        let is_continuation = |index: usize| {
+            self.binds
+                .get(&entry_args[index])
+                .map_or(false, |bound| *bound == continuation)
+        };
+
+        let kind = if is_continuation(0) {
+            TraceEventKind::Return {
+                value: self.next_args[0],
+            }
+        } else if is_continuation(1) {
+            TraceEventKind::Exception {
+                class: self.next_args[0],
+                reason: self.next_args[1],
+            }
+        } else {
+            return;
+        };
+
+        let ident = fun.fun.ident();
+        let module = Atom::try_from_str(ident.module.as_str()).unwrap();
+        let function = Atom::try_from_str(ident.name.as_str()).unwrap();
+
+        tracer.trace(proc, module, function, ident.arity, kind);
+    }
+
     fn make_const_term(
         &self,
         proc: &Arc<Process>,
@@ -350,6 +539,8 @@ impl CallExecutor {
         let res = match fun.fun.cons().const_kind(const_val) {
             ConstKind::Atomic(AtomicTerm::Atom(atom)) => Ok(Atom::str_to_term(&atom.0.as_str())),
             ConstKind::Atomic(AtomicTerm::Int(int)) => Ok(proc.integer(int.0)?),
+            ConstKind::Atomic(AtomicTerm::BigInt(big_int)) => Ok(proc.integer(big_int.0.clone())?),
+            ConstKind::Atomic(AtomicTerm::Float(float)) => Ok(proc.float(float.0)?),
             ConstKind::Atomic(AtomicTerm::Binary(bin)) => Ok(proc.binary_from_bytes(&bin.0)?),
             ConstKind::Tuple { entries } => {
                 let vec: Result<Vec<_>, _> = entries
@@ -384,7 +575,6 @@ impl CallExecutor {
 
                 Ok(proc.map_from_hash_map(map)?)
             }
-            kind => unimplemented!("{:?}", kind),
         };
         res
     }
@@ -401,39 +591,50 @@ impl CallExecutor {
         let mut env = Vec::new();
         for v in live.iter() {
             assert!(fun.fun.value_argument(v).is_some());
-            env.push(self.make_term(proc, fun, v)?);
+            env.push(self.binds[&v]);
         }
 
         let module = Atom::try_from_str(fun.fun.ident().module.as_str()).unwrap();
         let arity = fun.fun.ident().arity as u8;
+        let index = fun.closure_indexes[&(fun.index, block)];
+        let old_unique = fun.old_unique(block);
 
-        let closure = proc.anonymous_closure_with_env_from_slice(
-            module,
-            // TODO generate `index` scoped to `module`
-            block.as_u32(),
-            // TODO calculate `old_unique` from `code`
-            fun.index.index() as u32,
-            // TODO calculate `unique` from `code`
-            Default::default(),
-            arity,
-            Some(crate::code::interpreter_closure_code),
-            proc.pid().into(),
-            &env,
-        )?;
+        anonymous_closure(proc, module, index, old_unique, fun.unique, arity, &env)
+    }
+
+    /// A fun that runs `local` in the same module version as `fun`, so that local calls stay in
+    /// the version of the caller.
+    fn make_local_closure(
+        &self,
+        proc: &Arc<Process>,
+        fun: &ErlangFunction,
+        local: &LocalFunction,
+    ) -> Result<Term, SystemException> {
+        let module = Atom::try_from_str(fun.fun.ident().module.as_str()).unwrap();
 
-        Ok(closure)
+        anonymous_closure(
+            proc,
+            module,
+            local.closure_index,
+            local.old_unique,
+            fun.unique,
+            local.arity,
+            &[],
+        )
     }
 
+    /// Raises `badarg` for operands of the wrong type, such as a non-boolean operand of a
+    /// `LogicOp`.
     fn make_term(
         &self,
         proc: &Arc<Process>,
         fun: &ErlangFunction,
         value: Value,
-    ) -> Result<Term, SystemException> {
+    ) -> Result<Term, Exception> {
         match fun.fun.value_kind(value) {
-            ValueKind::Block(block) => self.make_closure(proc, fun, block),
+            ValueKind::Block(block) => Ok(self.make_closure(proc, fun, block)?),
             ValueKind::Argument(_, _) => Ok(self.binds[&value]),
-            ValueKind::Const(cons) => self.make_const_term(proc, fun, cons),
+            ValueKind::Const(cons) => Ok(self.make_const_term(proc, fun, cons)?),
             ValueKind::PrimOp(prim) => {
                 let reads = fun.fun.primop_reads(prim);
                 match fun.fun.primop_kind(prim) {
@@ -465,8 +666,8 @@ impl CallExecutor {
                     PrimOpKind::LogicOp(LogicOp::And) => {
                         let mut acc = true;
                         for read in reads.iter() {
-                            let term = self.make_term(proc, fun, *read).unwrap();
-                            let res: bool = term.try_into().ok().unwrap();
+                            let term = self.make_term(proc, fun, *read)?;
+                            let res: bool = term.try_into()?;
                             acc = acc & res;
                         }
                         Ok(acc.into())
@@ -474,16 +675,53 @@ impl CallExecutor {
                     PrimOpKind::LogicOp(LogicOp::Or) => {
                         let mut acc = false;
                         for read in reads.iter() {
-                            let term = self.make_term(proc, fun, *read).unwrap();
-                            let res: bool = term.try_into().ok().unwrap();
+                            let term = self.make_term(proc, fun, *read)?;
+                            let res: bool = term.try_into()?;
                             acc = acc | res;
                         }
                         Ok(acc.into())
                     }
-                    PrimOpKind::BinOp(BinOp::Equal) => {
-                        let lhs: Atom = self.make_term(proc, fun, reads[0])?.try_into().unwrap();
-                        let rhs: Atom = self.make_term(proc, fun, reads[1])?.try_into().unwrap();
-                        Ok((lhs == rhs).into())
+                    PrimOpKind::LogicOp(LogicOp::Eq) => {
+                        let mut acc = None;
+                        let mut all_equal = true;
+                        for read in reads.iter() {
+                            let term = self.make_term(proc, fun, *read)?;
+                            let res: bool = term.try_into()?;
+                            all_equal = all_equal && acc.map_or(true, |acc| acc == res);
+                            acc = Some(res);
+                        }
+                        Ok(all_equal.into())
+                    }
+                    PrimOpKind::LogicOp(LogicOp::Xor) => {
+                        let mut acc = false;
+                        for read in reads.iter() {
+                            let term = self.make_term(proc, fun, *read)?;
+                            let res: bool = term.try_into()?;
+                            acc = acc ^ res;
+                        }
+                        Ok(acc.into())
+                    }
+                    PrimOpKind::BinOp(op) => {
+                        assert!(reads.len() == 2);
+                        let lhs = self.make_term(proc, fun, reads[0])?;
+                        let rhs = self.make_term(proc, fun, reads[1])?;
+                        let res = match op {
+                            BinOp::Equal => erlang::are_equal_after_conversion_2::native(lhs, rhs),
+                            BinOp::NotEqual => {
+                                erlang::are_not_equal_after_conversion_2::native(lhs, rhs)
+                            }
+                            BinOp::LessEqual => erlang::is_equal_or_less_than_2::native(lhs, rhs),
+                            BinOp::Less => erlang::is_less_than_2::native(lhs, rhs),
+                            BinOp::GreaterEqual => {
+                                erlang::is_greater_than_or_equal_2::native(lhs, rhs)
+                            }
+                            BinOp::Greater => erlang::is_greater_than_2::native(lhs, rhs),
+                            BinOp::ExactEqual => erlang::are_exactly_equal_2::native(lhs, rhs),
+                            BinOp::ExactNotEqual => {
+                                erlang::are_exactly_not_equal_2::native(lhs, rhs)
+                            }
+                        };
+                        Ok(res)
                     }
                     PrimOpKind::CaptureFunction => {
                         let module: Atom = self.make_term(proc, fun, reads[0])?.try_into().unwrap();
@@ -491,14 +729,27 @@ impl CallExecutor {
                             self.make_term(proc, fun, reads[1])?.try_into().unwrap();
                         let arity: u8 = self.make_term(proc, fun, reads[2])?.try_into().unwrap();
 
+                        // Local calls stay in the version of the caller.  Fully-qualified calls of
+                        // the module's own functions are marked by `mark_qualified_calls`, so
+                        // they don't match the module.
+                        if let ValueKind::Const(_) = fun.fun.value_kind(reads[0]) {
+                            let own_module =
+                                Atom::try_from_str(fun.fun.ident().module.as_str()).unwrap();
+
+                            if module == own_module {
+                                if let Some(local) = fun.locals.get(&(function, arity as usize)) {
+                                    return Ok(self.make_local_closure(proc, fun, local)?);
+                                }
+                            }
+                        }
+
                         Ok(proc.export_closure(
-                            module,
+                            qualified::unmarked(module),
                             function,
                             arity,
                             Some(crate::code::interpreter_mfa_code),
                         )?)
                     }
-                    kind => unimplemented!("{:?}", kind),
                 }
             }
         }
@@ -509,7 +760,7 @@ impl CallExecutor {
         proc: &Arc<Process>,
         fun: &ErlangFunction,
         value: Value,
-    ) -> Result<OpResult, SystemException> {
+    ) -> Result<OpResult, Exception> {
         if let ValueKind::Block(block) = fun.fun.value_kind(value) {
             Ok(OpResult::Block(block))
         } else {
@@ -518,17 +769,50 @@ impl CallExecutor {
         }
     }
 
+    /// Calls the failure continuation `value` with an `error` of `reason`.
+    fn val_call_error(
+        &mut self,
+        proc: &Arc<Process>,
+        fun: &ErlangFunction,
+        value: Value,
+        reason: Term,
+    ) -> Result<OpResult, Exception> {
+        self.next_args.push(atom!("error"));
+        self.next_args.push(reason);
+        self.next_args.push(atom!("trace"));
+        self.val_call(proc, fun, value)
+    }
+
+    /// Raises an error with `reason` through the throw continuation `fun` was called with.  If
+    /// the continuation is no longer live, nothing can catch the error, so the process exits.
+    fn raise(&mut self, proc: &Arc<Process>, fun: &ErlangFunction, reason: Term) -> OpResult {
+        let entry_args = fun.fun.block_args(fun.fun.block_entry());
+
+        match self.binds.get(&entry_args[1]).copied() {
+            Some(throw_continuation) => {
+                self.next_args.push(atom!("error"));
+                self.next_args.push(reason);
+                self.next_args.push(atom!("trace"));
+
+                OpResult::Term(throw_continuation)
+            }
+            None => {
+                proc.exception(liblumen_alloc::error!(reason));
+
+                OpResult::Exit
+            }
+        }
+    }
+
     fn run_erlang_op(
         &mut self,
         _vm: &VMState,
         proc: &Arc<Process>,
         fun: &ErlangFunction,
         block: Block,
-    ) -> Result<OpResult, SystemException> {
+    ) -> Result<OpResult, Exception> {
         let reads = fun.fun.block_reads(block);
         let kind = fun.fun.block_kind(block).unwrap();
-        trace!("OP: {:?} {}", kind, block);
-        println!("{:?}", reads);
 
         proc.reduce();
 
@@ -587,7 +871,7 @@ impl CallExecutor {
             }
             OpKind::IfBool => {
                 let call_n = if reads.len() == 4 {
-                    let bool_term = self.make_term(proc, fun, reads[3]).unwrap();
+                    let bool_term = self.make_term(proc, fun, reads[3])?;
                     let b: std::result::Result<bool, _> = bool_term.try_into();
                     match b {
                         Ok(true) => 0,
@@ -595,7 +879,7 @@ impl CallExecutor {
                         Err(_) => 2,
                     }
                 } else if reads.len() == 3 {
-                    let bool_term = self.make_term(proc, fun, reads[2]).unwrap();
+                    let bool_term = self.make_term(proc, fun, reads[2])?;
                     let b: std::result::Result<bool, _> = bool_term.try_into();
                     match b {
                         Ok(true) => 0,
@@ -610,8 +894,15 @@ impl CallExecutor {
             }
             OpKind::Match { branches } => self::r#match::match_op(self, proc, fun, branches, block),
             OpKind::MapPut { action } => {
-                let map_term: Boxed<Map> = self.make_term(proc, fun, reads[2])?.try_into().unwrap();
-                let hashmap_ref: &HashMap<Term, Term> = map_term.as_ref().as_ref();
+                let map_term = self.make_term(proc, fun, reads[2])?;
+                let map: Boxed<Map> = match map_term.try_into() {
+                    Ok(map) => map,
+                    Err(_) => {
+                        let reason = proc.tuple_from_slice(&[atom!("badmap"), map_term])?;
+                        return self.val_call_error(proc, fun, reads[1], reason);
+                    }
+                };
+                let hashmap_ref: &HashMap<Term, Term> = map.as_ref().as_ref();
                 let mut hashmap = hashmap_ref.clone();
 
                 let mut idx = 3;
@@ -624,12 +915,13 @@ impl CallExecutor {
                         MapPutUpdate::Put => {
                             hashmap.insert(key, val);
                         }
-                        MapPutUpdate::Update => {
-                            if hashmap.contains_key(&key) {
-                                unimplemented!()
+                        MapPutUpdate::Update => match hashmap.get_mut(&key) {
+                            Some(existing) => *existing = val,
+                            None => {
+                                let reason = proc.tuple_from_slice(&[atom!("badkey"), key])?;
+                                return self.val_call_error(proc, fun, reads[1], reason);
                             }
-                            hashmap.insert(key, val);
-                        }
+                        },
                     }
                 }
 
@@ -639,11 +931,13 @@ impl CallExecutor {
             OpKind::Intrinsic(name) if *name == Symbol::intern("receive_start") => {
                 assert!(reads.len() == 2);
 
-                let timeout = self.make_term(proc, fun, reads[1])?;
-                // Only infinity supported
-                assert!(timeout == Atom::str_to_term("infinity"));
+                let timeout_term = self.make_term(proc, fun, reads[1])?;
+                let timeout = match receive_timeout(proc, timeout_term) {
+                    Some(timeout) => timeout,
+                    None => return Ok(self.raise(proc, fun, atom!("timeout_value"))),
+                };
 
-                proc.mailbox.lock().borrow_mut().recv_start();
+                proc.mailbox.lock().borrow_mut().recv_start(timeout);
 
                 self.next_args.push(Term::NIL);
                 self.val_call(proc, fun, reads[0])
@@ -663,6 +957,15 @@ impl CallExecutor {
 
                     self.next_args.push(msg_term);
                     self.val_call(proc, fun, reads[1])
+                } else if mailbox.recv_timed_out() {
+                    // No message matched before the timeout, so run the
+                    // `after` clause.
+                    mailbox.recv_after();
+
+                    std::mem::drop(mailbox);
+                    std::mem::drop(mailbox_lock);
+
+                    self.val_call(proc, fun, reads[0])
                 } else {
                     // If there are no messages, schedule a call
                     // to the current block for later.
@@ -674,43 +977,90 @@ impl CallExecutor {
             OpKind::Intrinsic(name) if *name == Symbol::intern("receive_done") => {
                 assert!(reads.len() >= 1);
 
-                let mailbox_lock = proc.mailbox.lock();
-                let mut mailbox = mailbox_lock.borrow_mut();
+                let off_heap = proc.mailbox.lock().borrow().recv_last_off_heap();
 
-                if mailbox.recv_last_off_heap() {
-                    // Copy to process heap
-                    unimplemented!()
-                } else {
-                    for n in 0..(reads.len() - 1) {
-                        let term = self.make_term(proc, fun, reads[n + 1]).unwrap();
-                        self.next_args.push(term);
-                    }
+                for n in 0..(reads.len() - 1) {
+                    let term = self.make_term(proc, fun, reads[n + 1])?;
+
+                    // Terms bound from a message in a heap fragment are copied to the process
+                    // heap.  Other bindings into the fragment stay valid, as the fragment is
+                    // attached to the process when the message is removed and only freed by the
+                    // next garbage collection.  The mailbox isn't locked while copying, as senders
+                    // lock the mailbox while holding the heap.
+                    let term = if off_heap {
+                        term.clone_to_heap(&mut proc.acquire_heap())?
+                    } else {
+                        term
+                    };
+
+                    self.next_args.push(term);
                 }
 
-                mailbox.recv_finish(proc);
+                let option_timer_reference = proc.mailbox.lock().borrow_mut().recv_finish(proc);
+
+                if let Some(timer_reference) = option_timer_reference {
+                    lumen_runtime::timer::cancel(&timer_reference);
+                }
 
                 self.val_call(proc, fun, reads[0])
             }
-            OpKind::BinaryPush {
-                specifier: BinaryEntrySpecifier::Bytes { .. },
-            } => {
-                assert!(reads.len() == 4);
+            OpKind::BinaryPush { specifier } => {
+                assert!(reads.len() == 4 || reads.len() == 5);
                 let head = self.make_term(proc, fun, reads[2])?;
                 let tail = self.make_term(proc, fun, reads[3])?;
 
-                let mut head_bin: Vec<u8> = head.try_into().unwrap();
-                let tail_bin: Vec<u8> = tail.try_into().unwrap();
-                head_bin.extend(tail_bin.iter());
+                let size = if reads.len() == 5 {
+                    let size_term = self.make_term(proc, fun, reads[4])?;
+                    match self::binary::size(size_term) {
+                        Some(size) => Some(size),
+                        None => return self.val_call_error(proc, fun, reads[1], atom!("badarg")),
+                    }
+                } else {
+                    None
+                };
 
-                self.next_args.push(proc.binary_from_bytes(&head_bin)?);
-                self.val_call(proc, fun, reads[0])
-            }
-            OpKind::Unreachable => {
-                println!("==== Reached OpKind::Unreachable! ====");
-                println!("Fun: {} Block: {}", fun.fun.ident(), block);
-                unreachable!();
+                let mut bit_vec = self::binary::BitVec::from_bitstring(head).unwrap();
+                match bit_vec.push_segment(specifier, tail, size) {
+                    Some(()) => {
+                        self.next_args.push(bit_vec.to_term(proc)?);
+                        self.val_call(proc, fun, reads[0])
+                    }
+                    None => self.val_call_error(proc, fun, reads[1], atom!("badarg")),
+                }
             }
+            OpKind::Unreachable => unreachable!("Fun: {} Block: {}", fun.fun.ident(), block),
             kind => unimplemented!("{:?}", kind),
         }
     }
 }
+
+fn anonymous_closure(
+    proc: &Arc<Process>,
+    module: Atom,
+    index: u32,
+    old_unique: u32,
+    unique: [u8; 16],
+    arity: u8,
+    env: &[Term],
+) -> Result<Term, SystemException> {
+    let code = crate::code::interpreter_closure_code;
+
+    // Funs decoded by `binary_to_term` find their code by their identity
+    if lumen_runtime::code::anonymous::get(&module, &index, &old_unique, &unique, &arity).is_none()
+    {
+        lumen_runtime::code::anonymous::insert(module, index, old_unique, unique, arity, code);
+    }
+
+    let closure = proc.anonymous_closure_with_env_from_slice(
+        module,
+        index,
+        old_unique,
+        unique,
+        arity,
+        Some(code),
+        proc.pid().into(),
+        env,
+    )?;
+
+    Ok(closure)
+}
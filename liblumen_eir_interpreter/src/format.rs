@@ -0,0 +1,189 @@
+//! Formats terms in Erlang syntax, like `io:format("~w", [Term])`, so that the output of the CLI
+//! can be read back as Erlang terms.
+
+use std::fmt::{self, Display, Write};
+
+use liblumen_alloc::erts::term::prelude::*;
+
+/// Displays the wrapped term in Erlang syntax.
+pub struct ErlangFormat(pub Term);
+
+impl Display for ErlangFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.decode().unwrap() {
+            TypedTerm::Nil => f.write_str("[]"),
+            TypedTerm::Atom(atom) => write_atom(f, atom.name()),
+            TypedTerm::SmallInteger(small_integer) => write!(f, "{}", small_integer),
+            TypedTerm::BigInteger(big_integer) => write!(f, "{}", big_integer.as_ref()),
+            TypedTerm::Float(float) => write_float(f, float.into()),
+            TypedTerm::Tuple(tuple) => {
+                f.write_char('{')?;
+                write_elements(f, tuple.iter().copied())?;
+                f.write_char('}')
+            }
+            TypedTerm::List(cons) => {
+                f.write_char('[')?;
+
+                for (index, result) in cons.into_iter().enumerate() {
+                    match result {
+                        Ok(element) => {
+                            if 0 < index {
+                                f.write_char(',')?;
+                            }
+
+                            write!(f, "{}", ErlangFormat(element))?;
+                        }
+                        Err(ImproperList { tail }) => write!(f, "|{}", ErlangFormat(tail))?,
+                    }
+                }
+
+                f.write_char(']')
+            }
+            TypedTerm::Map(map) => {
+                let mut entries: Vec<(Term, Term)> =
+                    map.iter().map(|(key, value)| (*key, *value)).collect();
+                entries.sort_unstable_by(|(key1, _), (key2, _)| key1.cmp(key2));
+
+                f.write_str("#{")?;
+
+                for (index, (key, value)) in entries.into_iter().enumerate() {
+                    if 0 < index {
+                        f.write_char(',')?;
+                    }
+
+                    write!(f, "{} => {}", ErlangFormat(key), ErlangFormat(value))?;
+                }
+
+                f.write_char('}')
+            }
+            TypedTerm::HeapBinary(heap_binary) => write_bitstring(f, heap_binary.as_bytes(), &[]),
+            TypedTerm::ProcBin(process_binary) => {
+                write_bitstring(f, process_binary.as_bytes(), &[])
+            }
+            TypedTerm::BinaryLiteral(binary_literal) => {
+                write_bitstring(f, binary_literal.as_bytes(), &[])
+            }
+            TypedTerm::SubBinary(subbinary) => {
+                let bytes: Vec<u8> = subbinary.full_byte_iter().collect();
+                let bits: Vec<u8> = if subbinary.is_binary() {
+                    Vec::new()
+                } else {
+                    subbinary.partial_byte_bit_iter().collect()
+                };
+
+                write_bitstring(f, &bytes, &bits)
+            }
+            TypedTerm::MatchContext(match_context) => {
+                let bytes: Vec<u8> = match_context.full_byte_iter().collect();
+                let bits: Vec<u8> = match_context.partial_byte_bit_iter().collect();
+
+                write_bitstring(f, &bytes, &bits)
+            }
+            TypedTerm::Pid(pid) => write!(f, "<0.{}.{}>", pid.number(), pid.serial()),
+            TypedTerm::Closure(closure) => {
+                let module_function_arity = closure.module_function_arity();
+
+                f.write_str("#Fun<")?;
+                write_atom(f, module_function_arity.module.name())?;
+                f.write_char('.')?;
+                write_atom(f, module_function_arity.function.name())?;
+                write!(f, ".{}>", module_function_arity.arity)
+            }
+            // Not readable in Erlang either
+            TypedTerm::Port(port) => write!(f, "{}", port),
+            TypedTerm::Reference(reference) => write!(f, "{}", reference.as_ref()),
+            TypedTerm::ExternalPid(external_pid) => write!(f, "{}", external_pid.as_ref()),
+            TypedTerm::ExternalPort(external_port) => write!(f, "{}", external_port.as_ref()),
+            TypedTerm::ExternalReference(external_reference) => {
+                write!(f, "{}", external_reference.as_ref())
+            }
+            TypedTerm::ResourceReference(resource) => write!(f, "{}", resource.as_ref()),
+        }
+    }
+}
+
+const RESERVED_WORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "end", "fun", "if", "let", "not", "of", "or", "orelse", "receive",
+    "rem", "try", "when", "xor",
+];
+
+fn write_atom(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+    let mut chars = name.chars();
+    let unquoted = match chars.next() {
+        Some(first) => {
+            first.is_ascii_lowercase()
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+                && !RESERVED_WORDS.contains(&name)
+        }
+        None => false,
+    };
+
+    if unquoted {
+        f.write_str(name)
+    } else {
+        f.write_char('\'')?;
+
+        for c in name.chars() {
+            match c {
+                '\'' => f.write_str("\\'")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\t' => f.write_str("\\t")?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        f.write_char('\'')
+    }
+}
+
+fn write_float(f: &mut fmt::Formatter, float: f64) -> fmt::Result {
+    // Rust's shortest round-trip representation, but Erlang requires a fraction before any
+    // exponent: `1e300` is `1.0e300`.
+    let string = format!("{:?}", float);
+
+    match string.find('e') {
+        Some(index) if !string[..index].contains('.') => {
+            write!(f, "{}.0{}", &string[..index], &string[index..])
+        }
+        _ => f.write_str(&string),
+    }
+}
+
+fn write_elements<I: Iterator<Item = Term>>(f: &mut fmt::Formatter, elements: I) -> fmt::Result {
+    for (index, element) in elements.enumerate() {
+        if 0 < index {
+            f.write_char(',')?;
+        }
+
+        write!(f, "{}", ErlangFormat(element))?;
+    }
+
+    Ok(())
+}
+
+/// Writes `bytes` followed by the `bits` of the partial byte, each `0` or `1`.
+fn write_bitstring(f: &mut fmt::Formatter, bytes: &[u8], bits: &[u8]) -> fmt::Result {
+    f.write_str("<<")?;
+
+    for (index, byte) in bytes.iter().enumerate() {
+        if 0 < index {
+            f.write_char(',')?;
+        }
+
+        write!(f, "{}", byte)?;
+    }
+
+    if !bits.is_empty() {
+        if !bytes.is_empty() {
+            f.write_char(',')?;
+        }
+
+        let partial_byte = bits.iter().fold(0, |acc, bit| (acc << 1) | bit);
+
+        write!(f, "{}:{}", partial_byte, bits.len())?;
+    }
+
+    f.write_str(">>")
+}
@@ -1,11 +1,17 @@
 #![deny(warnings)]
 
+pub mod beam;
 pub mod code;
 mod exec;
+pub mod format;
+pub mod literal;
 mod module;
 pub use module::NativeModule;
 pub mod call_result;
 mod native;
+pub mod qualified;
+pub mod shell;
+pub mod trace;
 mod vm;
 
 #[cfg(test)]
@@ -0,0 +1,340 @@
+//! Parses Erlang term literals, like `erl_parse:parse_term/1`, so that the CLI can take terms as
+//! arguments without evaluating them.  It reads everything that `format` writes except funs,
+//! pids, ports and references, which have no literal syntax.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use hashbrown::HashMap;
+use num_bigint::BigInt;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+/// Parses the comma-separated term literals in `source` onto the heap of `process`.
+pub fn parse_terms(process: &Process, source: &str) -> Result<Vec<Term>, String> {
+    let mut parser = LiteralParser {
+        process,
+        chars: source.chars().peekable(),
+    };
+
+    parser.skip_whitespace();
+
+    let terms = if parser.chars.peek().is_none() {
+        Vec::new()
+    } else {
+        parser.terms_until(None)?
+    };
+
+    match parser.chars.next() {
+        None => Ok(terms),
+        Some(c) => Err(format!("unexpected {:?}", c)),
+    }
+}
+
+struct LiteralParser<'a> {
+    process: &'a Process,
+    chars: Peekable<Chars<'a>>,
+}
+
+impl LiteralParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().map_or(false, |c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected {:?}, found {:?}", expected, c)),
+            None => Err(format!("expected {:?}, found end of input", expected)),
+        }
+    }
+
+    /// Whether the next character is `c`, consuming it if it is.
+    fn accept(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+
+        if self.chars.peek() == Some(&c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Comma-separated terms up to, but not including, `close`, or the end of the input.
+    fn terms_until(&mut self, close: Option<char>) -> Result<Vec<Term>, String> {
+        let mut terms = Vec::new();
+
+        self.skip_whitespace();
+        if close.is_some() && self.chars.peek() == close.as_ref() {
+            return Ok(terms);
+        }
+
+        loop {
+            terms.push(self.term()?);
+
+            if !self.accept(',') {
+                self.skip_whitespace();
+                return Ok(terms);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        self.skip_whitespace();
+
+        let term = match self.chars.peek().copied() {
+            Some('[') => {
+                self.chars.next();
+                self.list()?
+            }
+            Some('{') => {
+                self.chars.next();
+                let elements = self.terms_until(Some('}'))?;
+                self.expect('}')?;
+                self.process.tuple_from_slice(&elements)
+            }
+            Some('#') => {
+                self.chars.next();
+                self.expect('{')?;
+                self.map()?
+            }
+            Some('<') => {
+                self.chars.next();
+                self.expect('<')?;
+                self.binary()?
+            }
+            Some('"') => {
+                self.chars.next();
+                let string = self.quoted('"')?;
+                self.process.charlist_from_str(&string)
+            }
+            Some('\'') => {
+                self.chars.next();
+                let name = self.quoted('\'')?;
+                return atom(&name);
+            }
+            Some('$') => {
+                self.chars.next();
+                let c = self.character()?;
+                self.process.integer(c)
+            }
+            Some(c) if c == '-' || c == '+' || c.is_ascii_digit() => return self.number(),
+            Some(c) if c.is_ascii_lowercase() => {
+                let name = self.name();
+                return atom(&name);
+            }
+            Some(c) => return Err(format!("{:?} does not start a term literal", c)),
+            None => return Err("expected a term, found end of input".to_string()),
+        };
+
+        term.map_err(|_| "term does not fit on the heap".to_string())
+    }
+
+    fn list(&mut self) -> Result<AllocResult<Term>, String> {
+        let elements = self.terms_until(Some(']'))?;
+
+        let tail = if !elements.is_empty() && self.accept('|') {
+            Some(self.term()?)
+        } else {
+            None
+        };
+
+        self.expect(']')?;
+
+        Ok(match tail {
+            Some(tail) => self.process.improper_list_from_slice(&elements, tail),
+            None => self.process.list_from_slice(&elements),
+        })
+    }
+
+    fn map(&mut self) -> Result<AllocResult<Term>, String> {
+        let mut hash_map = HashMap::new();
+
+        if !self.accept('}') {
+            loop {
+                let key = self.term()?;
+                self.expect('=')?;
+                self.expect('>')?;
+                let value = self.term()?;
+                hash_map.insert(key, value);
+
+                if !self.accept(',') {
+                    break;
+                }
+            }
+
+            self.expect('}')?;
+        }
+
+        Ok(self.process.map_from_hash_map(hash_map))
+    }
+
+    /// Binaries of byte and string segments, such as `<<1,2>>` and `<<"abc">>`.
+    fn binary(&mut self) -> Result<AllocResult<Term>, String> {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        if !self.accept('>') {
+            loop {
+                self.skip_whitespace();
+
+                if self.accept('"') {
+                    let string = self.quoted('"')?;
+
+                    for c in string.chars() {
+                        bytes.push(c as u32 as u8);
+                    }
+                } else {
+                    let digits = self.name();
+
+                    match digits.parse::<u8>() {
+                        Ok(byte) => bytes.push(byte),
+                        Err(_) => return Err(format!("{:?} is not a byte", digits)),
+                    }
+                }
+
+                if !self.accept(',') {
+                    break;
+                }
+            }
+
+            self.expect('>')?;
+        }
+
+        self.expect('>')?;
+
+        Ok(self.process.binary_from_bytes(&bytes))
+    }
+
+    fn number(&mut self) -> Result<Term, String> {
+        let mut text = String::new();
+
+        if let Some(sign) = self.chars.next_if_sign() {
+            text.push(sign);
+        }
+
+        text.push_str(&self.name());
+
+        // The fraction and exponent of a float
+        if self.chars.peek() == Some(&'.') {
+            text.push('.');
+            self.chars.next();
+            text.push_str(&self.name());
+
+            if text.ends_with('e') || text.ends_with('E') {
+                if let Some(sign) = self.chars.next_if_sign() {
+                    text.push(sign);
+                }
+                text.push_str(&self.name());
+            }
+
+            return match text.parse::<f64>() {
+                Ok(float) => self
+                    .process
+                    .float(float)
+                    .map_err(|_| "term does not fit on the heap".to_string()),
+                Err(_) => Err(format!("{:?} is not a float", text)),
+            };
+        }
+
+        let (radix, digits) = match text.find('#') {
+            Some(index) => {
+                let radix = text[..index]
+                    .trim_start_matches(|c| c == '-' || c == '+')
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|radix| 2 <= *radix && *radix <= 36)
+                    .ok_or_else(|| format!("{:?} has an invalid base", text))?;
+                let sign = if text.starts_with('-') { "-" } else { "" };
+
+                (radix, format!("{}{}", sign, &text[index + 1..]))
+            }
+            None => (10, text.clone()),
+        };
+
+        match BigInt::parse_bytes(digits.as_bytes(), radix) {
+            Some(big_int) => self
+                .process
+                .integer(big_int)
+                .map_err(|_| "term does not fit on the heap".to_string()),
+            None => Err(format!("{:?} is not an integer", text)),
+        }
+    }
+
+    /// Alphanumeric characters, such as an unquoted atom or the digits of a number.
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+
+        while let Some(c) = self.chars.peek().copied() {
+            if c.is_alphanumeric() || c == '_' || c == '@' || c == '#' {
+                name.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+
+        name
+    }
+
+    /// The rest of a string or quoted atom after its opening `quote`.
+    fn quoted(&mut self, quote: char) -> Result<String, String> {
+        let mut string = String::new();
+
+        loop {
+            match self.chars.peek().copied() {
+                Some(c) if c == quote => {
+                    self.chars.next();
+                    return Ok(string);
+                }
+                Some(_) => string.push(self.character()?),
+                None => return Err(format!("missing closing {:?}", quote)),
+            }
+        }
+    }
+
+    /// A character, which may be escaped, in a string, quoted atom or character literal.
+    fn character(&mut self) -> Result<char, String> {
+        match self.chars.next() {
+            Some('\\') => match self.chars.next() {
+                Some('n') => Ok('\n'),
+                Some('t') => Ok('\t'),
+                Some('r') => Ok('\r'),
+                Some('s') => Ok(' '),
+                Some('e') => Ok('\x1b'),
+                Some('0') => Ok('\0'),
+                Some(c) => Ok(c),
+                None => Err("unterminated escape".to_string()),
+            },
+            Some(c) => Ok(c),
+            None => Err("expected a character, found end of input".to_string()),
+        }
+    }
+}
+
+trait NextIfSign {
+    fn next_if_sign(&mut self) -> Option<char>;
+}
+
+impl NextIfSign for Peekable<Chars<'_>> {
+    fn next_if_sign(&mut self) -> Option<char> {
+        match self.peek().copied() {
+            Some(c) if c == '-' || c == '+' => {
+                self.next();
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn atom(name: &str) -> Result<Term, String> {
+    Atom::try_from_str(name)
+        .map(|atom| atom.encode().unwrap())
+        .map_err(|_| format!("{:?} is not a valid atom", name))
+}
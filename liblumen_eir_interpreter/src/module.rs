@@ -1,47 +1,172 @@
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
-use libeir_ir::{Function, FunctionIndex, LiveValues, Module};
+use libeir_ir::{Block, Function, FunctionIndex, LiveValues, Module};
 
+use liblumen_alloc::atom;
 use liblumen_alloc::erts::exception::Exception;
+use liblumen_alloc::erts::process::alloc::StackPrimitives;
 use liblumen_alloc::erts::process::code::Result;
 use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::closure::Definition;
 use liblumen_alloc::erts::term::prelude::*;
 
-macro_rules! trace {
-    ($($t:tt)*) => (lumen_runtime::system::io::puts(&format_args!($($t)*).to_string()))
-}
-//macro_rules! trace {
-//    ($($t:tt)*) => ()
-//}
+use lumen_runtime::registry;
+use lumen_runtime::scheduler::Scheduled;
 
 pub enum ResolvedFunction<'a> {
     Native(NativeFunctionKind),
     Erlang(&'a ErlangFunction),
 }
 
+/// Like the BEAM, an Erlang module can have two versions loaded at the same time: the current
+/// version, which is called by name, and the old version, which is still run by the funs and
+/// continuations that were created by it.
+///
+/// Local calls, like funs and continuations, stay in the version of the caller, while
+/// fully-qualified calls, including those of a module to itself, run the current version.
 pub struct ModuleRegistry {
     map: HashMap<Atom, ModuleType>,
+    old: HashMap<Atom, ErlangModule>,
 }
 
 impl ModuleRegistry {
     pub fn new() -> Self {
         ModuleRegistry {
             map: HashMap::new(),
+            old: HashMap::new(),
         }
     }
 
+    /// Loads `module` as the current version and makes the previous current version the old
+    /// version.  Like `c:c/1`, any existing old version is purged first.
     pub fn register_erlang_module(&mut self, module: Module) {
         let erl_module = ErlangModule::from_eir(module);
-        match self.map.remove(&erl_module.name) {
-            None => self
-                .map
-                .insert(erl_module.name, ModuleType::Erlang(erl_module)),
-            Some(ModuleType::Native(native)) => self
-                .map
-                .insert(erl_module.name, ModuleType::Overlayed(erl_module, native)),
-            _ => panic!(),
+        let name = erl_module.name;
+        let unique = erl_module.unique;
+
+        let previous = match self.map.remove(&name) {
+            None => {
+                self.map.insert(name, ModuleType::Erlang(erl_module));
+                None
+            }
+            Some(ModuleType::Native(native)) => {
+                self.map
+                    .insert(name, ModuleType::Overlayed(erl_module, native));
+                None
+            }
+            Some(ModuleType::Erlang(current)) => {
+                self.map.insert(name, ModuleType::Erlang(erl_module));
+                Some(current)
+            }
+            Some(ModuleType::Overlayed(current, native)) => {
+                self.map
+                    .insert(name, ModuleType::Overlayed(erl_module, native));
+                Some(current)
+            }
         };
+
+        // Reloading the same code replaces the current version in place, as the funs of both
+        // versions are identical.
+        let previous = previous.filter(|previous| previous.unique != unique);
+
+        if let Some(previous) = previous {
+            self.purge(name);
+            self.old.insert(name, previous);
+        }
+    }
+
+    /// Removes the old version of `module`, killing the processes that still run it.  Returns
+    /// whether there was an old version.
+    pub fn purge(&mut self, module: Atom) -> bool {
+        match self.old.remove(&module) {
+            Some(old) => {
+                for arc_process in registry::processes() {
+                    if !arc_process.is_exiting() && uses_code(&arc_process, module, old.unique) {
+                        kill(&arc_process);
+                    }
+                }
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the old version of `module` unless a process still runs it.  Returns whether
+    /// there is no old version left.
+    pub fn soft_purge(&mut self, module: Atom) -> bool {
+        self.soft_purge_unless_used_by(module, &[])
+    }
+
+    /// Like `soft_purge`, but also keeps the old version of `module` if one of `terms`, which are
+    /// not on the stack of any process, has a fun created by it.
+    pub fn soft_purge_unless_used_by(&mut self, module: Atom, terms: &[Term]) -> bool {
+        match self.old.get(&module) {
+            Some(old) => {
+                let unique = old.unique;
+
+                if terms
+                    .iter()
+                    .any(|term| term_uses_code(*term, module, unique))
+                    || registry::processes().iter().any(|arc_process| {
+                        !arc_process.is_exiting() && uses_code(arc_process, module, unique)
+                    })
+                {
+                    false
+                } else {
+                    self.old.remove(&module);
+
+                    true
+                }
+            }
+            None => true,
+        }
+    }
+
+    /// Makes the current version of `module` the old version, like `code:delete/1`, so that it
+    /// can no longer be called by name.  Returns `false` without deleting the current version if
+    /// there is already an old version.
+    pub fn delete(&mut self, module: Atom) -> bool {
+        if self.old.contains_key(&module) {
+            return false;
+        }
+
+        match self.map.remove(&module) {
+            Some(ModuleType::Erlang(current)) => {
+                self.old.insert(module, current);
+
+                true
+            }
+            Some(ModuleType::Overlayed(current, native)) => {
+                self.map.insert(module, ModuleType::Native(native));
+                self.old.insert(module, current);
+
+                true
+            }
+            Some(native @ ModuleType::Native(_)) => {
+                self.map.insert(module, native);
+
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the current or old version of the Erlang `module` is loaded.
+    pub fn is_loaded(&self, module: Atom) -> bool {
+        match self.map.get(&module) {
+            Some(ModuleType::Erlang(_)) | Some(ModuleType::Overlayed(_, _)) => true,
+            Some(ModuleType::Native(_)) | None => self.old.contains_key(&module),
+        }
+    }
+
+    /// Whether `process` runs the old version of `module`.
+    pub fn check_process_code(&self, process: &Process, module: Atom) -> bool {
+        match self.old.get(&module) {
+            Some(old) => uses_code(process, module, old.unique),
+            None => false,
+        }
     }
 
     pub fn register_native_module(&mut self, native: NativeModule) {
@@ -60,7 +185,6 @@ impl ModuleRegistry {
         function: Atom,
         arity: usize,
     ) -> Option<ResolvedFunction> {
-        trace!("LOOKUP {}:{}/{}", module, function, arity);
         match self.map.get(&module) {
             None => None,
             Some(ModuleType::Erlang(erl)) => erl
@@ -86,23 +210,91 @@ impl ModuleRegistry {
         }
     }
 
-    pub fn lookup_function_idx(
+    /// Looks up the function and block of the fun with `index` in the version of `module`
+    /// identified by `unique`, which is either the current or the old version.  Returns `None` if
+    /// that version was purged or has no fun with `index`.
+    pub fn lookup_closure(
         &self,
         module: Atom,
-        index: FunctionIndex,
-    ) -> Option<&ErlangFunction> {
-        let ret = match self.map.get(&module) {
-            None => None,
-            Some(ModuleType::Erlang(erl)) => Some(&erl.funs[&index]),
-            Some(ModuleType::Overlayed(erl, _)) => Some(&erl.funs[&index]),
+        unique: [u8; 16],
+        index: u32,
+    ) -> Option<(&ErlangFunction, Block)> {
+        let current = match self.map.get(&module) {
+            Some(ModuleType::Erlang(erl)) => Some(erl),
+            Some(ModuleType::Overlayed(erl, _)) => Some(erl),
             Some(ModuleType::Native(_)) => unreachable!(),
+            None => None,
         };
 
-        if let Some(erl) = ret.as_ref() {
-            trace!("LOOKUP IDX {}", erl.fun.ident());
+        current
+            .into_iter()
+            .chain(self.old.get(&module))
+            .find(|erl| erl.unique == unique)
+            .and_then(|erl| {
+                let (function_index, block) = *erl.closures.get(index as usize)?;
+
+                erl.funs.get(&function_index).map(|fun| (fun, block))
+            })
+    }
+}
+
+/// Whether the current frame or the stack of `process` has a fun, including a continuation,
+/// created by the version of `module` identified by `unique`.
+fn uses_code(process: &Process, module: Atom, unique: [u8; 16]) -> bool {
+    if let (Some(module_function_arity), Some(definition)) = (
+        process.current_module_function_arity(),
+        process.current_definition(),
+    ) {
+        if module_function_arity.module == module && definition_uses_code(&definition, unique) {
+            return true;
         }
+    }
+
+    let mut heap = process.acquire_heap();
+
+    (1..=heap.stack_size())
+        .filter_map(|slot| heap.stack_slot(slot))
+        .any(|term| term_uses_code(term, module, unique))
+}
 
-        ret
+fn definition_uses_code(definition: &Definition, unique: [u8; 16]) -> bool {
+    match definition {
+        Definition::Anonymous {
+            unique: definition_unique,
+            ..
+        } => *definition_unique == unique,
+        Definition::Export { .. } => false,
+    }
+}
+
+fn term_uses_code(term: Term, module: Atom, unique: [u8; 16]) -> bool {
+    match term.decode() {
+        Ok(TypedTerm::Closure(closure)) => {
+            (closure.module() == module && definition_uses_code(closure.definition(), unique))
+                || closure
+                    .env_iter()
+                    .any(|element| term_uses_code(*element, module, unique))
+        }
+        Ok(TypedTerm::Tuple(tuple)) => tuple
+            .iter()
+            .any(|element| term_uses_code(*element, module, unique)),
+        Ok(TypedTerm::List(cons)) => cons.into_iter().any(|result| match result {
+            Ok(element) => term_uses_code(element, module, unique),
+            Err(ImproperList { tail }) => term_uses_code(tail, module, unique),
+        }),
+        Ok(TypedTerm::Map(map)) => map.iter().any(|(key, value)| {
+            term_uses_code(*key, module, unique) || term_uses_code(*value, module, unique)
+        }),
+        _ => false,
+    }
+}
+
+fn kill(process: &Process) {
+    process.exit(atom!("killed"));
+
+    // Exiting processes are only cleaned up from the run queues
+    if let Some(arc_scheduler) = process.scheduler() {
+        arc_scheduler.stop_waiting(process);
     }
 }
 
@@ -145,36 +337,83 @@ impl NativeModule {
     }
 }
 
+/// A function of a module version, as seen by local calls from the other functions of the same
+/// version.
+pub struct LocalFunction {
+    pub index: FunctionIndex,
+    pub arity: u8,
+    /// The `index` of the fun that runs the function from its entry block.
+    pub closure_index: u32,
+    /// The `old_unique` of the fun that runs the function from its entry block.
+    pub old_unique: u32,
+}
+
 pub struct ErlangFunction {
     pub fun: Function,
     pub index: FunctionIndex,
     pub live: LiveValues,
+    /// The `unique` of the module version that the function belongs to.
+    pub unique: [u8; 16],
+    /// MD5 of the code of the function, which the `old_unique` of its funs is derived from.
+    pub hash: [u8; 16],
+    /// The functions of the same module version, by name and arity.
+    pub locals: Arc<BTreeMap<(Atom, usize), LocalFunction>>,
+    /// The `index` of the funs of the same module version, by function and block.
+    pub closure_indexes: Arc<BTreeMap<(FunctionIndex, Block), u32>>,
+}
+
+impl ErlangFunction {
+    /// Like the hash of the parse tree of the fun that `erlc` uses for `OldUniq`, derived from
+    /// the code of the function and the block of the fun, and limited to the 27 bits of
+    /// `erlang:phash2/1`.
+    pub fn old_unique(&self, block: Block) -> u32 {
+        let mut context = md5::Context::new();
+        context.consume(&self.hash);
+        context.consume(&block.as_u32().to_be_bytes());
+        let digest = context.compute();
+
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&digest[..4]);
+
+        u32::from_be_bytes(bytes) & ((1 << 27) - 1)
+    }
 }
 
 pub struct ErlangModule {
     pub name: Atom,
+    /// Identifies this version of the module in the funs it creates, so that they keep running
+    /// it after a newer version is loaded.  Like the `unique` that `erlc` writes in the `FunT`
+    /// chunk, it is the MD5 of the code of the module, so it is stable across loads of the same
+    /// code.
+    pub unique: [u8; 16],
     pub funs: BTreeMap<FunctionIndex, ErlangFunction>,
     pub name_map: BTreeMap<(Atom, usize), FunctionIndex>,
+    /// The function and block of the fun with each `index`.  Like `erlc`, which numbers the funs
+    /// of a module in the order they appear in the source, funs are numbered in the order of
+    /// their functions and then their blocks.
+    pub closures: Vec<(FunctionIndex, Block)>,
 }
 
 impl ErlangModule {
     pub fn from_eir(module: Module) -> Self {
         let name_atom = Atom::try_from_str(module.name().as_str()).unwrap();
 
-        let funs = module
+        let hashes: BTreeMap<FunctionIndex, [u8; 16]> = module
             .function_iter()
             .map(|fun_def| {
-                let fun = fun_def.function();
-                let nfun = ErlangFunction {
-                    live: fun.live_values(),
-                    index: fun_def.index(),
-                    fun: fun.clone(),
-                };
-                (fun_def.index(), nfun)
+                let text = fun_def.function().to_text();
+                (fun_def.index(), md5::compute(text.as_bytes()).0)
             })
             .collect();
 
-        let name_map = module
+        let mut context = md5::Context::new();
+        context.consume(name_atom.name().as_bytes());
+        for hash in hashes.values() {
+            context.consume(hash);
+        }
+        let unique = context.compute().0;
+
+        let name_map: BTreeMap<(Atom, usize), FunctionIndex> = module
             .function_iter()
             .map(|fun_def| {
                 let fun = fun_def.function();
@@ -184,10 +423,67 @@ impl ErlangModule {
             })
             .collect();
 
+        let closures: Vec<(FunctionIndex, Block)> = module
+            .function_iter()
+            .flat_map(|fun_def| {
+                let index = fun_def.index();
+
+                fun_def
+                    .function()
+                    .block_iter()
+                    .map(move |block| (index, block))
+            })
+            .collect();
+
+        let closure_indexes: Arc<BTreeMap<(FunctionIndex, Block), u32>> = Arc::new(
+            closures
+                .iter()
+                .enumerate()
+                .map(|(closure_index, function_block)| (*function_block, closure_index as u32))
+                .collect(),
+        );
+
+        let mut funs: BTreeMap<FunctionIndex, ErlangFunction> = BTreeMap::new();
+        let mut locals = BTreeMap::new();
+
+        for fun_def in module.function_iter() {
+            let fun = fun_def.function();
+            let nfun = ErlangFunction {
+                live: fun.live_values(),
+                index: fun_def.index(),
+                fun: fun.clone(),
+                unique,
+                hash: hashes[&fun_def.index()],
+                locals: Arc::new(BTreeMap::new()),
+                closure_indexes: closure_indexes.clone(),
+            };
+
+            let ident = fun.ident();
+            let name = Atom::try_from_str(ident.name.as_str()).unwrap();
+            locals.insert(
+                (name, ident.arity),
+                LocalFunction {
+                    index: fun_def.index(),
+                    arity: ident.arity as u8,
+                    closure_index: closure_indexes[&(fun_def.index(), fun.block_entry())],
+                    old_unique: nfun.old_unique(fun.block_entry()),
+                },
+            );
+
+            funs.insert(fun_def.index(), nfun);
+        }
+
+        let locals = Arc::new(locals);
+        for nfun in funs.values_mut() {
+            nfun.locals = locals.clone();
+        }
+
         ErlangModule {
             name: name_atom,
+            unique,
             funs,
             name_map,
+            closures,
         }
     }
 }
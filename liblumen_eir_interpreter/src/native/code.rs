@@ -0,0 +1,31 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::module::NativeModule;
+use crate::VM;
+
+pub fn make_code() -> NativeModule {
+    let mut native = NativeModule::new(Atom::try_from_str("code").unwrap());
+
+    native.add_simple(Atom::try_from_str("purge").unwrap(), 1, |_proc, args| {
+        let module: Atom = args[0].try_into().map_err(|_| badarg!())?;
+        let purged = VM.modules.write().unwrap().purge(module);
+
+        Ok(purged.into())
+    });
+
+    native.add_simple(
+        Atom::try_from_str("soft_purge").unwrap(),
+        1,
+        |_proc, args| {
+            let module: Atom = args[0].try_into().map_err(|_| badarg!())?;
+            let purged = VM.modules.write().unwrap().soft_purge(module);
+
+            Ok(purged.into())
+        },
+    );
+
+    native
+}
@@ -1,8 +1,13 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::badarg;
 use liblumen_alloc::erts::term::prelude::*;
 
 use lumen_runtime::otp::erlang;
+use lumen_runtime::registry::pid_to_self_or_process;
 
 use crate::module::NativeModule;
+use crate::VM;
 
 pub fn make_erlang() -> NativeModule {
     let mut native = NativeModule::new(Atom::try_from_str("erlang").unwrap());
@@ -72,6 +77,25 @@ pub fn make_erlang() -> NativeModule {
         },
     );
 
+    native.add_simple(
+        Atom::try_from_str("term_to_binary").unwrap(),
+        1,
+        |proc, args| erlang::term_to_binary_1::native(proc, args[0]),
+    );
+    native.add_simple(
+        Atom::try_from_str("binary_to_term").unwrap(),
+        1,
+        |proc, args| erlang::binary_to_term_1::native(proc, args[0]),
+    );
+
+    native.add_simple(Atom::try_from_str("error").unwrap(), 1, |_proc, args| {
+        erlang::error_1::native(args[0])
+    });
+
+    native.add_simple(Atom::try_from_str("exit").unwrap(), 2, |proc, args| {
+        erlang::exit_2::native(proc, args[0], args[1])
+    });
+
     native.add_simple(Atom::try_from_str("exit").unwrap(), 1, |_proc, args| {
         panic!("{:?}", args[0]);
         //Ok(erlang::exit_1::native(args[0]).unwrap())
@@ -152,6 +176,9 @@ pub fn make_erlang() -> NativeModule {
         2,
         |_proc, args| Ok(erlang::is_function_1::native(args[0])),
     );
+    native.add_simple(Atom::try_from_str("fun_info").unwrap(), 2, |proc, args| {
+        erlang::fun_info_2::native(proc, args[0], args[1])
+    });
     native.add_simple(Atom::try_from_str("is_tuple").unwrap(), 1, |_proc, args| {
         Ok(erlang::is_tuple_1::native(args[0]))
     });
@@ -193,12 +220,49 @@ pub fn make_erlang() -> NativeModule {
         erlang::whereis_1::native(args[0])
     });
 
+    native.add_simple(
+        Atom::try_from_str("process_info").unwrap(),
+        1,
+        |proc, args| erlang::process_info_1::native(proc, args[0]),
+    );
     native.add_simple(
         Atom::try_from_str("process_info").unwrap(),
         2,
         |proc, args| erlang::process_info_2::native(proc, args[0], args[1]),
     );
 
+    native.add_yielding(
+        Atom::try_from_str("garbage_collect").unwrap(),
+        0,
+        |proc, args| crate::code::garbage_collect(proc, args, proc.pid_term(), Term::NIL),
+    );
+    native.add_yielding(
+        Atom::try_from_str("garbage_collect").unwrap(),
+        1,
+        |proc, args| crate::code::garbage_collect(proc, args, args[2], Term::NIL),
+    );
+    native.add_yielding(
+        Atom::try_from_str("garbage_collect").unwrap(),
+        2,
+        |proc, args| crate::code::garbage_collect(proc, args, args[2], args[3]),
+    );
+
+    native.add_simple(
+        Atom::try_from_str("statistics").unwrap(),
+        1,
+        |proc, args| erlang::statistics_1::native(proc, args[0]),
+    );
+    native.add_simple(
+        Atom::try_from_str("system_flag").unwrap(),
+        2,
+        |proc, args| erlang::system_flag_2::native(proc, args[0], args[1]),
+    );
+    native.add_simple(
+        Atom::try_from_str("system_info").unwrap(),
+        1,
+        |proc, args| erlang::system_info_1::native(proc, args[0]),
+    );
+
     native.add_simple(Atom::try_from_str("get").unwrap(), 1, |proc, args| {
         Ok(erlang::get_1::native(proc, args[0]))
     });
@@ -216,5 +280,28 @@ pub fn make_erlang() -> NativeModule {
         erlang::element_2::native(args[0], args[1])
     });
 
+    native.add_simple(
+        Atom::try_from_str("check_process_code").unwrap(),
+        2,
+        |proc, args| {
+            let pid = match args[0].decode()? {
+                TypedTerm::Pid(pid) => pid,
+                _ => return Err(badarg!().into()),
+            };
+            let module: Atom = args[1].try_into().map_err(|_| badarg!())?;
+
+            let uses_old_code = match pid_to_self_or_process(pid, proc) {
+                Some(arc_process) => VM
+                    .modules
+                    .read()
+                    .unwrap()
+                    .check_process_code(&arc_process, module),
+                None => false,
+            };
+
+            Ok(uses_old_code.into())
+        },
+    );
+
     native
 }
@@ -1,6 +1,11 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::badarg;
 use liblumen_alloc::erts::term::prelude::*;
 
 use crate::module::NativeModule;
+use crate::trace::FunctionPattern;
+use crate::VM;
 
 pub fn make_lumen_intrinsics() -> NativeModule {
     let mut native = NativeModule::new(Atom::try_from_str("lumen_intrinsics").unwrap());
@@ -25,5 +30,53 @@ pub fn make_lumen_intrinsics() -> NativeModule {
         },
     );
 
+    native.add_simple(
+        Atom::try_from_str("trace_process").unwrap(),
+        2,
+        |_proc, args| {
+            let pid = match args[0].decode()? {
+                TypedTerm::Pid(pid) => pid,
+                _ => return Err(badarg!().into()),
+            };
+            let enabled: bool = args[1].try_into().map_err(|_| badarg!())?;
+
+            VM.tracer.write().unwrap().trace_process(pid, enabled);
+
+            Ok(Atom::str_to_term("ok"))
+        },
+    );
+
+    native.add_simple(
+        Atom::try_from_str("trace_function").unwrap(),
+        4,
+        |_proc, args| {
+            let wildcard = Atom::str_to_term("_");
+
+            let module: Atom = args[0].try_into().map_err(|_| badarg!())?;
+            let function: Option<Atom> = if args[1] == wildcard {
+                None
+            } else {
+                Some(args[1].try_into().map_err(|_| badarg!())?)
+            };
+            let arity: Option<usize> = if args[2] == wildcard {
+                None
+            } else {
+                Some(args[2].try_into().map_err(|_| badarg!())?)
+            };
+            let enabled: bool = args[3].try_into().map_err(|_| badarg!())?;
+
+            VM.tracer.write().unwrap().trace_function(
+                FunctionPattern {
+                    module,
+                    function,
+                    arity,
+                },
+                enabled,
+            );
+
+            Ok(Atom::str_to_term("ok"))
+        },
+    );
+
     native
 }
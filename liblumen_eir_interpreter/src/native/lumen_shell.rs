@@ -0,0 +1,59 @@
+use std::convert::TryInto;
+use std::path::Path;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::format::ErlangFormat;
+use crate::module::NativeModule;
+
+pub fn make_lumen_shell() -> NativeModule {
+    let mut native = NativeModule::new(Atom::try_from_str("lumen_shell").unwrap());
+
+    native.add_simple(Atom::try_from_str("reply").unwrap(), 2, |_proc, args| {
+        crate::shell::reply(args[0], args[1]);
+        Ok(Atom::str_to_term("ok"))
+    });
+
+    native.add_simple(Atom::try_from_str("c").unwrap(), 1, |proc, args| {
+        let mut file = file_name(args[0])?;
+
+        if Path::new(&file).extension().is_none() {
+            file.push_str(".erl");
+        }
+
+        match crate::shell::load_file(&file) {
+            Ok(module) => {
+                let module_term = Atom::str_to_term(module.name());
+                let term = proc.tuple_from_slice(&[Atom::str_to_term("ok"), module_term])?;
+
+                Ok(term)
+            }
+            Err(()) => Ok(Atom::str_to_term("error")),
+        }
+    });
+
+    native.add_simple(Atom::try_from_str("flush").unwrap(), 0, |proc, _args| {
+        let mailbox_guard = proc.mailbox.lock();
+        let mut mailbox = mailbox_guard.borrow_mut();
+
+        while 0 < mailbox.len() {
+            let message = *mailbox.iter().next().unwrap().data();
+            lumen_runtime::system::io::puts(&format!("Shell got {}", ErlangFormat(message)));
+            mailbox.remove(0, proc);
+        }
+
+        Ok(Atom::str_to_term("ok"))
+    });
+
+    native
+}
+
+/// Like `c:c/1`, the file can be named by an atom, a binary or a charlist.
+fn file_name(term: Term) -> exception::Result<String> {
+    match term.decode()? {
+        TypedTerm::Atom(atom) => Ok(atom.name().to_string()),
+        TypedTerm::List(cons) => cons.try_into(),
+        _ => lumen_runtime::binary_to_string::binary_to_string(term),
+    }
+}
@@ -1,3 +1,6 @@
+mod code;
+pub use code::make_code;
+
 mod erlang;
 pub use erlang::make_erlang;
 
@@ -12,3 +15,6 @@ pub use logger::make_logger;
 
 mod lumen_intrinsics;
 pub use lumen_intrinsics::make_lumen_intrinsics;
+
+mod lumen_shell;
+pub use lumen_shell::make_lumen_shell;
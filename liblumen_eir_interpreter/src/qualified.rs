@@ -0,0 +1,108 @@
+//! EIR lowers local calls and fully-qualified calls with a literal module to the same
+//! `CaptureFunction`, but like the BEAM, only local calls stay in the version of the caller, so
+//! that `?MODULE:loop()` switches to the current version.  Before lowering, the module of each
+//! fully-qualified call of a module to itself is marked, so that the interpreter can tell the two
+//! apart.
+
+use libeir_intern::Symbol;
+use libeir_syntax_erl::ast::{self, Expr, Module as ErlAstModule};
+
+use liblumen_alloc::erts::term::prelude::Atom;
+
+const MARK: &str = "$qualified$";
+
+/// Marks the fully-qualified calls of `module` to its own functions.  Must be called before
+/// `lower_module`.
+pub fn mark_qualified_calls(module: &mut ErlAstModule) {
+    let name = module.name.name;
+
+    for function in module.functions.values_mut() {
+        for clause in function.clauses.iter_mut() {
+            mark_sequence(name, &mut clause.body);
+        }
+    }
+}
+
+/// The module called by a `CaptureFunction` of `module`, without the mark of
+/// `mark_qualified_calls`.
+pub fn unmarked(module: Atom) -> Atom {
+    let name = module.name();
+
+    if name.starts_with(MARK) {
+        Atom::try_from_str(&name[MARK.len()..]).unwrap()
+    } else {
+        module
+    }
+}
+
+fn mark_sequence(module: Symbol, expressions: &mut [Expr]) {
+    for expression in expressions.iter_mut() {
+        mark_expression(module, expression);
+    }
+}
+
+fn mark_clauses(module: Symbol, clauses: &mut [ast::Clause]) {
+    for clause in clauses.iter_mut() {
+        mark_sequence(module, &mut clause.body);
+    }
+}
+
+fn mark_expression(module: Symbol, expression: &mut Expr) {
+    match expression {
+        Expr::Apply(ast::Apply { callee, args, .. }) => {
+            if let Expr::Remote(ast::Remote {
+                module: callee_module,
+                ..
+            }) = &mut **callee
+            {
+                if let Expr::Literal(ast::Literal::Atom(_, ident)) = &mut **callee_module {
+                    if ident.name == module {
+                        ident.name = Symbol::intern(&format!("{}{}", MARK, module));
+                    }
+                }
+            } else {
+                mark_expression(module, callee);
+            }
+
+            mark_sequence(module, args);
+        }
+        Expr::Match(ast::Match { expr, .. }) => mark_expression(module, expr),
+        Expr::Begin(ast::Begin { body, .. }) => mark_sequence(module, body),
+        Expr::Case(ast::Case { expr, clauses, .. }) => {
+            mark_expression(module, expr);
+            mark_clauses(module, clauses);
+        }
+        Expr::If(ast::If { clauses, .. }) => {
+            for clause in clauses.iter_mut() {
+                mark_sequence(module, &mut clause.body);
+            }
+        }
+        Expr::Receive(ast::Receive { clauses, after, .. }) => {
+            if let Some(clauses) = clauses {
+                mark_clauses(module, clauses);
+            }
+
+            if let Some(after) = after {
+                mark_sequence(module, &mut after.body);
+            }
+        }
+        Expr::Catch(ast::Catch { expr, .. }) => mark_expression(module, expr),
+        Expr::Fun(ast::Function::Unnamed(ast::Lambda { clauses, .. }))
+        | Expr::Fun(ast::Function::Named(ast::NamedFunction { clauses, .. })) => {
+            for clause in clauses.iter_mut() {
+                mark_sequence(module, &mut clause.body);
+            }
+        }
+        Expr::BinaryExpr(ast::BinaryExpr { lhs, rhs, .. }) => {
+            mark_expression(module, lhs);
+            mark_expression(module, rhs);
+        }
+        Expr::UnaryExpr(ast::UnaryExpr { operand, .. }) => mark_expression(module, operand),
+        Expr::Tuple(ast::Tuple { elements, .. }) => mark_sequence(module, elements),
+        Expr::Cons(ast::Cons { head, tail, .. }) => {
+            mark_expression(module, head);
+            mark_expression(module, tail);
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,599 @@
+//! An interactive Erlang shell.
+//!
+//! Each expression is lowered into an ad-hoc module whose `eval/1` takes the values of the bound
+//! variables and returns the value of the expression with the values of any newly bound
+//! variables.  The ad-hoc modules are evaluated in a long-lived shell process, so that `self()`
+//! and the mailbox survive between expressions.
+
+use std::convert::TryInto;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Once};
+
+use libeir_diagnostics::{ColorChoice, Emitter, StandardStreamEmitter};
+
+use libeir_ir::Module;
+
+use libeir_passes::PassManager;
+
+use libeir_syntax_erl::ast::{self, Expr, Module as ErlAstModule};
+use libeir_syntax_erl::lower_module;
+use libeir_syntax_erl::{ParseConfig, Parser};
+
+use liblumen_alloc::atom;
+use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::HeapFragment;
+
+use lumen_runtime::otp::erlang;
+use lumen_runtime::scheduler::Scheduler;
+
+use crate::call_result::{call_erlang, ProcessResult, ProcessResultReceiver};
+use crate::format::ErlangFormat;
+use crate::qualified::mark_qualified_calls;
+use crate::VM;
+
+const SERVER_MODULE: &str = "lumen_shell_server";
+const SERVER_SOURCE: &str = "
+-module(lumen_shell_server).
+
+loop() ->
+    receive
+        {eval, Module, Bindings, Reply} ->
+            lumen_shell:reply(Reply, Module:eval(Bindings)),
+            loop()
+    end.
+";
+
+/// Variable that holds the value of the expressions in an ad-hoc module.
+const VALUE_VARIABLE: &str = "LumenShellValue";
+
+static REGISTER_SERVER: Once = Once::new();
+static EVALUATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs the shell on stdin and stdout until `q().`, `halt().` or the end of input.
+pub fn run() -> io::Result<()> {
+    let mut shell = Shell::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let stdout = io::stdout();
+
+    loop {
+        let mut input = String::new();
+
+        // Read lines until the expressions are terminated with `.`
+        loop {
+            {
+                let mut stdout = stdout.lock();
+                if input.is_empty() {
+                    write!(stdout, "{}> ", shell.prompt_number())?;
+                } else {
+                    write!(stdout, "{}.. ", shell.prompt_number())?;
+                }
+                stdout.flush()?;
+            }
+
+            match lines.next() {
+                Some(line) => {
+                    input.push_str(&line?);
+                    input.push('\n');
+
+                    if input.trim_end().ends_with('.') {
+                        break;
+                    }
+                }
+                None => return Ok(()),
+            }
+        }
+
+        match input.trim() {
+            "q()." | "halt()." => return Ok(()),
+            "." => continue,
+            _ => (),
+        }
+
+        match shell.eval(&input) {
+            Ok(value) => println!("{}", ErlangFormat(value)),
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+pub enum ShellError {
+    /// The diagnostics were already emitted to stderr.
+    Compile,
+    Exception {
+        class: Term,
+        reason: Term,
+        stacktrace: Term,
+    },
+    /// The shell process is waiting for a message that nothing can send.
+    Deadlock,
+}
+
+impl Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShellError::Compile => write!(f, "* compilation failed"),
+            ShellError::Exception { class, reason, .. } => write!(
+                f,
+                "** exception {}: {}",
+                ErlangFormat(*class),
+                ErlangFormat(*reason)
+            ),
+            ShellError::Deadlock => write!(
+                f,
+                "** shell process is waiting for a message that will never be sent"
+            ),
+        }
+    }
+}
+
+pub struct Shell {
+    init_arc_process: Arc<Process>,
+    server: ProcessResultReceiver,
+    reply_sender: Term,
+    reply_receiver: Receiver<ProcessResult>,
+    /// The values are in the `HeapFragment`s of the `ProcessResult`s of earlier evaluations.
+    bindings: Vec<(String, Term)>,
+    evaluations: usize,
+    /// The ad-hoc modules of the evaluations that are not purged yet.
+    eval_modules: Vec<Atom>,
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        &*VM;
+
+        REGISTER_SERVER.call_once(|| {
+            let eir_mod = lower_string(SERVER_SOURCE).unwrap();
+            register(eir_mod);
+        });
+
+        let init_arc_process = Scheduler::current().spawn_init(0).unwrap();
+
+        let (tx, reply_receiver) = channel();
+        let reply_sender = init_arc_process
+            .resource(Box::new(ReplySender { tx }))
+            .unwrap();
+
+        let server = start_server(&init_arc_process);
+
+        Shell {
+            init_arc_process,
+            server,
+            reply_sender,
+            reply_receiver,
+            bindings: Vec::new(),
+            evaluations: 0,
+            eval_modules: Vec::new(),
+        }
+    }
+
+    /// The pid of the long-lived shell process, which changes when an evaluation raises.
+    pub fn pid(&self) -> Pid {
+        self.server.process.pid()
+    }
+
+    /// Evaluates the `.` terminated `input` expressions in the shell process.
+    pub fn eval(&mut self, input: &str) -> Result<Term, ShellError> {
+        let expressions = input.trim().trim_end_matches('.');
+
+        let bound_names: Vec<&str> = self
+            .bindings
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let new_names: Vec<String> = bound_variables(expressions)
+            .map_err(|()| ShellError::Compile)?
+            .into_iter()
+            .filter(|name| !bound_names.contains(&name.as_str()))
+            .collect();
+
+        let module_name = format!(
+            "lumen_shell_eval_{}",
+            EVALUATIONS.fetch_add(1, Ordering::SeqCst)
+        );
+        let source = format!(
+            "-module({module}).\n\
+             \n\
+             eval([{bound}]) ->\n\
+             \x20   {value} = begin\n\
+             {expressions}\n\
+             \x20   end,\n\
+             \x20   {{{value}, [{new}]}}.\n\
+             \n\
+             c(File) -> lumen_shell:c(File).\n\
+             \n\
+             flush() -> lumen_shell:flush().\n",
+            module = module_name,
+            bound = bound_names.join(", "),
+            value = VALUE_VARIABLE,
+            expressions = expressions,
+            new = new_names.join(", "),
+        );
+        let eir_mod = lower_string(&source).map_err(|()| ShellError::Compile)?;
+        let module = register(eir_mod);
+        self.eval_modules.push(module);
+
+        self.evaluations += 1;
+
+        let result = self
+            .send_eval(module.encode().unwrap())
+            .map(|result| self.bind(new_names, result));
+
+        // After binding, so that the module of a bound fun is kept
+        self.purge_eval_modules();
+
+        result
+    }
+
+    /// Binds `new_names` to the values of the newly bound variables in the `{Value, NewValues}`
+    /// `result` of an evaluation, returning `Value`.
+    fn bind(&mut self, new_names: Vec<String>, result: Term) -> Term {
+        let tuple: Boxed<Tuple> = result.try_into().unwrap();
+
+        match tuple.elements()[1].decode().unwrap() {
+            TypedTerm::Nil => (),
+            TypedTerm::List(cons) => {
+                for (name, result) in new_names.into_iter().zip(cons.into_iter()) {
+                    self.bindings.push((name, result.unwrap()));
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        tuple.elements()[0]
+    }
+
+    /// Deletes and purges the ad-hoc modules of the evaluations, so that they do not accumulate.
+    /// A module is kept while a binding or a process still has a fun created by it, and purged
+    /// after a later evaluation once none do.
+    fn purge_eval_modules(&mut self) {
+        let values: Vec<Term> = self.bindings.iter().map(|(_, value)| *value).collect();
+        let mut modules = VM.modules.write().unwrap();
+
+        self.eval_modules.retain(|module| {
+            modules.delete(*module);
+
+            !modules.soft_purge_unless_used_by(*module, &values)
+        });
+    }
+
+    fn prompt_number(&self) -> usize {
+        self.evaluations + 1
+    }
+
+    /// Sends `{eval, Module, Bindings, Reply}` to the shell process and runs it until it replies.
+    fn send_eval(&mut self, module: Term) -> Result<Term, ShellError> {
+        let values: Vec<Term> = self.bindings.iter().map(|(_, value)| *value).collect();
+
+        // The message is shallow, so it only needs the words for the tuple and the list cells.
+        let mut fragment = HeapFragment::new_from_word_size(8 + 2 * values.len()).unwrap();
+        let heap = unsafe { fragment.as_mut() };
+        let bindings = heap.list_from_slice(&values).unwrap();
+        let message = heap
+            .tuple_from_slice(&[atom!("eval"), module, bindings, self.reply_sender])
+            .unwrap();
+
+        erlang::send_2::native(
+            &self.init_arc_process,
+            self.server.process.pid_term(),
+            message,
+        )
+        .unwrap();
+
+        loop {
+            let ran = Scheduler::current().run_through(&self.server.process);
+
+            if let Ok(ProcessResult { result, .. }) = self.reply_receiver.try_recv() {
+                return Ok(result.unwrap());
+            }
+
+            let status_result = match *self.server.process.status.read() {
+                Status::Exiting(_) => Some(match self.server.try_get() {
+                    Some(ProcessResult {
+                        result: Err((class, reason, stacktrace)),
+                        ..
+                    }) => Err(ShellError::Exception {
+                        class,
+                        reason,
+                        stacktrace,
+                    }),
+                    _ => Err(ShellError::Exception {
+                        class: atom!("exit"),
+                        reason: atom!("killed"),
+                        stacktrace: Term::NIL,
+                    }),
+                }),
+                Status::Waiting => {
+                    if !ran && Scheduler::current().hierarchy.read().is_empty() {
+                        Some(Err(ShellError::Deadlock))
+                    } else {
+                        None
+                    }
+                }
+                Status::Runnable | Status::Running => None,
+            };
+
+            if let Some(result) = status_result {
+                // Like `erl`, a new shell process takes over after an exception.  A deadlocked
+                // shell process is also replaced, so that the shell is usable again.
+                if let Err(ShellError::Deadlock) = result {
+                    self.server.process.exit(atom!("killed"));
+                    Scheduler::current().stop_waiting(&self.server.process);
+                }
+
+                self.server = start_server(&self.init_arc_process);
+
+                return result;
+            }
+        }
+    }
+}
+
+struct ReplySender {
+    tx: Sender<ProcessResult>,
+}
+
+fn start_server(init_arc_process: &Arc<Process>) -> ProcessResultReceiver {
+    let module = Atom::try_from_str(SERVER_MODULE).unwrap();
+    let function = Atom::try_from_str("loop").unwrap();
+
+    call_erlang(init_arc_process.clone(), module, function, &[])
+}
+
+/// `lumen_shell:reply(Reply, Result)` sends `Result` of the evaluation back to the `Shell`.
+pub(crate) fn reply(reply: Term, result: Term) {
+    let reply_resource: Boxed<Resource> = reply.try_into().unwrap();
+    let reply_any: Resource = reply_resource.into();
+    let reply_sender: &ReplySender = reply_any.downcast_ref().unwrap();
+
+    let (result, heap) = result.clone_to_fragment().unwrap();
+
+    reply_sender
+        .tx
+        .send(ProcessResult {
+            heap,
+            result: Ok(result),
+        })
+        .unwrap();
+}
+
+/// Compiles the Erlang source file, or `.beam` file with `debug_info`, at `path` and loads the
+/// module, returning its name.
+pub(crate) fn load_file<P: AsRef<Path>>(path: P) -> Result<Atom, ()> {
+    if crate::beam::is_beam_file(&path) {
+        let source = crate::beam::erlang_source(path)?;
+
+        return lower_string(&source).map(register);
+    }
+
+    let parser = Parser::new(ParseConfig::default());
+    let errs = match parser.parse_file::<_, ErlAstModule>(path) {
+        Ok(parsed) => return lower(parsed, parser).map(register),
+        Err(errs) => errs,
+    };
+    let emitter =
+        StandardStreamEmitter::new(ColorChoice::Auto).set_codemap(parser.config.codemap.clone());
+    for err in errs.iter() {
+        emitter.diagnostic(&err.to_diagnostic()).unwrap();
+    }
+    Err(())
+}
+
+fn lower_string(source: &str) -> Result<Module, ()> {
+    let (parsed, parser) = parse_string(source)?;
+
+    lower(parsed, parser)
+}
+
+fn parse_string(source: &str) -> Result<(ErlAstModule, Parser), ()> {
+    let parser = Parser::new(ParseConfig::default());
+    let errs = match parser.parse_string::<&str, ErlAstModule>(source) {
+        Ok(parsed) => return Ok((parsed, parser)),
+        Err(errs) => errs,
+    };
+    let emitter =
+        StandardStreamEmitter::new(ColorChoice::Auto).set_codemap(parser.config.codemap.clone());
+    for err in errs.iter() {
+        emitter.diagnostic(&err.to_diagnostic()).unwrap();
+    }
+    Err(())
+}
+
+fn lower(mut parsed: ErlAstModule, parser: Parser) -> Result<Module, ()> {
+    mark_qualified_calls(&mut parsed);
+    let (res, messages) = lower_module(&parsed);
+
+    let emitter =
+        StandardStreamEmitter::new(ColorChoice::Auto).set_codemap(parser.config.codemap.clone());
+    for err in messages.iter() {
+        emitter.diagnostic(&err.to_diagnostic()).unwrap();
+    }
+
+    res
+}
+
+/// Loads `eir_mod`, returning its name.
+fn register(mut eir_mod: Module) -> Atom {
+    let name = Atom::try_from_str(eir_mod.name().as_str()).unwrap();
+
+    for fun_def in eir_mod.function_iter() {
+        let fun = fun_def.function();
+        fun.graph_validate_global();
+    }
+
+    let mut pass_manager = PassManager::default();
+    pass_manager.run(&mut eir_mod);
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    name
+}
+
+/// The variables that are still bound after `expressions`, such as `X` and `Y` in
+/// `{X, Y} = foo(), X + Y`, in order of first appearance.
+fn bound_variables(expressions: &str) -> Result<Vec<String>, ()> {
+    let source = format!(
+        "-module(lumen_shell_bindings).\n\nbindings() ->\n{}.\n",
+        expressions
+    );
+    let (parsed, _) = parse_string(&source)?;
+    let mut variables = Vec::new();
+
+    for function in parsed.functions.values() {
+        for clause in &function.clauses {
+            sequence_variables(&clause.body, &mut variables);
+        }
+    }
+
+    Ok(variables)
+}
+
+/// Adds the variables that are still bound after the sequence of `expressions` to `variables`.
+fn sequence_variables(expressions: &[Expr], variables: &mut Vec<String>) {
+    for expression in expressions {
+        expression_variables(expression, variables);
+    }
+}
+
+/// Adds the variables that are still bound after `expression` to `variables`.  Like the
+/// compiler, variables bound in the clauses of a `case`, `if` or `receive` are only still bound
+/// if every clause binds them, and variables bound in a `fun`, comprehension, `catch` or `try`
+/// are not.
+fn expression_variables(expression: &Expr, variables: &mut Vec<String>) {
+    match expression {
+        Expr::Match(ast::Match { pattern, expr, .. }) => {
+            pattern_variables(pattern, variables);
+            expression_variables(expr, variables);
+        }
+        Expr::Begin(ast::Begin { body, .. }) => sequence_variables(body, variables),
+        Expr::Case(ast::Case { expr, clauses, .. }) => {
+            expression_variables(expr, variables);
+            common_variables(clauses.iter().map(clause_variables), variables);
+        }
+        Expr::If(ast::If { clauses, .. }) => common_variables(
+            clauses.iter().map(|clause| body_variables(&clause.body)),
+            variables,
+        ),
+        Expr::Receive(ast::Receive { clauses, after, .. }) => common_variables(
+            clauses
+                .iter()
+                .flatten()
+                .map(clause_variables)
+                .chain(after.iter().map(|after| body_variables(&after.body))),
+            variables,
+        ),
+        // Matches in the arguments of calls and operators and in the elements of tuples and lists
+        Expr::Apply(ast::Apply { args, .. }) => sequence_variables(args, variables),
+        Expr::BinaryExpr(ast::BinaryExpr { lhs, rhs, .. }) => {
+            expression_variables(lhs, variables);
+            expression_variables(rhs, variables);
+        }
+        Expr::UnaryExpr(ast::UnaryExpr { operand, .. }) => expression_variables(operand, variables),
+        Expr::Tuple(ast::Tuple { elements, .. }) => sequence_variables(elements, variables),
+        Expr::Cons(ast::Cons { head, tail, .. }) => {
+            expression_variables(head, variables);
+            expression_variables(tail, variables);
+        }
+        _ => (),
+    }
+}
+
+/// The variables bound by the pattern and body of a `case` or `receive` clause
+fn clause_variables(clause: &ast::Clause) -> Vec<String> {
+    let mut variables = Vec::new();
+    pattern_variables(&clause.pattern, &mut variables);
+    sequence_variables(&clause.body, &mut variables);
+
+    variables
+}
+
+fn body_variables(body: &[Expr]) -> Vec<String> {
+    let mut variables = Vec::new();
+    sequence_variables(body, &mut variables);
+
+    variables
+}
+
+/// Adds the variables bound by every one of `clauses` to `variables`, in the order of the first
+/// clause.
+fn common_variables<I>(clauses: I, variables: &mut Vec<String>)
+where
+    I: Iterator<Item = Vec<String>>,
+{
+    let clauses: Vec<Vec<String>> = clauses.collect();
+
+    if let Some((first, rest)) = clauses.split_first() {
+        for variable in first {
+            if rest.iter().all(|clause| clause.contains(variable)) {
+                add_variable(variables, variable.clone());
+            }
+        }
+    }
+}
+
+/// Adds the variables in `pattern` to `variables`.
+fn pattern_variables(pattern: &Expr, variables: &mut Vec<String>) {
+    match pattern {
+        Expr::Var(ast::Var(_, name)) => {
+            let name = name.to_string();
+
+            // `_` matches without binding
+            if name != "_" {
+                add_variable(variables, name);
+            }
+        }
+        Expr::Match(ast::Match { pattern, expr, .. }) => {
+            pattern_variables(pattern, variables);
+            pattern_variables(expr, variables);
+        }
+        Expr::Tuple(ast::Tuple { elements, .. }) => {
+            for element in elements {
+                pattern_variables(element, variables);
+            }
+        }
+        Expr::Cons(ast::Cons { head, tail, .. }) => {
+            pattern_variables(head, variables);
+            pattern_variables(tail, variables);
+        }
+        // `"prefix" ++ Suffix`
+        Expr::BinaryExpr(ast::BinaryExpr { lhs, rhs, .. }) => {
+            pattern_variables(lhs, variables);
+            pattern_variables(rhs, variables);
+        }
+        Expr::Map(ast::Map { fields, .. }) => {
+            for field in fields {
+                match field {
+                    ast::MapField::Assoc { value, .. } | ast::MapField::Exact { value, .. } => {
+                        pattern_variables(value, variables)
+                    }
+                }
+            }
+        }
+        Expr::Binary(ast::Binary { elements, .. }) => {
+            for element in elements {
+                pattern_variables(&element.bit_expr, variables);
+            }
+        }
+        Expr::Record(ast::Record { fields, .. }) => {
+            for field in fields {
+                if let Some(value) = &field.value {
+                    pattern_variables(value, variables);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+fn add_variable(variables: &mut Vec<String>, variable: String) {
+    if !variables.contains(&variable) {
+        variables.push(variable);
+    }
+}
@@ -1,3 +1,4 @@
+use super::qualified::mark_qualified_calls;
 use super::VM;
 
 use libeir_diagnostics::{ColorChoice, Emitter, StandardStreamEmitter};
@@ -32,7 +33,8 @@ where
 }
 
 pub fn lower(input: &str, config: ParseConfig) -> Result<Module, ()> {
-    let (parsed, parser): (ErlAstModule, _) = parse(input, config);
+    let (mut parsed, parser): (ErlAstModule, _) = parse(input, config);
+    mark_qualified_calls(&mut parsed);
     let (res, messages) = lower_module(&parsed);
 
     let emitter =
@@ -292,3 +294,999 @@ run(N) -> this_proc(N, 0).
     println!("{:?}", res.result);
     //assert!(res.result == Ok(100));
 }
+
+#[test]
+fn receive_after() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("receive_after").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(receive_after).
+
+poll() ->
+    receive
+        a -> a
+    after 0 -> empty
+    end.
+
+wait() ->
+    receive
+        a -> a
+    after 10 -> timeout
+    end.
+
+run() ->
+    self() ! b,
+    {poll(), wait()}.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    let expected = init_arc_process
+        .tuple_from_slice(&[Atom::str_to_term("empty"), Atom::str_to_term("timeout")])
+        .unwrap();
+    assert!(res.result == Ok(expected));
+}
+
+#[test]
+fn receive_timeout_value() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("receive_timeout_value").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(receive_timeout_value).
+
+wait(Timeout) ->
+    receive
+        a -> a
+    after Timeout -> timeout
+    end.
+
+caught() ->
+    try wait(-1)
+    catch
+        error:timeout_value -> caught
+    end.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(
+        init_arc_process.clone(),
+        module,
+        Atom::try_from_str("wait").unwrap(),
+        &[Atom::str_to_term("soon")],
+    );
+    assert!(res.result.is_err());
+    if let Err((typ, reason, _trace)) = res.result {
+        assert!(typ == Atom::str_to_term("error"));
+        assert!(reason == Atom::str_to_term("timeout_value"));
+    }
+
+    let res = crate::call_result::call_run_erlang(
+        init_arc_process.clone(),
+        module,
+        Atom::try_from_str("caught").unwrap(),
+        &[],
+    );
+    assert!(res.result == Ok(Atom::str_to_term("caught")));
+}
+
+#[test]
+fn receive_off_heap() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("receive_off_heap").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(receive_off_heap).
+
+reply(Parent) -> Parent ! {result, [1, 2], {a, b}}.
+
+run() ->
+    on_heap = process_flag(message_queue_data, off_heap),
+    spawn(receive_off_heap, reply, [self()]),
+    Received = receive
+        {result, List, Tuple} -> {List, Tuple}
+    end,
+    true = garbage_collect(),
+    Received.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    let list = init_arc_process
+        .list_from_slice(&[
+            init_arc_process.integer(1).unwrap(),
+            init_arc_process.integer(2).unwrap(),
+        ])
+        .unwrap();
+    let tuple = init_arc_process
+        .tuple_from_slice(&[Atom::str_to_term("a"), Atom::str_to_term("b")])
+        .unwrap();
+    let expected = init_arc_process.tuple_from_slice(&[list, tuple]).unwrap();
+    assert!(res.result == Ok(expected));
+}
+
+#[test]
+fn map_update() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("map_update").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(map_update).
+
+update(M) -> M#{a := 2}.
+
+existing() -> maps:get(a, update(#{a => 1})).
+
+missing() -> update(#{b => 1}).
+
+not_map() -> update(b).
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(
+        init_arc_process.clone(),
+        module,
+        Atom::try_from_str("existing").unwrap(),
+        &[],
+    );
+    let int = init_arc_process.integer(2).unwrap();
+    assert!(res.result == Ok(int));
+
+    let res = crate::call_result::call_run_erlang(
+        init_arc_process.clone(),
+        module,
+        Atom::try_from_str("missing").unwrap(),
+        &[],
+    );
+    assert!(res.result.is_err());
+    if let Err((typ, reason, _trace)) = res.result {
+        assert!(typ == Atom::str_to_term("error"));
+        let expected = init_arc_process
+            .tuple_from_slice(&[Atom::str_to_term("badkey"), Atom::str_to_term("a")])
+            .unwrap();
+        assert!(reason == expected);
+    }
+
+    let res = crate::call_result::call_run_erlang(
+        init_arc_process.clone(),
+        module,
+        Atom::try_from_str("not_map").unwrap(),
+        &[],
+    );
+    assert!(res.result.is_err());
+    if let Err((typ, reason, _trace)) = res.result {
+        assert!(typ == Atom::str_to_term("error"));
+        let expected = init_arc_process
+            .tuple_from_slice(&[Atom::str_to_term("badmap"), Atom::str_to_term("b")])
+            .unwrap();
+        assert!(reason == expected);
+    }
+}
+
+#[test]
+fn binary_construction() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("binary_construction").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(binary_construction).
+
+run() ->
+    Len = 2,
+    Bin = <<\"abc\">>,
+    <<Len:16, 1:12/little, -1:4, 1.0/float, 1.5:32/float-little, Bin:Len/binary, 228/utf8>>.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    let expected = init_arc_process
+        .binary_from_bytes(&[
+            0, 2, 1, 0x0F, 0x3F, 0xF0, 0, 0, 0, 0, 0, 0, 0, 0, 0xC0, 0x3F, 97, 98, 0xC3, 0xA4,
+        ])
+        .unwrap();
+    assert!(res.result == Ok(expected));
+}
+
+#[test]
+fn binary_matching() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("binary_matching").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(binary_matching).
+
+run() ->
+    {parse(<<0, 3, \"abcde\">>), parse(<<0, 9, \"abc\">>), decode(<<-2:12/little, 1.5/float, 228/utf8>>)}.
+
+parse(<<Len:16, Payload:Len/binary, Rest/binary>>) -> {Payload, Rest};
+parse(_) -> short.
+
+decode(<<I:12/little-signed, F/float, C/utf8>>) -> {I, F, C}.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    let payload = init_arc_process.binary_from_bytes(b"abc").unwrap();
+    let rest = init_arc_process.binary_from_bytes(b"de").unwrap();
+    let parsed = init_arc_process.tuple_from_slice(&[payload, rest]).unwrap();
+    let decoded = init_arc_process
+        .tuple_from_slice(&[
+            init_arc_process.integer(-2).unwrap(),
+            init_arc_process.float(1.5).unwrap(),
+            init_arc_process.integer(228).unwrap(),
+        ])
+        .unwrap();
+    let expected = init_arc_process
+        .tuple_from_slice(&[parsed, Atom::str_to_term("short"), decoded])
+        .unwrap();
+    assert!(res.result == Ok(expected));
+}
+
+#[test]
+fn tracing() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::trace::{FunctionPattern, TraceEvent, TraceSink};
+
+    struct VecSink(Arc<Mutex<Vec<String>>>);
+
+    impl TraceSink for VecSink {
+        fn event(&self, event: &TraceEvent) {
+            self.0.lock().unwrap().push(event.to_string());
+        }
+    }
+
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("tracing").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(tracing).
+
+run() ->
+    {Doubled} = double(2),
+    Doubled.
+
+double(X) ->
+    Y = X * 2,
+    {Y}.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let pattern = FunctionPattern {
+        module,
+        function: Some(Atom::try_from_str("double").unwrap()),
+        arity: None,
+    };
+
+    let previous_sink = {
+        let mut tracer = VM.tracer.write().unwrap();
+        tracer.trace_function(pattern, true);
+        tracer.set_sink(Box::new(VecSink(events.clone())))
+    };
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+
+    // The tracer is shared by all tests
+    {
+        let mut tracer = VM.tracer.write().unwrap();
+        tracer.trace_function(pattern, false);
+        tracer.set_sink(previous_sink);
+    }
+
+    assert!(res.result == Ok(init_arc_process.integer(4).unwrap()));
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].ends_with("call tracing:double(2)"));
+    assert!(events[1].contains("return tracing:double/1 -> "));
+}
+
+#[test]
+fn vm_call() {
+    use std::time::Duration;
+
+    use crate::call_result::{Budget, CallError};
+
+    &*VM;
+
+    let module = Atom::try_from_str("vm_call").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(vm_call).
+
+ok() -> ok.
+
+raise() -> erlang:error(oops).
+
+deadlock() ->
+    receive
+        never -> ok
+    end.
+
+forever() -> forever().
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let ok = VM
+        .call(
+            module,
+            Atom::try_from_str("ok").unwrap(),
+            &[],
+            Budget::Unlimited,
+        )
+        .unwrap();
+    assert!(ok.result == Ok(Atom::str_to_term("ok")));
+
+    let raise = VM
+        .call(
+            module,
+            Atom::try_from_str("raise").unwrap(),
+            &[],
+            Budget::Unlimited,
+        )
+        .unwrap();
+    match raise.result {
+        Err((class, reason, _)) => {
+            assert_eq!(class, Atom::str_to_term("error"));
+            assert_eq!(reason, Atom::str_to_term("oops"));
+        }
+        Ok(_) => panic!("expected exception"),
+    }
+
+    match VM.call(
+        module,
+        Atom::try_from_str("deadlock").unwrap(),
+        &[],
+        Budget::Unlimited,
+    ) {
+        Err(CallError::Deadlock) => (),
+        _ => panic!("expected deadlock"),
+    }
+
+    match VM.call(
+        module,
+        Atom::try_from_str("forever").unwrap(),
+        &[],
+        Budget::Reductions(10_000),
+    ) {
+        Err(CallError::BudgetExhausted) => (),
+        _ => panic!("expected reductions to be exhausted"),
+    }
+
+    match VM.call(
+        module,
+        Atom::try_from_str("forever").unwrap(),
+        &[],
+        Budget::Time(Duration::from_millis(10)),
+    ) {
+        Err(CallError::BudgetExhausted) => (),
+        _ => panic!("expected time to be exhausted"),
+    }
+}
+
+#[test]
+fn prim_ops() {
+    use crate::call_result::Budget;
+    use crate::format::ErlangFormat;
+
+    &*VM;
+
+    let eir_mod = compile(
+        "
+-module(prim_ops).
+
+run() ->
+    {classify(1.5),
+     classify(123456789012345678901234567890),
+     classify(1),
+     classify(1.0),
+     classify(2),
+     same(a, a),
+     same(a, b),
+     logic(true, false),
+     logic(false, false)}.
+
+classify(1.5) -> float;
+classify(123456789012345678901234567890) -> big;
+classify(X) when X =:= 1 -> exactly_one;
+classify(X) when X == 1 -> one;
+classify(X) when X > 1, X >= 2, X =/= 3, X /= 4, X < 5, X =< 6 -> small.
+
+same(X, X) -> true;
+same(_, _) -> false.
+
+logic(X, Y) when X xor Y -> xor;
+logic(X, Y) when X =:= Y -> eq.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = VM
+        .call(
+            Atom::try_from_str("prim_ops").unwrap(),
+            Atom::try_from_str("run").unwrap(),
+            &[],
+            Budget::Unlimited,
+        )
+        .unwrap();
+
+    assert_eq!(
+        ErlangFormat(res.result.unwrap()).to_string(),
+        "{float,big,exactly_one,one,small,true,false,xor,eq}"
+    );
+}
+
+#[test]
+fn logic_op_with_non_boolean_raises_badarg() {
+    use crate::call_result::Budget;
+    use crate::format::ErlangFormat;
+
+    &*VM;
+
+    let eir_mod = compile(
+        "
+-module(logic_op_badarg).
+
+run() ->
+    {raises(fun() -> logic(1, true) end),
+     raises(fun() -> logic(true, 1) end),
+     logic(true, false)}.
+
+raises(Fun) ->
+    try Fun() catch error:Reason -> Reason end.
+
+logic(X, Y) when X xor Y -> xor;
+logic(_, _) -> other.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = VM
+        .call(
+            Atom::try_from_str("logic_op_badarg").unwrap(),
+            Atom::try_from_str("run").unwrap(),
+            &[],
+            Budget::Unlimited,
+        )
+        .unwrap();
+
+    assert_eq!(
+        ErlangFormat(res.result.unwrap()).to_string(),
+        "{badarg,badarg,xor}"
+    );
+}
+
+#[test]
+fn closure_identity() {
+    use crate::call_result::Budget;
+    use crate::format::ErlangFormat;
+
+    &*VM;
+
+    let source = "
+-module(closure_identity).
+
+make(X) -> fun() -> X end.
+
+run() ->
+    F = make(1),
+    G = binary_to_term(term_to_binary(F)),
+    {F =:= make(1), F =:= make(2), G =:= F, G()}.
+";
+    let module = Atom::try_from_str("closure_identity").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(compile(source));
+
+    let res = VM.call(module, function, &[], Budget::Unlimited).unwrap();
+    assert_eq!(
+        ErlangFormat(res.result.unwrap()).to_string(),
+        "{true,false,true,1}"
+    );
+
+    // The same code has the same identity, so reloading it doesn't make an old version
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(compile(source));
+    assert!(!VM.modules.write().unwrap().purge(module));
+}
+
+#[test]
+fn error_handler() {
+    use crate::call_result::Budget;
+    use crate::format::ErlangFormat;
+
+    &*VM;
+
+    let source = "
+-module(error_handler_test).
+
+undefined_function(Module, Function, Arguments) ->
+    {handled, Module, Function, Arguments}.
+
+run() ->
+    error_handler = process_flag(error_handler, error_handler_test),
+    not_loaded:function(1, 2).
+";
+    let module = Atom::try_from_str("error_handler_test").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(compile(source));
+
+    let res = VM.call(module, function, &[], Budget::Unlimited).unwrap();
+    assert_eq!(
+        ErlangFormat(res.result.unwrap()).to_string(),
+        "{handled,not_loaded,function,[1,2]}"
+    );
+}
+
+#[test]
+fn erlang_format() {
+    use crate::format::ErlangFormat;
+
+    let arc_scheduler = Scheduler::current();
+    let process = arc_scheduler.spawn_init(0).unwrap();
+
+    let string = process.binary_from_str("ab").unwrap();
+    let list = process
+        .improper_list_from_slice(
+            &[Atom::str_to_term("ok"), Atom::str_to_term("Quoted")],
+            process.float(1.0e300).unwrap(),
+        )
+        .unwrap();
+    let tuple = process
+        .tuple_from_slice(&[process.integer(-1).unwrap(), string, list, Term::NIL])
+        .unwrap();
+
+    assert_eq!(
+        ErlangFormat(tuple).to_string(),
+        "{-1,<<97,98>>,[ok,'Quoted'|1.0e300],[]}"
+    );
+}
+
+#[test]
+fn shell() {
+    use crate::format::ErlangFormat;
+    use crate::shell::{Shell, ShellError};
+
+    let mut shell = Shell::new();
+    let eval =
+        |shell: &mut Shell, input: &str| ErlangFormat(shell.eval(input).unwrap()).to_string();
+
+    assert_eq!(eval(&mut shell, "X = 1."), "1");
+    assert_eq!(eval(&mut shell, "{Y, Z} = {X + 1, X + 2}."), "{2,3}");
+    assert_eq!(eval(&mut shell, "Y + Z."), "5");
+
+    let pid = shell.pid();
+    assert_eq!(eval(&mut shell, "self() ! hi, flush()."), "ok");
+    assert_eq!(shell.pid(), pid);
+
+    match shell.eval("erlang:error(oops).") {
+        Err(ShellError::Exception { class, reason, .. }) => {
+            assert_eq!(class, Atom::str_to_term("error"));
+            assert_eq!(reason, Atom::str_to_term("oops"));
+        }
+        _ => panic!("expected exception"),
+    }
+    assert_ne!(shell.pid(), pid);
+
+    // Bindings survive the replacement of the shell process
+    assert_eq!(eval(&mut shell, "X."), "1");
+}
+
+#[test]
+fn shell_bindings() {
+    use crate::format::ErlangFormat;
+    use crate::shell::Shell;
+
+    let mut shell = Shell::new();
+    let eval =
+        |shell: &mut Shell, input: &str| ErlangFormat(shell.eval(input).unwrap()).to_string();
+
+    // Variables bound in every clause are still bound after a `case` or `receive`
+    assert_eq!(
+        eval(&mut shell, "case {1, 2} of {A, B} -> ok; {B, A} -> ok end."),
+        "ok"
+    );
+    assert_eq!(eval(&mut shell, "A + B."), "3");
+    assert_eq!(
+        eval(
+            &mut shell,
+            "self() ! {3}, receive {C} -> ok after 0 -> C = 0 end."
+        ),
+        "ok"
+    );
+    assert_eq!(eval(&mut shell, "C."), "3");
+
+    // Variables bound in a fun are not
+    assert_eq!(eval(&mut shell, "F = fun() -> D = 4, D end, F()."), "4");
+    assert_eq!(eval(&mut shell, "D = 5."), "5");
+}
+
+#[test]
+fn shell_purges_eval_modules() {
+    use std::convert::TryInto;
+
+    use crate::shell::Shell;
+
+    let mut shell = Shell::new();
+    let eval_module =
+        |shell: &mut Shell, input: &str| -> Atom { shell.eval(input).unwrap().try_into().unwrap() };
+    let is_loaded = |module: Atom| VM.modules.read().unwrap().is_loaded(module);
+
+    let module = eval_module(&mut shell, "?MODULE.");
+    assert!(!is_loaded(module));
+
+    // The module of a bound fun is kept, so that the fun can still be called
+    let fun_module = eval_module(&mut shell, "F = fun() -> ?MODULE end, F().");
+    eval_module(&mut shell, "?MODULE.");
+    assert!(is_loaded(fun_module));
+    assert_eq!(eval_module(&mut shell, "F()."), fun_module);
+}
+
+#[test]
+fn beam_file() {
+    use crate::format::ErlangFormat;
+    use crate::shell::Shell;
+
+    let mut shell = Shell::new();
+    let eval =
+        |shell: &mut Shell, input: &str| ErlangFormat(shell.eval(input).unwrap()).to_string();
+
+    assert_eq!(
+        eval(
+            &mut shell,
+            "c(\"../liblumen_beam/tests/testdata/ast/test.beam\")."
+        ),
+        "{ok,test}"
+    );
+    assert_eq!(eval(&mut shell, "test:to_my_list([1, 2])."), "{1,{2,nil}}");
+    assert_eq!(eval(&mut shell, "test:sum([1, 2, 3])."), "6");
+    assert_eq!(eval(&mut shell, "test:op(1)."), "2");
+}
+
+#[test]
+fn hot_code_loading() {
+    use crate::call_result::{call_erlang, run_erlang, Budget};
+    use liblumen_alloc::erts::process::Status;
+
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("hot_code_loading").unwrap();
+    let function = Atom::try_from_str("wait").unwrap();
+    let version = |version: usize| {
+        compile(&format!(
+            "
+-module(hot_code_loading).
+
+version() -> {}.
+
+wait(Module) ->
+    receive
+        go -> {version(), hot_code_loading:version(), Module:version()}
+    end.
+",
+            version
+        ))
+    };
+
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(version(1));
+
+    let recv = call_erlang(
+        init_arc_process.clone(),
+        module,
+        function,
+        &[module.encode().unwrap()],
+    );
+    while *recv.process.status.read() != Status::Waiting {
+        arc_scheduler.run_through(&recv.process);
+    }
+
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(version(2));
+
+    // The receive continuation is still in version 1
+    assert!(VM
+        .modules
+        .read()
+        .unwrap()
+        .check_process_code(&recv.process, module));
+    assert!(!VM.modules.write().unwrap().soft_purge(module));
+
+    lumen_runtime::otp::erlang::send_2::native(
+        &init_arc_process,
+        recv.process.pid_term(),
+        Atom::str_to_term("go"),
+    )
+    .unwrap();
+
+    // The local call stays in version 1, while the fully-qualified calls run version 2
+    let res = run_erlang(recv, Budget::Unlimited).unwrap();
+    let expected = init_arc_process
+        .tuple_from_slice(&[
+            init_arc_process.integer(1).unwrap(),
+            init_arc_process.integer(2).unwrap(),
+            init_arc_process.integer(2).unwrap(),
+        ])
+        .unwrap();
+    assert!(res.result == Ok(expected));
+    assert!(VM.modules.write().unwrap().soft_purge(module));
+
+    let recv = call_erlang(
+        init_arc_process.clone(),
+        module,
+        function,
+        &[module.encode().unwrap()],
+    );
+    while *recv.process.status.read() != Status::Waiting {
+        arc_scheduler.run_through(&recv.process);
+    }
+
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(version(3));
+
+    assert!(VM.modules.write().unwrap().purge(module));
+    assert!(recv.process.is_exiting());
+    assert!(!VM.modules.write().unwrap().purge(module));
+}
+
+#[test]
+fn purged_fun() {
+    use std::convert::TryInto;
+
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("purged_fun").unwrap();
+    let version = |version: usize| {
+        compile(&format!(
+            "
+-module(purged_fun).
+
+make_fun() -> fun() -> {} end.
+
+call_fun(Fun) -> Fun().
+",
+            version
+        ))
+    };
+
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(version(1));
+
+    let res = crate::call_result::call_run_erlang(
+        init_arc_process.clone(),
+        module,
+        Atom::try_from_str("make_fun").unwrap(),
+        &[],
+    );
+    let fun = res.result.unwrap();
+
+    // Version 1 becomes the old version and is then purged
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(version(2));
+    VM.modules
+        .write()
+        .unwrap()
+        .register_erlang_module(version(3));
+
+    let res = crate::call_result::call_run_erlang(
+        init_arc_process.clone(),
+        module,
+        Atom::try_from_str("call_fun").unwrap(),
+        &[fun],
+    );
+    assert!(res.result.is_err());
+    if let Err((typ, reason, _trace)) = res.result {
+        assert!(typ == Atom::str_to_term("error"));
+        let reason_tuple: Boxed<Tuple> = reason.try_into().unwrap();
+        assert!(reason_tuple.iter().next().cloned() == Some(Atom::str_to_term("badfun")));
+    }
+}
+
+#[test]
+fn fun_index() {
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let module = Atom::try_from_str("fun_index").unwrap();
+    let function = Atom::try_from_str("run").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(fun_index).
+
+first() -> fun() -> first end.
+
+second() -> fun() -> second end.
+
+run() ->
+    {index, First} = erlang:fun_info(first(), index),
+    {index, Second} = erlang:fun_info(second(), index),
+    First < Second.
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    let res = crate::call_result::call_run_erlang(init_arc_process.clone(), module, function, &[]);
+    assert!(res.result == Ok(Atom::str_to_term("true")));
+}
+
+#[test]
+fn literal_arguments() {
+    use crate::literal::parse_terms;
+
+    &*VM;
+
+    let arc_scheduler = Scheduler::current();
+    let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
+
+    let terms = parse_terms(
+        &init_arc_process,
+        "{ok, 'Quoted'}, [1, -2 | 16#ff], \"ab\", <<1,\"c\">>, #{a => 2.5}",
+    )
+    .unwrap();
+    let expected = [
+        init_arc_process
+            .tuple_from_slice(&[Atom::str_to_term("ok"), Atom::str_to_term("Quoted")])
+            .unwrap(),
+        init_arc_process
+            .improper_list_from_slice(
+                &[
+                    init_arc_process.integer(1).unwrap(),
+                    init_arc_process.integer(-2).unwrap(),
+                ],
+                init_arc_process.integer(255).unwrap(),
+            )
+            .unwrap(),
+        init_arc_process.charlist_from_str("ab").unwrap(),
+        init_arc_process.binary_from_bytes(&[1, b'c']).unwrap(),
+        init_arc_process
+            .map_from_hash_map(
+                vec![(Atom::str_to_term("a"), init_arc_process.float(2.5).unwrap())]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap(),
+    ];
+    assert!(terms == expected);
+
+    // Expressions are not evaluated
+    assert!(parse_terms(&init_arc_process, "os:cmd(\"ls\")").is_err());
+    assert!(parse_terms(&init_arc_process, "X").is_err());
+    assert!(parse_terms(&init_arc_process, "1 + 2").is_err());
+}
+
+#[test]
+fn large_result() {
+    use std::convert::TryInto;
+
+    &*VM;
+
+    let module = Atom::try_from_str("large_result").unwrap();
+
+    let eir_mod = compile(
+        "
+-module(large_result).
+
+seq(0) -> [];
+seq(N) -> [{N, N} | seq(N - 1)].
+
+run() -> seq(1000).
+
+raise() -> erlang:error(seq(1000)).
+",
+    );
+
+    VM.modules.write().unwrap().register_erlang_module(eir_mod);
+
+    // Results larger than a fixed-size fragment are copied whole
+    for function in &["run", "raise"] {
+        let res = VM
+            .call(
+                module,
+                Atom::try_from_str(function).unwrap(),
+                &[],
+                crate::call_result::Budget::Unlimited,
+            )
+            .unwrap();
+        let list = match res.result {
+            Ok(list) => list,
+            Err((_, reason, _)) => reason,
+        };
+        let cons: Boxed<Cons> = list.try_into().unwrap();
+        assert_eq!(cons.into_iter().count(), 1000);
+    }
+}
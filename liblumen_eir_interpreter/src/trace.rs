@@ -0,0 +1,159 @@
+//! Run-time selectable tracing of interpreted function calls.
+//!
+//! Tracing is off by default.  It is enabled per process with [`Tracer::trace_process`] or per
+//! module/function with [`Tracer::trace_function`] and every call, return and exception of a
+//! traced function is written to the [`TraceSink`] of the [`Tracer`].
+
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+/// The functions matched by a pattern.  `None` matches any function name or arity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionPattern {
+    pub module: Atom,
+    pub function: Option<Atom>,
+    pub arity: Option<usize>,
+}
+
+impl FunctionPattern {
+    fn matches(&self, module: Atom, function: Atom, arity: usize) -> bool {
+        self.module == module
+            && self.function.map_or(true, |pattern| pattern == function)
+            && self.arity.map_or(true, |pattern| pattern == arity)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TraceEventKind<'a> {
+    Call { arguments: &'a [Term] },
+    Return { value: Term },
+    Exception { class: Term, reason: Term },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TraceEvent<'a> {
+    pub pid: Pid,
+    pub module: Atom,
+    pub function: Atom,
+    pub arity: usize,
+    pub kind: TraceEventKind<'a>,
+}
+
+impl Display for TraceEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            TraceEventKind::Call { arguments } => {
+                write!(f, "{} call {}:{}(", self.pid, self.module, self.function)?;
+
+                for (index, argument) in arguments.iter().enumerate() {
+                    if 0 < index {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", argument)?;
+                }
+
+                write!(f, ")")
+            }
+            TraceEventKind::Return { value } => write!(
+                f,
+                "{} return {}:{}/{} -> {}",
+                self.pid, self.module, self.function, self.arity, value
+            ),
+            TraceEventKind::Exception { class, reason } => write!(
+                f,
+                "{} exception {}:{}/{} {}:{}",
+                self.pid, self.module, self.function, self.arity, class, reason
+            ),
+        }
+    }
+}
+
+/// Receives the events of traced functions.
+pub trait TraceSink: Send + Sync {
+    fn event(&self, event: &TraceEvent);
+}
+
+/// Writes each event as a line to standard output.
+pub struct PutsSink;
+
+impl TraceSink for PutsSink {
+    fn event(&self, event: &TraceEvent) {
+        lumen_runtime::system::io::puts(&event.to_string());
+    }
+}
+
+pub struct Tracer {
+    sink: Box<dyn TraceSink>,
+    pids: HashSet<Pid>,
+    function_patterns: HashSet<FunctionPattern>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self {
+            sink: Box::new(PutsSink),
+            pids: HashSet::new(),
+            function_patterns: HashSet::new(),
+        }
+    }
+
+    /// Replaces the sink, returning the previous one so that it can be restored.
+    pub fn set_sink(&mut self, sink: Box<dyn TraceSink>) -> Box<dyn TraceSink> {
+        std::mem::replace(&mut self.sink, sink)
+    }
+
+    /// Enables or disables tracing of all functions called by the process with `pid`.
+    pub fn trace_process(&mut self, pid: Pid, enabled: bool) {
+        if enabled {
+            self.pids.insert(pid);
+        } else {
+            self.pids.remove(&pid);
+        }
+    }
+
+    /// Enables or disables tracing of the functions matching `pattern` in all processes.
+    pub fn trace_function(&mut self, pattern: FunctionPattern, enabled: bool) {
+        if enabled {
+            self.function_patterns.insert(pattern);
+        } else {
+            self.function_patterns.remove(&pattern);
+        }
+    }
+
+    /// Whether anything is traced, so that callers can skip building events.
+    pub fn is_enabled(&self) -> bool {
+        !(self.pids.is_empty() && self.function_patterns.is_empty())
+    }
+
+    pub fn is_traced(&self, process: &Process, module: Atom, function: Atom, arity: usize) -> bool {
+        self.pids.contains(&process.pid())
+            || self
+                .function_patterns
+                .iter()
+                .any(|pattern| pattern.matches(module, function, arity))
+    }
+
+    /// Writes `kind` to the sink if the function is traced in `process`.
+    pub fn trace(
+        &self,
+        process: &Process,
+        module: Atom,
+        function: Atom,
+        arity: usize,
+        kind: TraceEventKind,
+    ) {
+        if self.is_traced(process, module, function, arity) {
+            self.sink.event(&TraceEvent {
+                pid: process.pid(),
+                module,
+                function,
+                arity,
+                kind,
+            });
+        }
+    }
+}
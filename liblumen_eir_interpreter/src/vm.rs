@@ -1,35 +1,34 @@
-use std::rc::Rc;
 use std::sync::{Arc, RwLock};
 
-use libeir_ir::FunctionIdent;
-
-use liblumen_alloc::atom;
-use liblumen_alloc::erts::exception::RuntimeException;
-use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
-use lumen_runtime::process::spawn::options::Options;
-use lumen_runtime::scheduler::{Scheduler, Spawned};
-use lumen_runtime::system;
+use lumen_runtime::scheduler::Scheduler;
 
+use super::call_result::{call_erlang, run_erlang, Budget, CallError, ProcessResult};
 use super::module::ModuleRegistry;
+use super::trace::Tracer;
 
 pub struct VMState {
     pub modules: RwLock<ModuleRegistry>,
     pub closure_hack: RwLock<Vec<Vec<Term>>>,
     pub init: Arc<Process>,
+    pub tracer: RwLock<Tracer>,
 }
 
 impl VMState {
     pub fn new() -> Self {
         lumen_runtime::otp::erlang::apply_3::set_code(crate::code::apply);
+        lumen_runtime::shell::set_start(crate::shell::run);
 
         let mut modules = ModuleRegistry::new();
         modules.register_native_module(crate::native::make_erlang());
+        modules.register_native_module(crate::native::make_code());
         modules.register_native_module(crate::native::make_lists());
         modules.register_native_module(crate::native::make_maps());
         modules.register_native_module(crate::native::make_logger());
         modules.register_native_module(crate::native::make_lumen_intrinsics());
+        modules.register_native_module(crate::native::make_lumen_shell());
 
         let arc_scheduler = Scheduler::current();
         let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
@@ -38,87 +37,24 @@ impl VMState {
             modules: RwLock::new(modules),
             closure_hack: RwLock::new(Vec::new()),
             init: init_arc_process,
+            tracer: RwLock::new(Tracer::new()),
         }
     }
 
+    /// Runs `module:function(args...)` in a new process until it returns or raises, or `budget`
+    /// runs out.
     pub fn call(
-        &mut self,
-        fun: &FunctionIdent,
+        &self,
+        module: Atom,
+        function: Atom,
         args: &[Term],
-    ) -> Result<Rc<Term>, (Rc<Term>, Rc<Term>, Rc<Term>)> {
+        budget: Budget,
+    ) -> Result<ProcessResult, CallError> {
         let arc_scheduler = Scheduler::current();
         let init_arc_process = arc_scheduler.spawn_init(0).unwrap();
 
-        let module = Atom::try_from_str(&fun.module.as_str()).unwrap();
-        let function = Atom::try_from_str(&fun.name.as_str()).unwrap();
-        let arguments = init_arc_process
-            .list_from_slice(args)
-            // if not enough memory here, resize `spawn_init` heap
-            .unwrap();
-
-        let mut options: Options = Default::default();
-        options.min_heap_size = Some(4 + 1000 * 2);
-
-        let Spawned {
-            arc_process: run_arc_process,
-            ..
-        } = Scheduler::spawn_apply_3(
-            &init_arc_process,
-            options,
-            module,
-            function,
-            arguments)
-            // if this fails  a bigger sized heap
-            .unwrap();
+        let recv = call_erlang(init_arc_process, module, function, args);
 
-        loop {
-            let ran = Scheduler::current().run_through(&run_arc_process);
-
-            match *run_arc_process.status.read() {
-                Status::Exiting(ref exception) => match exception {
-                    RuntimeException::Exit(err) => {
-                        let reason = err.reason();
-                        if reason != atom!("normal") {
-                            panic!("Process exited: {:?}", reason);
-                        } else {
-                            panic!("yay!");
-                        }
-                    }
-                    _ => {
-                        panic!(
-                            "Process exception: {:?}\n{:?}",
-                            exception,
-                            run_arc_process.stacktrace()
-                        );
-                    }
-                },
-                Status::Waiting => {
-                    if ran {
-                        system::io::puts(&format!(
-                            "WAITING Run queues len = {:?}",
-                            Scheduler::current().run_queues_len()
-                        ));
-                    } else {
-                        panic!(
-                            "{:?} did not run.  Deadlock likely in {:#?}",
-                            run_arc_process,
-                            Scheduler::current()
-                        );
-                    }
-                }
-                Status::Runnable => {
-                    system::io::puts(&format!(
-                        "RUNNABLE Run queues len = {:?}",
-                        Scheduler::current().run_queues_len()
-                    ));
-                }
-                Status::Running => {
-                    system::io::puts(&format!(
-                        "RUNNING Run queues len = {:?}",
-                        Scheduler::current().run_queues_len()
-                    ));
-                }
-            }
-        }
+        run_erlang(recv, budget)
     }
 }
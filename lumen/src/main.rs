@@ -95,6 +95,17 @@ fn main() -> anyhow::Result<()> {
                         .multiple(true),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("shell")
+                .about("Starts an interactive shell on the interpreter")
+                .arg(
+                    Arg::with_name("remote")
+                        .help("Connects a remote shell to the specified node")
+                        .long("remote")
+                        .value_name("NODE")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
 
     // Handle success/failure
@@ -118,6 +129,13 @@ fn main() -> anyhow::Result<()> {
 fn dispatch(matches: ArgMatches) -> anyhow::Result<()> {
     match matches.subcommand() {
         ("compile", Some(args)) => compiler::dispatch(&args),
+        ("shell", Some(_args)) => {
+            // Sets the interpreter as the runtime's shell
+            &*liblumen_eir_interpreter::VM;
+
+            // The runtime parses `shell` and `--remote` from the same arguments
+            lumen_runtime::main(crate_name!(), crate_version!(), std::env::args().collect())
+        }
         _ => Ok(()),
     }
 }
@@ -7,6 +7,8 @@ use std::path::Path;
 
 use clap::{App, AppSettings, Arg, SubCommand};
 
+use crate::scheduler;
+
 pub type ConfigResult<T> = std::result::Result<T, ConfigError>;
 //TODO: Needs to be HashMap<Atom, HashMap<Atom, Term>>
 pub type AppConfig = HashMap<String, HashMap<String, String>>;
@@ -57,6 +59,8 @@ pub struct Config {
     pub debug: bool,
     pub name: Option<String>,
     pub cookie: Option<String>,
+    /// The number of scheduler threads, which defaults to one per logical CPU
+    pub schedulers: usize,
     pub command: Command,
     pub extra: Vec<String>,
 }
@@ -98,6 +102,12 @@ impl Config {
                      .help("The secret cookie to use in distributed mode")
                      .takes_value(true)
                      .env("COOKIE"))
+            .arg(Arg::with_name("schedulers")
+                     .long("schedulers")
+                     .help("The number of scheduler threads to run processes on\n\
+                            Defaults to the number of logical CPUs")
+                     .takes_value(true)
+                     .validator(is_valid_schedulers))
             .arg(Arg::with_name("extra")
                      .last(true)
                      .multiple(true)
@@ -138,6 +148,10 @@ impl Config {
             debug: matches.is_present("debug"),
             name: matches.value_of("name").map(|v| v.to_string()),
             cookie: matches.value_of("cookie").map(|v| v.to_string()),
+            schedulers: matches
+                .value_of("schedulers")
+                .map(|v| v.parse().unwrap())
+                .unwrap_or_else(scheduler::default_threads),
             command,
             extra: extra.iter().map(|v| v.to_string()).collect(),
         })
@@ -149,6 +163,14 @@ fn is_valid_node_name(_f: String) -> Result<(), String> {
     Ok(())
 }
 
+fn is_valid_schedulers(v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(0) => Err("There must be at least 1 scheduler".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Invalid number of schedulers: {}", err)),
+    }
+}
+
 fn with_file<T>(v: Option<&OsStr>, default: T, fun: fn(String) -> T) -> ConfigResult<T> {
     match v {
         None => Ok(default),
@@ -55,6 +55,8 @@ mod run;
 // `pub` for `examples/spawn-chain`
 pub mod scheduler;
 mod send;
+// `pub` for `liblumen_eir_interpreter` to set the shell
+pub mod shell;
 mod stacktrace;
 // `pub` for `examples/spawn-chain`
 pub mod system;
@@ -64,10 +66,10 @@ mod term;
 #[cfg(test)]
 mod test;
 pub mod time;
-// Public so that external code can all `timer::expire` to expire timers
-mod timer;
+// `pub` for receive timeouts in `liblumen_eir_interpreter`
+pub mod timer;
 
-use self::config::Config;
+use self::config::{Command, Config};
 use self::logging::Logger;
 use self::system::break_handler;
 
@@ -104,7 +106,7 @@ cfg_if! {
 /// The main entry point for the runtime, it is invoked by the platform-specific shims found above
 pub fn main(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result<()> {
     // Load configuration
-    let _config = Config::from_argv(name.to_string(), version.to_string(), argv)?;
+    let config = Config::from_argv(name.to_string(), version.to_string(), argv)?;
 
     // This bus is used to receive signals across threads in the system
     let mut bus: Bus<break_handler::Signal> = Bus::new(1);
@@ -116,8 +118,19 @@ pub fn main(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result<()>
     // Start logger
     Logger::init(Level::Info).expect("Unexpected failure initializing logger");
 
-    // TEMP: Blocking loop which waits for user input
-    loop {
-        let _ = rx1.recv()?;
+    match config.command {
+        Command::Run => {
+            let _schedulers = scheduler::spawn_threads(config.schedulers)?;
+
+            // TEMP: Blocking loop which waits for user input
+            loop {
+                let _ = rx1.recv()?;
+            }
+        }
+        Command::Shell => shell::start(),
+        Command::RemoteShell(target) => Err(anyhow::anyhow!(
+            "Cannot connect a remote shell to {} because distribution is not supported",
+            target
+        )),
     }
 }
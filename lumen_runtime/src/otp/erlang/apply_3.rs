@@ -97,7 +97,30 @@ pub fn code(arc_process: &Arc<Process>) -> code::Result {
 
             Process::call_code(arc_process)
         }
-        None => undef(arc_process, module, function, argument_list),
+        None => {
+            let error_handler = arc_process.error_handler();
+            let undefined_function = Atom::try_from_str("undefined_function").unwrap();
+
+            // Like BEAM, calls to undefined functions are passed to
+            // `ErrorHandler:undefined_function(Module, Function, Arguments)` when the process's
+            // error handler is loaded.
+            match crate::code::export::get(&error_handler, &undefined_function, 3) {
+                Some(code) => {
+                    crate::code::export::place_frame_with_arguments(
+                        &arc_process,
+                        Placement::Replace,
+                        error_handler,
+                        undefined_function,
+                        3,
+                        code,
+                        vec![module, function, argument_list],
+                    )?;
+
+                    Process::call_code(arc_process)
+                }
+                None => undef(arc_process, module, function, argument_list),
+            }
+        }
     }
 }
 
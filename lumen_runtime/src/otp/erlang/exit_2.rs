@@ -0,0 +1,46 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::process::exit_signal;
+use crate::registry::{atom_to_process, pid_to_process};
+
+/// Sends an exit signal with `reason` to `pid_or_port`, which can also be a registered name.
+/// Exit signals to processes that no longer exist are ignored.
+#[native_implemented_function(exit/2)]
+pub fn native(process: &Process, pid_or_port: Term, reason: Term) -> exception::Result<Term> {
+    match pid_or_port.decode()? {
+        TypedTerm::Pid(pid) => {
+            if pid == process.pid() {
+                exit_signal(process, process, reason)?;
+            } else if let Some(pid_arc_process) = pid_to_process(&pid) {
+                exit_signal(process, &pid_arc_process, reason)?;
+            }
+
+            Ok(true.into())
+        }
+        TypedTerm::Atom(name) => match atom_to_process(&name) {
+            Some(name_arc_process) => {
+                exit_signal(process, &name_arc_process, reason)?;
+
+                Ok(true.into())
+            }
+            None => Err(badarg!().into()),
+        },
+        // Processes on other nodes cannot be reached, so, like processes that no longer exist,
+        // their exit signals are dropped
+        TypedTerm::ExternalPid(_) => Ok(true.into()),
+        // Ports do not take exit signals yet
+        _ => Err(badarg!().into()),
+    }
+}
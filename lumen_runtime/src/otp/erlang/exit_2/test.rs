@@ -0,0 +1,188 @@
+use proptest::prop_assert_eq;
+use proptest::strategy::Strategy;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::exit_2::native;
+use crate::process;
+use crate::scheduler::{with_process, with_process_arc};
+use crate::test::{external_arc_node, has_message, strategy};
+
+#[test]
+fn without_pid_port_or_atom_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term(arc_process.clone()).prop_filter(
+                    "Cannot be pid, port or atom",
+                    |pid_or_port| {
+                        !(pid_or_port.is_pid() || pid_or_port.is_port() || pid_or_port.is_atom())
+                    },
+                ),
+                |pid_or_port| {
+                    prop_assert_eq!(
+                        native(&arc_process, pid_or_port, Atom::str_to_term("reason")),
+                        Err(badarg!().into())
+                    );
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_unregistered_name_errors_badarg() {
+    with_process(|process| {
+        assert_eq!(
+            native(
+                process,
+                Atom::str_to_term("exit_2_unregistered"),
+                Atom::str_to_term("reason")
+            ),
+            Err(badarg!().into())
+        );
+    });
+}
+
+#[test]
+fn with_external_pid_returns_true() {
+    with_process(|process| {
+        assert_eq!(
+            native(
+                process,
+                process.external_pid(external_arc_node(), 2, 3).unwrap(),
+                Atom::str_to_term("reason")
+            ),
+            Ok(true.into())
+        );
+        assert!(!process.is_exiting());
+    });
+}
+
+#[test]
+fn with_non_existent_pid_returns_true() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, Pid::next_term(), Atom::str_to_term("reason")),
+            Ok(true.into())
+        );
+        assert!(!process.is_exiting());
+    });
+}
+
+#[test]
+fn with_normal_reason_does_not_exit_other_process() {
+    with_process(|process| {
+        let other_arc_process = process::test(process);
+
+        assert_eq!(
+            native(
+                process,
+                other_arc_process.pid_term(),
+                Atom::str_to_term("normal")
+            ),
+            Ok(true.into())
+        );
+        assert!(!other_arc_process.is_exiting());
+    });
+}
+
+#[test]
+fn with_normal_reason_exits_self() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, process.pid_term(), Atom::str_to_term("normal")),
+            Ok(true.into())
+        );
+        assert_eq!(exit_reason(process), Some(Atom::str_to_term("normal")));
+    });
+}
+
+#[test]
+fn with_reason_exits_other_process() {
+    with_process(|process| {
+        let other_arc_process = process::test(process);
+        let reason = Atom::str_to_term("reason");
+
+        assert_eq!(
+            native(process, other_arc_process.pid_term(), reason),
+            Ok(true.into())
+        );
+        assert_eq!(exit_reason(&other_arc_process), Some(reason));
+        assert!(!process.is_exiting());
+    });
+}
+
+#[test]
+fn with_registered_name_exits_process() {
+    with_process(|process| {
+        let other_arc_process = process::test(process);
+        let name = Atom::try_from_str("exit_2_registered").unwrap();
+        let reason = Atom::str_to_term("reason");
+
+        assert!(crate::registry::put_atom_to_process(
+            name,
+            other_arc_process.clone()
+        ));
+
+        assert_eq!(
+            native(process, Atom::str_to_term("exit_2_registered"), reason),
+            Ok(true.into())
+        );
+        assert_eq!(exit_reason(&other_arc_process), Some(reason));
+    });
+}
+
+#[test]
+fn with_trapping_process_sends_exit_message() {
+    with_process(|process| {
+        let other_arc_process = process::test(process);
+        other_arc_process.trap_exit(true);
+
+        for reason in &[Atom::str_to_term("normal"), Atom::str_to_term("reason")] {
+            assert_eq!(
+                native(process, other_arc_process.pid_term(), *reason),
+                Ok(true.into())
+            );
+            assert!(!other_arc_process.is_exiting());
+
+            let message = process
+                .tuple_from_slice(&[Atom::str_to_term("EXIT"), process.pid_term(), *reason])
+                .unwrap();
+            assert!(has_message(&other_arc_process, message));
+        }
+    });
+}
+
+#[test]
+fn with_kill_reason_exits_trapping_process_with_killed() {
+    with_process(|process| {
+        let other_arc_process = process::test(process);
+        other_arc_process.trap_exit(true);
+
+        assert_eq!(
+            native(
+                process,
+                other_arc_process.pid_term(),
+                Atom::str_to_term("kill")
+            ),
+            Ok(true.into())
+        );
+        assert_eq!(
+            exit_reason(&other_arc_process),
+            Some(Atom::str_to_term("killed"))
+        );
+    });
+}
+
+fn exit_reason(process: &Process) -> Option<Term> {
+    match *process.status.read() {
+        Status::Exiting(ref exception) => exception.reason(),
+        _ => None,
+    }
+}
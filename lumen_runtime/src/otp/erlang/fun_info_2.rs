@@ -0,0 +1,55 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::closure::{Creator, Definition};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, badarg};
+
+use lumen_runtime_macros::native_implemented_function;
+
+/// Returns `{Item, Info}` for `fun`.  Export funs have no `index`, `new_index`, `new_uniq`, `pid`
+/// or `uniq`, so those are `undefined`.
+#[native_implemented_function(fun_info/2)]
+pub fn native(process: &Process, fun: Term, item: Term) -> exception::Result<Term> {
+    let closure: Boxed<Closure> = fun.try_into()?;
+    let item_atom: Atom = item.try_into()?;
+
+    let info = match (item_atom.name(), closure.definition()) {
+        ("arity", _) => closure.arity().into(),
+        ("env", _) => process.list_from_slice(closure.env_slice())?,
+        ("module", _) => closure.module().encode()?,
+        ("name", _) => closure.function().encode()?,
+        ("type", Definition::Export { .. }) => atom!("external"),
+        ("type", Definition::Anonymous { .. }) => atom!("local"),
+        ("index", Definition::Anonymous { index, .. })
+        | ("new_index", Definition::Anonymous { index, .. }) => process.integer(*index)?,
+        ("new_uniq", Definition::Anonymous { unique, .. }) => process.binary_from_bytes(unique)?,
+        ("uniq", Definition::Anonymous { old_unique, .. }) => process.integer(*old_unique)?,
+        ("pid", Definition::Anonymous { creator, .. }) => match creator {
+            Creator::Local(pid) => pid.encode()?,
+            Creator::External(external_pid) => process.external_pid(
+                external_pid.arc_node(),
+                external_pid.number() as usize,
+                external_pid.serial() as usize,
+            )?,
+        },
+        ("index", Definition::Export { .. })
+        | ("new_index", Definition::Export { .. })
+        | ("new_uniq", Definition::Export { .. })
+        | ("uniq", Definition::Export { .. })
+        | ("pid", Definition::Export { .. }) => atom!("undefined"),
+        _ => return Err(badarg!().into()),
+    };
+
+    process
+        .tuple_from_slice(&[item, info])
+        .map_err(|error| error.into())
+}
@@ -0,0 +1,144 @@
+use std::convert::TryInto;
+
+use proptest::prop_assert_eq;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::closure::Creator;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::fun_info_2::native;
+use crate::scheduler::{with_process, with_process_arc};
+use crate::test::strategy;
+
+#[test]
+fn without_function_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term::is_not_function(arc_process.clone()),
+                |function| {
+                    prop_assert_eq!(
+                        native(&arc_process, function, Atom::str_to_term("arity")),
+                        Err(badarg!().into())
+                    );
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_function_and_unknown_item_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term::is_function(arc_process.clone()),
+                |function| {
+                    prop_assert_eq!(
+                        native(&arc_process, function, Atom::str_to_term("unknown")),
+                        Err(badarg!().into())
+                    );
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_anonymous_function_returns_definition() {
+    with_process(|process| {
+        let module = Atom::try_from_str("module").unwrap();
+        let index = 1;
+        let old_unique = 2;
+        let unique = [3; 16];
+        let env = [Atom::str_to_term("env")];
+        let function = process
+            .anonymous_closure_with_env_from_slice(
+                module,
+                index,
+                old_unique,
+                unique,
+                1,
+                None,
+                Creator::Local(process.pid()),
+                &env,
+            )
+            .unwrap();
+
+        assert_eq!(
+            info(process, function, "arity"),
+            process.integer(1).unwrap()
+        );
+        assert_eq!(
+            info(process, function, "env"),
+            process.list_from_slice(&env).unwrap()
+        );
+        assert_eq!(info(process, function, "module"), module.encode().unwrap());
+        assert_eq!(info(process, function, "type"), Atom::str_to_term("local"));
+        assert_eq!(
+            info(process, function, "index"),
+            process.integer(index).unwrap()
+        );
+        assert_eq!(
+            info(process, function, "uniq"),
+            process.integer(old_unique).unwrap()
+        );
+        assert_eq!(
+            info(process, function, "new_uniq"),
+            process.binary_from_bytes(&unique).unwrap()
+        );
+        assert_eq!(info(process, function, "pid"), process.pid_term());
+    });
+}
+
+#[test]
+fn with_export_function_returns_undefined_for_anonymous_items() {
+    with_process(|process| {
+        let module = Atom::try_from_str("module").unwrap();
+        let function_name = Atom::try_from_str("function").unwrap();
+        let function = process
+            .export_closure(module, function_name, 0, None)
+            .unwrap();
+
+        assert_eq!(
+            info(process, function, "arity"),
+            process.integer(0).unwrap()
+        );
+        assert_eq!(info(process, function, "env"), Term::NIL);
+        assert_eq!(
+            info(process, function, "name"),
+            function_name.encode().unwrap()
+        );
+        assert_eq!(
+            info(process, function, "type"),
+            Atom::str_to_term("external")
+        );
+
+        for item in &["index", "new_index", "new_uniq", "pid", "uniq"] {
+            assert_eq!(
+                info(process, function, item),
+                Atom::str_to_term("undefined")
+            );
+        }
+    });
+}
+
+/// The `Info` of the `{Item, Info}` returned for `item`
+fn info(process: &Process, function: Term, item: &str) -> Term {
+    let item_term = Atom::str_to_term(item);
+    let tuple: Boxed<Tuple> = native(process, function, item_term)
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    assert_eq!(tuple.len(), 2);
+    assert_eq!(tuple[0], item_term);
+
+    tuple[1]
+}
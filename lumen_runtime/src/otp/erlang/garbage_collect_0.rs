@@ -0,0 +1,53 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::sync::Arc;
+
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::code;
+use liblumen_alloc::erts::process::code::stack::frame::{Frame, Placement};
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{Arity, ModuleFunctionArity};
+
+use crate::otp::erlang::garbage_collect_2::garbage_collect;
+
+pub fn place_frame_with_arguments(process: &Process, placement: Placement) -> AllocResult<()> {
+    process.place_frame(frame(), placement);
+
+    Ok(())
+}
+
+pub fn export() {
+    crate::code::export::insert(super::module(), function(), ARITY, code);
+}
+
+// Private
+
+const ARITY: Arity = 0;
+
+fn code(arc_process: &Arc<Process>) -> code::Result {
+    arc_process.reduce();
+
+    garbage_collect(arc_process, arc_process.pid_term(), Term::NIL)
+}
+
+fn frame() -> Frame {
+    Frame::new(module_function_arity(), code)
+}
+
+fn function() -> Atom {
+    Atom::try_from_str("garbage_collect").unwrap()
+}
+
+fn module_function_arity() -> Arc<ModuleFunctionArity> {
+    Arc::new(ModuleFunctionArity {
+        module: super::module(),
+        function: function(),
+        arity: ARITY,
+    })
+}
@@ -0,0 +1,22 @@
+use liblumen_alloc::erts::process::code::stack::frame::Placement;
+
+use crate::future::{run_until_ready, Ready};
+use crate::otp::erlang::garbage_collect_0::place_frame_with_arguments;
+
+#[test]
+fn returns_true_after_collecting() {
+    let Ready {
+        arc_process,
+        result,
+    } = run_until_ready(
+        Default::default(),
+        |child_process| {
+            place_frame_with_arguments(child_process, Placement::Push).map_err(|e| e.into())
+        },
+        5_000,
+    )
+    .unwrap();
+
+    assert_eq!(result, Ok(true.into()));
+    assert_eq!(arc_process.major_gcs(), 1);
+}
@@ -0,0 +1,85 @@
+use std::mem;
+
+use proptest::prop_assert_eq;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
+use liblumen_alloc::erts::process::code::stack::frame::Placement;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::future::{run_until_ready, Ready};
+use crate::otp::erlang::garbage_collect_1::place_frame_with_arguments;
+use crate::process;
+use crate::scheduler::with_process_arc;
+use crate::test::strategy;
+
+#[test]
+fn without_pid_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(&strategy::term::is_not_pid(arc_process.clone()), |pid| {
+                let Ready {
+                    arc_process: child_arc_process,
+                    result,
+                } = run(|child_process| pid.clone_to_process(child_process));
+
+                prop_assert_eq!(result, Err(badarg!().into()));
+
+                mem::drop(child_arc_process);
+
+                Ok(())
+            })
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_dead_pid_returns_false() {
+    let Ready { result, .. } = run(|_| Pid::next_term());
+
+    assert_eq!(result, Ok(false.into()));
+}
+
+#[test]
+fn with_runnable_pid_returns_true_after_it_collects() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+        let pid = arc_process.pid_term();
+
+        let Ready { result, .. } = run(|_| pid);
+
+        assert_eq!(result, Ok(true.into()));
+        assert_eq!(arc_process.major_gcs(), 1);
+    });
+}
+
+#[test]
+fn with_waiting_pid_returns_true_after_it_collects() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+        arc_process.wait();
+        let pid = arc_process.pid_term();
+
+        let Ready { result, .. } = run(|_| pid);
+
+        assert_eq!(result, Ok(true.into()));
+        assert_eq!(arc_process.major_gcs(), 1);
+    });
+}
+
+fn run<F>(pid: F) -> Ready
+where
+    F: Fn(&Process) -> Term,
+{
+    run_until_ready(
+        Default::default(),
+        |child_process| {
+            place_frame_with_arguments(child_process, Placement::Push, pid(child_process))
+                .map_err(|e| e.into())
+        },
+        5_000,
+    )
+    .unwrap()
+}
@@ -0,0 +1,159 @@
+mod options;
+
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception::{self, AllocResult};
+use liblumen_alloc::erts::process::code;
+use liblumen_alloc::erts::process::code::stack::frame::{Frame, Placement};
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{Arity, ModuleFunctionArity};
+
+use crate::otp::erlang::garbage_collect_2::options::Options;
+use crate::process::garbage_collection;
+use crate::registry::pid_to_process;
+
+/// How `garbage_collect/0,1,2` completes after `request`.
+#[derive(Debug, PartialEq)]
+pub enum Requested {
+    /// An asynchronous request, for which `garbage_collect` returns `async` right away
+    Async,
+    /// A synchronous request, made as an asynchronous request with `request_id`.  The caller
+    /// waits for the reply with `garbage_collection::receive_reply` and returns whether the
+    /// collection ran.
+    Sync { request_id: Term },
+}
+
+pub fn place_frame_with_arguments(
+    process: &Process,
+    placement: Placement,
+    pid: Term,
+    options: Term,
+) -> AllocResult<()> {
+    process.stack_push(options)?;
+    process.stack_push(pid)?;
+    process.place_frame(frame(), placement);
+
+    Ok(())
+}
+
+pub fn export() {
+    crate::code::export::insert(super::module(), function(), ARITY, code);
+}
+
+/// Requests that the process identified by `pid` collect garbage.
+///
+/// The collection is scheduled to run on the scheduler of the process identified by `pid` once
+/// that process is not running, so when collecting the calling process, the collection runs after
+/// the calling process's current run.
+pub fn request(process: &Process, pid: Term, options: Term) -> exception::Result<Requested> {
+    let Options { r#type, request_id } = options.try_into()?;
+    let pid_pid: Pid = pid.try_into()?;
+
+    let (requested, request_id) = match request_id {
+        Some(request_id) => (Requested::Async, request_id),
+        None => {
+            let request_id = process.next_reference()?;
+
+            (Requested::Sync { request_id }, request_id)
+        }
+    };
+
+    let queued = match pid_to_process(&pid_pid) {
+        Some(target_arc_process) => {
+            garbage_collection::request(process, target_arc_process, r#type, Some(request_id))?
+        }
+        None => false,
+    };
+
+    // Requests that were not queued are answered immediately, as they would be once a queued
+    // request ran.
+    if !queued {
+        let tag = atom!("garbage_collect");
+        let message = process.tuple_from_slice(&[tag, request_id, false.into()])?;
+        process.send_from_self(message);
+    }
+
+    Ok(requested)
+}
+
+// Private
+
+const ARITY: Arity = 2;
+
+fn code(arc_process: &Arc<Process>) -> code::Result {
+    arc_process.reduce();
+
+    let pid = arc_process.stack_pop().unwrap();
+    let options = arc_process.stack_pop().unwrap();
+
+    garbage_collect(arc_process, pid, options)
+}
+
+/// Like BEAM, a synchronous collection returns once the collection ran, with `false` if the process
+/// exited before collecting.
+pub(in crate::otp::erlang) fn garbage_collect(
+    arc_process: &Arc<Process>,
+    pid: Term,
+    options: Term,
+) -> code::Result {
+    match request(arc_process, pid, options) {
+        Ok(Requested::Async) => {
+            arc_process.return_from_call(atom!("async"))?;
+
+            Process::call_code(arc_process)
+        }
+        Ok(Requested::Sync { request_id }) => {
+            let module_function_arity = arc_process.current_module_function_arity().unwrap();
+
+            arc_process.stack_push(request_id)?;
+            arc_process.replace_frame(Frame::new(module_function_arity, wait));
+
+            Process::call_code(arc_process)
+        }
+        Err(exception) => code::result_from_exception(arc_process, exception),
+    }
+}
+
+/// Expects the following on stack:
+/// * request ID of the synchronous request
+fn wait(arc_process: &Arc<Process>) -> code::Result {
+    arc_process.reduce();
+
+    let request_id = arc_process.stack_top().unwrap();
+
+    match garbage_collection::receive_reply(arc_process, request_id) {
+        Some(collected) => {
+            arc_process.stack_pop();
+            arc_process.return_from_call(collected.into())?;
+
+            Process::call_code(arc_process)
+        }
+        None => Ok(()),
+    }
+}
+
+fn frame() -> Frame {
+    Frame::new(module_function_arity(), code)
+}
+
+fn function() -> Atom {
+    Atom::try_from_str("garbage_collect").unwrap()
+}
+
+fn module_function_arity() -> Arc<ModuleFunctionArity> {
+    Arc::new(ModuleFunctionArity {
+        module: super::module(),
+        function: function(),
+        arity: ARITY,
+    })
+}
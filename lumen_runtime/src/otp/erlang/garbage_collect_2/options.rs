@@ -0,0 +1,68 @@
+use std::convert::{TryFrom, TryInto};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::exception::Exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::process::garbage_collection::Type;
+
+#[derive(Default)]
+pub struct Options {
+    pub r#type: Type,
+    pub request_id: Option<Term>,
+}
+
+impl Options {
+    fn put_option_term(&mut self, option: Term) -> Result<&Self, Exception> {
+        let tuple: Boxed<Tuple> = option.try_into()?;
+
+        if tuple.len() == 2 {
+            let name: Atom = tuple[0].try_into()?;
+            let value = tuple[1];
+
+            match name.name() {
+                "async" => {
+                    self.request_id = Some(value);
+
+                    Ok(self)
+                }
+                "type" => {
+                    let value_atom: Atom = value.try_into()?;
+
+                    self.r#type = match value_atom.name() {
+                        "major" => Type::Major,
+                        "minor" => Type::Minor,
+                        _ => return Err(badarg!().into()),
+                    };
+
+                    Ok(self)
+                }
+                _ => Err(badarg!().into()),
+            }
+        } else {
+            Err(badarg!().into())
+        }
+    }
+}
+
+impl TryFrom<Term> for Options {
+    type Error = Exception;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        let mut options: Options = Default::default();
+        let mut options_term = term;
+
+        loop {
+            match options_term.decode()? {
+                TypedTerm::Nil => return Ok(options),
+                TypedTerm::List(cons) => {
+                    options.put_option_term(cons.head)?;
+                    options_term = cons.tail;
+
+                    continue;
+                }
+                _ => return Err(badarg!().into()),
+            }
+        }
+    }
+}
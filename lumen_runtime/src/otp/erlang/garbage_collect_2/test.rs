@@ -0,0 +1,151 @@
+use proptest::prop_assert_eq;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::garbage_collect_2::{request, Requested};
+use crate::process;
+use crate::process::garbage_collection;
+use crate::scheduler::{with_process, with_process_arc, Scheduler};
+use crate::test::{has_message, strategy};
+
+#[test]
+fn without_proper_list_for_options_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term::is_not_proper_list(arc_process.clone()),
+                |options| {
+                    prop_assert_eq!(
+                        request(&arc_process, arc_process.pid_term(), options),
+                        Err(badarg!().into())
+                    );
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_unknown_type_errors_badarg() {
+    with_process(|process| {
+        let options = options(
+            process,
+            &[(Atom::str_to_term("type"), Atom::str_to_term("unknown"))],
+        );
+
+        assert_eq!(
+            request(process, process.pid_term(), options),
+            Err(badarg!().into())
+        );
+    });
+}
+
+#[test]
+fn with_minor_type_does_minor_collection() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+        let options = options(
+            &parent_arc_process,
+            &[
+                (Atom::str_to_term("type"), Atom::str_to_term("minor")),
+                (Atom::str_to_term("async"), Atom::str_to_term("request_id")),
+            ],
+        );
+
+        assert_eq!(
+            request(&parent_arc_process, arc_process.pid_term(), options),
+            Ok(Requested::Async)
+        );
+
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        assert_eq!(arc_process.minor_gcs(), 1);
+        assert_eq!(arc_process.major_gcs(), 0);
+    });
+}
+
+#[test]
+fn with_async_option_returns_async_and_sends_message_after_collection() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+        let request_id = Atom::str_to_term("request_id");
+        let options = options(
+            &parent_arc_process,
+            &[(Atom::str_to_term("async"), request_id)],
+        );
+
+        assert_eq!(
+            request(&parent_arc_process, arc_process.pid_term(), options),
+            Ok(Requested::Async)
+        );
+
+        let message = reply(&parent_arc_process, request_id, true);
+
+        assert!(!has_message(&parent_arc_process, message));
+
+        assert!(Scheduler::current().run_through(&arc_process));
+
+        assert_eq!(arc_process.major_gcs(), 1);
+        assert!(has_message(&parent_arc_process, message));
+    });
+}
+
+#[test]
+fn with_async_option_and_dead_pid_sends_false() {
+    with_process(|process| {
+        let request_id = Atom::str_to_term("request_id");
+        let options = options(process, &[(Atom::str_to_term("async"), request_id)]);
+
+        assert_eq!(
+            request(process, Pid::next_term(), options),
+            Ok(Requested::Async)
+        );
+        assert!(has_message(process, reply(process, request_id, false)));
+    });
+}
+
+#[test]
+fn without_async_option_and_pid_that_exits_before_collecting_sends_false() {
+    with_process_arc(|parent_arc_process| {
+        let arc_process = process::test(&parent_arc_process);
+
+        let request_id =
+            match request(&parent_arc_process, arc_process.pid_term(), Term::NIL).unwrap() {
+                Requested::Sync { request_id } => request_id,
+                requested => panic!("{:?} is not synchronous", requested),
+            };
+
+        arc_process.exit(Atom::str_to_term("kill"));
+        garbage_collection::collect_requested(&arc_process);
+
+        assert_eq!(arc_process.major_gcs(), 0);
+        assert!(has_message(
+            &parent_arc_process,
+            reply(&parent_arc_process, request_id, false)
+        ));
+    });
+}
+
+fn options(process: &Process, options: &[(Term, Term)]) -> Term {
+    let vec: Vec<Term> = options
+        .iter()
+        .map(|(name, value)| process.tuple_from_slice(&[*name, *value]).unwrap())
+        .collect();
+
+    process.list_from_slice(&vec).unwrap()
+}
+
+fn reply(process: &Process, request_id: Term, collected: bool) -> Term {
+    process
+        .tuple_from_slice(&[
+            Atom::str_to_term("garbage_collect"),
+            request_id,
+            collected.into(),
+        ])
+        .unwrap()
+}
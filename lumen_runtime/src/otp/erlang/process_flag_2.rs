@@ -9,24 +9,69 @@ use std::convert::TryInto;
 
 use liblumen_alloc::badarg;
 use liblumen_alloc::erts::exception;
-use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::process::{MaxHeapSize, MessageQueueData, Priority, Process};
 use liblumen_alloc::erts::term::prelude::*;
 
 use lumen_runtime_macros::native_implemented_function;
 
+use crate::otp::erlang::process_info::max_heap_size;
+
+/// The maximum number of calls that `save_calls` can save
+const MAX_SAVE_CALLS: usize = 10_000;
+
 #[native_implemented_function(process_flag/2)]
 pub fn native(process: &Process, flag: Term, value: Term) -> exception::Result<Term> {
     let flag_atom: Atom = flag.try_into()?;
 
     match flag_atom.name() {
-        "error_handler" => unimplemented!(),
-        "max_heap_size" => unimplemented!(),
-        "message_queue_data" => unimplemented!(),
-        "min_bin_vheap_size" => unimplemented!(),
-        "min_heap_size" => unimplemented!(),
-        "priority" => unimplemented!(),
-        "save_calls" => unimplemented!(),
-        "sensitive" => unimplemented!(),
+        "error_handler" => {
+            let value_atom: Atom = value.try_into()?;
+
+            Ok(process.set_error_handler(value_atom).encode()?)
+        }
+        "max_heap_size" => {
+            let value_max_heap_size: MaxHeapSize = value.try_into()?;
+
+            max_heap_size(process, process.set_max_heap_size(value_max_heap_size))
+        }
+        "message_queue_data" => {
+            let value_message_queue_data: MessageQueueData = value.try_into()?;
+            let old_atom: Atom = process
+                .set_message_queue_data(value_message_queue_data)
+                .into();
+
+            Ok(old_atom.encode()?)
+        }
+        "min_bin_vheap_size" => {
+            let value_usize: usize = value.try_into()?;
+
+            Ok(process.integer(process.set_min_vheap_size(value_usize))?)
+        }
+        "min_heap_size" => {
+            let value_usize: usize = value.try_into()?;
+
+            Ok(process.integer(process.set_min_heap_size(value_usize))?)
+        }
+        "priority" => {
+            let value_priority: Priority = value.try_into()?;
+            let old_atom: Atom = process.set_priority(value_priority).into();
+
+            Ok(old_atom.encode()?)
+        }
+        "save_calls" => {
+            let value_usize: usize = value.try_into()?;
+
+            if value_usize <= MAX_SAVE_CALLS {
+                Ok(process.integer(process.set_save_calls(value_usize))?)
+            } else {
+                Err(badarg!().into())
+            }
+        }
+        "sensitive" => {
+            let value_bool: bool = value.try_into()?;
+
+            Ok(process.sensitive(value_bool).into())
+        }
         "trap_exit" => {
             let value_bool: bool = value.try_into()?;
 
@@ -1,3 +1,7 @@
+mod with_max_heap_size_flag;
+mod with_priority_flag;
+mod with_save_calls_flag;
+mod with_sensitive_flag;
 mod with_trap_exit_flag;
 
 use super::*;
@@ -30,7 +34,8 @@ fn unsupported_flag_atom() -> BoxedStrategy<Term> {
             let atom_atom: Atom = (*atom).try_into().unwrap();
 
             match atom_atom.name() {
-                "trap_exit" => false,
+                "error_handler" | "max_heap_size" | "message_queue_data" | "min_bin_vheap_size"
+                | "min_heap_size" | "priority" | "save_calls" | "sensitive" | "trap_exit" => false,
                 _ => true,
             }
         })
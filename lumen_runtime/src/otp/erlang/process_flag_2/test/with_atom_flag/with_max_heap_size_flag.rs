@@ -0,0 +1,69 @@
+use super::*;
+
+use liblumen_alloc::erts::process::MaxHeapSize;
+
+#[test]
+fn with_size_returns_old_max_heap_size_map() {
+    with_process(|process| {
+        let old_value = process
+            .map_from_slice(&[
+                (Atom::str_to_term("size"), process.integer(0).unwrap()),
+                (Atom::str_to_term("kill"), true.into()),
+                (Atom::str_to_term("error_logger"), true.into()),
+            ])
+            .unwrap();
+
+        assert_eq!(
+            native(process, flag(), process.integer(1_000_000).unwrap()),
+            Ok(old_value)
+        );
+        assert_eq!(
+            process.max_heap_size(),
+            MaxHeapSize {
+                size: 1_000_000,
+                kill: true,
+                error_logger: true
+            }
+        );
+    });
+}
+
+#[test]
+fn with_map_sets_size_kill_and_error_logger() {
+    with_process(|process| {
+        let value = process
+            .map_from_slice(&[
+                (
+                    Atom::str_to_term("size"),
+                    process.integer(2_000_000).unwrap(),
+                ),
+                (Atom::str_to_term("kill"), false.into()),
+            ])
+            .unwrap();
+
+        assert!(native(process, flag(), value).is_ok());
+        assert_eq!(
+            process.max_heap_size(),
+            MaxHeapSize {
+                size: 2_000_000,
+                kill: false,
+                error_logger: true
+            }
+        );
+    });
+}
+
+#[test]
+fn with_map_with_unknown_key_errors_badarg() {
+    with_process(|process| {
+        let value = process
+            .map_from_slice(&[(Atom::str_to_term("unknown"), true.into())])
+            .unwrap();
+
+        assert_eq!(native(process, flag(), value), Err(badarg!().into()));
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("max_heap_size")
+}
@@ -0,0 +1,49 @@
+use super::*;
+
+use liblumen_alloc::erts::process::Priority;
+
+use crate::process;
+
+#[test]
+fn without_priority_value_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term(arc_process.clone()).prop_filter(
+                    "Value cannot be a priority",
+                    |value| {
+                        let result: Result<Priority, _> = (*value).try_into();
+
+                        result.is_err()
+                    },
+                ),
+                |value| {
+                    prop_assert_eq!(native(&arc_process, flag(), value), Err(badarg!().into()));
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_priority_value_returns_old_priority_and_changes_priority() {
+    let arc_process = process::test(&process::test_init());
+
+    assert_eq!(arc_process.priority(), Priority::Normal);
+    assert_eq!(
+        native(&arc_process, flag(), Atom::str_to_term("high")),
+        Ok(Atom::str_to_term("normal"))
+    );
+    assert_eq!(arc_process.priority(), Priority::High);
+    assert_eq!(
+        native(&arc_process, flag(), Atom::str_to_term("low")),
+        Ok(Atom::str_to_term("high"))
+    );
+    assert_eq!(arc_process.priority(), Priority::Low);
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("priority")
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+use crate::otp::erlang::process_info_2;
+
+#[test]
+fn with_more_than_10000_errors_badarg() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, flag(), process.integer(10_001).unwrap()),
+            Err(badarg!().into())
+        );
+    });
+}
+
+#[test]
+fn with_count_returns_old_count_and_last_calls_is_list() {
+    with_process(|process| {
+        let last_calls = Atom::str_to_term("last_calls");
+
+        assert_eq!(
+            process_info_2::native(process, process.pid_term(), last_calls),
+            Ok(process
+                .tuple_from_slice(&[last_calls, false.into()])
+                .unwrap())
+        );
+
+        assert_eq!(
+            native(process, flag(), process.integer(10).unwrap()),
+            Ok(process.integer(0).unwrap())
+        );
+
+        assert_eq!(
+            process_info_2::native(process, process.pid_term(), last_calls),
+            Ok(process.tuple_from_slice(&[last_calls, Term::NIL]).unwrap())
+        );
+
+        assert_eq!(
+            native(process, flag(), process.integer(0).unwrap()),
+            Ok(process.integer(10).unwrap())
+        );
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("save_calls")
+}
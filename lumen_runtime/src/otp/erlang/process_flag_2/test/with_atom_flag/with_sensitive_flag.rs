@@ -0,0 +1,47 @@
+use super::*;
+
+use crate::otp::erlang::process_info_2;
+
+#[test]
+fn without_boolean_value_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term::is_not_boolean(arc_process.clone()),
+                |value| {
+                    prop_assert_eq!(native(&arc_process, flag(), value), Err(badarg!().into()));
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_true_value_hides_messages() {
+    with_process(|process| {
+        let messages = Atom::str_to_term("messages");
+        let message = Atom::str_to_term("message");
+
+        process.send_from_self(message);
+
+        assert_eq!(native(process, flag(), true.into()), Ok(false.into()));
+        assert_eq!(
+            process_info_2::native(process, process.pid_term(), messages),
+            Ok(process.tuple_from_slice(&[messages, Term::NIL]).unwrap())
+        );
+
+        assert_eq!(native(process, flag(), false.into()), Ok(true.into()));
+        assert_eq!(
+            process_info_2::native(process, process.pid_term(), messages),
+            Ok(process
+                .tuple_from_slice(&[messages, process.list_from_slice(&[message]).unwrap()])
+                .unwrap())
+        );
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("sensitive")
+}
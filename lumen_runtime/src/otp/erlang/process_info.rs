@@ -0,0 +1,527 @@
+use std::convert::TryInto;
+use std::mem;
+use std::sync::atomic::Ordering;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::alloc::{Heap, VirtualHeap};
+use liblumen_alloc::erts::process::{MaxHeapSize, Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::ModuleFunctionArity;
+use liblumen_alloc::CloneToProcess;
+use liblumen_alloc::{atom, badarg};
+
+use crate::registry::pid_to_process;
+
+/// The items returned by `process_info/1`, in the order they are returned
+const DEFAULT_ITEMS: &[&str] = &[
+    "current_function",
+    "initial_call",
+    "status",
+    "message_queue_len",
+    "links",
+    "dictionary",
+    "trap_exit",
+    "error_handler",
+    "priority",
+    "total_heap_size",
+    "heap_size",
+    "stack_size",
+    "reductions",
+    "garbage_collection",
+];
+
+/// The items supported by `process_info/2`.  Catches, group leaders, suspensions and process
+/// tracing are not tracked, so `catchlevel`, `group_leader`, `suspending` and `trace` are not
+/// supported.
+pub const ITEMS: &[&str] = &[
+    "backtrace",
+    "binary",
+    "current_function",
+    "current_location",
+    "current_stacktrace",
+    "dictionary",
+    "error_handler",
+    "garbage_collection",
+    "garbage_collection_info",
+    "heap_size",
+    "initial_call",
+    "links",
+    "last_calls",
+    "memory",
+    "message_queue_len",
+    "messages",
+    "min_heap_size",
+    "min_bin_vheap_size",
+    "monitored_by",
+    "monitors",
+    "message_queue_data",
+    "priority",
+    "reductions",
+    "registered_name",
+    "sequential_trace_token",
+    "stack_size",
+    "status",
+    "total_heap_size",
+    "trap_exit",
+];
+
+/// Calls `f` with the process for `pid` if it is alive, otherwise returns `undefined`.
+pub fn with_pid<F>(process: &Process, pid: Term, f: F) -> exception::Result<Term>
+where
+    F: FnOnce(&Process) -> exception::Result<Term>,
+{
+    let pid_pid: Pid = pid.try_into()?;
+
+    if process.pid() == pid_pid {
+        f(process)
+    } else {
+        match pid_to_process(&pid_pid) {
+            Some(pid_arc_process) => f(&pid_arc_process),
+            None => Ok(atom!("undefined")),
+        }
+    }
+}
+
+/// The `{Item, Value}` tuples of the `process_info/1` items of `target`, allocated on `process`.
+pub fn default_items(process: &Process, target: &Process) -> exception::Result<Term> {
+    let mut vec = Vec::with_capacity(DEFAULT_ITEMS.len() + 1);
+
+    for name in DEFAULT_ITEMS {
+        vec.push(item_tuple(process, target, name)?);
+    }
+
+    // `registered_name` is only included in `process_info/1` when the process is registered
+    if target.registered_name.read().is_some() {
+        vec.push(item_tuple(process, target, "registered_name")?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+/// Either a single `item` atom, which returns `{Item, Value}`, or a list of items, which returns a
+/// list of `{Item, Value}` in the same order as `items`.
+pub fn item_or_list(
+    process: &Process,
+    target: &Process,
+    item_or_list: Term,
+) -> exception::Result<Term> {
+    match item_or_list.decode()? {
+        TypedTerm::Atom(item_atom) => item(process, target, item_atom),
+        TypedTerm::Nil => Ok(Term::NIL),
+        TypedTerm::List(cons) => {
+            let mut names = Vec::new();
+
+            for result in cons.into_iter() {
+                match result {
+                    Ok(element) => names.push(supported_item_name(element.try_into()?)?),
+                    Err(_) => return Err(badarg!().into()),
+                }
+            }
+
+            let mut vec = Vec::with_capacity(names.len());
+
+            for name in names {
+                vec.push(item_tuple(process, target, name)?);
+            }
+
+            process.list_from_slice(&vec).map_err(|error| error.into())
+        }
+        _ => Err(badarg!().into()),
+    }
+}
+
+/// The `max_heap_size` map returned by `process_flag(max_heap_size, _)` and
+/// `process_info(_, garbage_collection)`
+pub fn max_heap_size(process: &Process, max_heap_size: MaxHeapSize) -> exception::Result<Term> {
+    process
+        .map_from_slice(&[
+            (atom!("size"), process.integer(max_heap_size.size)?),
+            (atom!("kill"), max_heap_size.kill.into()),
+            (atom!("error_logger"), max_heap_size.error_logger.into()),
+        ])
+        .map_err(|error| error.into())
+}
+
+// Private
+
+fn item(process: &Process, target: &Process, item: Atom) -> exception::Result<Term> {
+    match supported_item_name(item)? {
+        // Unlike the list form, the single item form does not return a tuple when there is no
+        // registered name
+        "registered_name" if target.registered_name.read().is_none() => Ok(Term::NIL),
+        name => item_tuple(process, target, name),
+    }
+}
+
+fn supported_item_name(item: Atom) -> exception::Result<&'static str> {
+    let name = item.name();
+
+    ITEMS
+        .iter()
+        .find(|supported| **supported == name)
+        .copied()
+        .ok_or_else(|| badarg!().into())
+}
+
+fn item_tuple(process: &Process, target: &Process, name: &str) -> exception::Result<Term> {
+    let tag = Atom::str_to_term(name);
+    let value = value(process, target, name)?;
+
+    process
+        .tuple_from_slice(&[tag, value])
+        .map_err(|error| error.into())
+}
+
+/// The value of the item `name` for `target`, allocated on `process`, so that `target` does not
+/// need to be the process calling `process_info`.
+fn value(process: &Process, target: &Process, name: &str) -> exception::Result<Term> {
+    match name {
+        "backtrace" if target.is_sensitive() => {
+            process.binary_from_str("").map_err(|error| error.into())
+        }
+        "backtrace" => backtrace(process, target),
+        "binary" => binary(process, target),
+        "current_function" => current_function(process, target),
+        "current_location" => current_location(process, target),
+        "current_stacktrace" => current_stacktrace(process, target),
+        "dictionary" if target.is_sensitive() => Ok(Term::NIL),
+        "dictionary" => dictionary(process, target),
+        "error_handler" => Ok(target.error_handler().encode()?),
+        "garbage_collection" => garbage_collection(process, target),
+        "garbage_collection_info" => garbage_collection_info(process, target),
+        "heap_size" => Ok(process.integer(target.heap_size())?),
+        "initial_call" => module_function_arity(process, &target.initial_module_function_arity),
+        "links" => links(process, target),
+        "last_calls" => last_calls(process, target),
+        "memory" => memory(process, target),
+        "message_queue_len" => message_queue_len(process, target),
+        "messages" if target.is_sensitive() => Ok(Term::NIL),
+        "messages" => messages(process, target),
+        "min_heap_size" => Ok(process.integer(target.min_heap_size())?),
+        "min_bin_vheap_size" => Ok(process.integer(target.min_vheap_size())?),
+        "monitored_by" => monitored_by(process, target),
+        "monitors" => monitors(process, target),
+        "message_queue_data" => {
+            let message_queue_data_atom: Atom = target.message_queue_data().into();
+
+            Ok(message_queue_data_atom.encode()?)
+        }
+        "priority" => {
+            let priority_atom: Atom = target.priority().into();
+
+            Ok(priority_atom.encode()?)
+        }
+        "reductions" => {
+            Ok(process.integer(target.total_reductions.load(Ordering::SeqCst) as usize)?)
+        }
+        "registered_name" => registered_name(target),
+        "sequential_trace_token" => Ok(Term::NIL),
+        "stack_size" => Ok(process.integer(target.stack_used())?),
+        "status" => Ok(status(&target.status.read())),
+        "total_heap_size" => Ok(process.integer(total_heap_size(target))?),
+        "trap_exit" => Ok(target.traps_exit().into()),
+        _ => unreachable!("{} is not a supported item", name),
+    }
+}
+
+fn backtrace(process: &Process, target: &Process) -> exception::Result<Term> {
+    let string = target.stacktrace().to_string();
+
+    process
+        .binary_from_str(&string)
+        .map_err(|error| error.into())
+}
+
+/// `{Address, Size, RefCount}` for each reference-counted binary referenced by `target`
+fn binary(process: &Process, target: &Process) -> exception::Result<Term> {
+    let binaries: Vec<(usize, usize, usize)> = target
+        .acquire_heap()
+        .binaries()
+        .map(|proc_bin| {
+            let address = unsafe { proc_bin.as_byte_ptr() } as usize;
+
+            (address, proc_bin.full_byte_len(), proc_bin.ref_count())
+        })
+        .collect();
+    let mut vec = Vec::with_capacity(binaries.len());
+
+    for (address, size, ref_count) in binaries {
+        vec.push(process.tuple_from_slice(&[
+            process.integer(address)?,
+            process.integer(size)?,
+            process.integer(ref_count)?,
+        ])?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+fn current_function(process: &Process, target: &Process) -> exception::Result<Term> {
+    match target.current_module_function_arity() {
+        Some(current_module_function_arity) => {
+            module_function_arity(process, &current_module_function_arity)
+        }
+        None => Ok(atom!("undefined")),
+    }
+}
+
+fn current_location(process: &Process, target: &Process) -> exception::Result<Term> {
+    match target.current_module_function_arity() {
+        Some(current_module_function_arity) => location(process, &current_module_function_arity),
+        None => Ok(atom!("undefined")),
+    }
+}
+
+fn current_stacktrace(process: &Process, target: &Process) -> exception::Result<Term> {
+    let mut vec = Vec::new();
+
+    for module_function_arity in target.stacktrace().iter() {
+        vec.push(location(process, module_function_arity)?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+fn dictionary(process: &Process, target: &Process) -> exception::Result<Term> {
+    let entries = {
+        // Holding the heap keeps `target` from collecting, and so moving, the entries while they
+        // are copied.
+        let _target_heap = target.acquire_heap();
+        let mut entries = Vec::new();
+
+        for (key, value) in target.dictionary_entries() {
+            entries.push((
+                clone_to_fragment(process, key)?,
+                clone_to_fragment(process, value)?,
+            ));
+        }
+
+        entries
+    };
+    let mut vec = Vec::with_capacity(entries.len());
+
+    for (key, value) in entries {
+        vec.push(process.tuple_from_slice(&[key, value])?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+/// Copies `term` to a heap fragment attached to `process`.  Unlike `clone_to_process`, this does
+/// not lock the heap of `process`, so it can be called while holding the heap of the process that
+/// owns `term`, even when that is `process`.
+fn clone_to_fragment(process: &Process, term: Term) -> exception::Result<Term> {
+    let (fragment_term, mut heap_fragment) = term.clone_to_fragment()?;
+    process.attach_fragment(unsafe { heap_fragment.as_mut() });
+
+    Ok(fragment_term)
+}
+
+fn garbage_collection(process: &Process, target: &Process) -> exception::Result<Term> {
+    let max_heap_size = max_heap_size(process, target.max_heap_size())?;
+
+    keyword_list(
+        process,
+        &[
+            ("max_heap_size", max_heap_size),
+            (
+                "min_bin_vheap_size",
+                process.integer(target.min_vheap_size())?,
+            ),
+            ("min_heap_size", process.integer(target.min_heap_size())?),
+            ("fullsweep_after", process.integer(target.max_gen_gcs())?),
+            ("minor_gcs", process.integer(target.minor_gcs())?),
+            ("major_gcs", process.integer(target.major_gcs())?),
+            ("gc_time", gc_time(process, target)?),
+        ],
+    )
+}
+
+fn garbage_collection_info(process: &Process, target: &Process) -> exception::Result<Term> {
+    let (heap_block_size, heap_size, old_heap_block_size, old_heap_size, bin_vheap_size) = {
+        let heap = target.acquire_heap();
+
+        (
+            heap.heap_size(),
+            heap.heap_used(),
+            heap.old_heap_size(),
+            heap.old_heap_used(),
+            heap.virtual_heap_used(),
+        )
+    };
+
+    keyword_list(
+        process,
+        &[
+            ("old_heap_block_size", process.integer(old_heap_block_size)?),
+            ("heap_block_size", process.integer(heap_block_size)?),
+            ("mbuf_size", process.integer(target.off_heap_size())?),
+            ("stack_size", process.integer(target.stack_used())?),
+            ("old_heap_size", process.integer(old_heap_size)?),
+            ("heap_size", process.integer(heap_size)?),
+            ("bin_vheap_size", process.integer(bin_vheap_size)?),
+            ("minor_gcs", process.integer(target.minor_gcs())?),
+            ("major_gcs", process.integer(target.major_gcs())?),
+            ("gc_time", gc_time(process, target)?),
+        ],
+    )
+}
+
+/// The total time in microseconds spent collecting garbage for `target`
+fn gc_time(process: &Process, target: &Process) -> exception::Result<Term> {
+    let microseconds = target.gc_time().as_micros() as u64;
+
+    process.integer(microseconds).map_err(|error| error.into())
+}
+
+fn keyword_list(process: &Process, keywords: &[(&str, Term)]) -> exception::Result<Term> {
+    let mut vec = Vec::with_capacity(keywords.len());
+
+    for (key, value) in keywords {
+        vec.push(process.tuple_from_slice(&[Atom::str_to_term(key), *value])?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+fn last_calls(process: &Process, target: &Process) -> exception::Result<Term> {
+    match target.saved_calls() {
+        Some(saved_calls) => {
+            let mut vec = Vec::with_capacity(saved_calls.len());
+
+            for saved_call in saved_calls {
+                vec.push(module_function_arity(process, &saved_call)?);
+            }
+
+            process.list_from_slice(&vec).map_err(|error| error.into())
+        }
+        None => Ok(false.into()),
+    }
+}
+
+fn links(process: &Process, target: &Process) -> exception::Result<Term> {
+    let pids: Vec<Pid> = target.linked_pid_set.lock().iter().copied().collect();
+    let mut vec = Vec::with_capacity(pids.len());
+
+    for pid in pids {
+        vec.push(pid.encode()?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+fn location(
+    process: &Process,
+    module_function_arity: &ModuleFunctionArity,
+) -> exception::Result<Term> {
+    process
+        .tuple_from_slice(&[
+            module_function_arity.module.encode()?,
+            module_function_arity.function.encode()?,
+            module_function_arity.arity.into(),
+            Term::NIL,
+        ])
+        .map_err(|error| error.into())
+}
+
+/// The size in bytes of the process, including its stack, heaps and messages
+fn memory(process: &Process, target: &Process) -> exception::Result<Term> {
+    let words = total_heap_size(target) + target.stack_used();
+    let bytes = mem::size_of::<Process>() + words * mem::size_of::<Term>();
+
+    process.integer(bytes).map_err(|error| error.into())
+}
+
+fn message_queue_len(process: &Process, target: &Process) -> exception::Result<Term> {
+    let len = target.mailbox.lock().borrow().len();
+
+    process.integer(len).map_err(|error| error.into())
+}
+
+fn messages(process: &Process, target: &Process) -> exception::Result<Term> {
+    let vec = {
+        // Holding the heap keeps `target` from collecting, and so moving, the messages on its
+        // heap, while holding the mailbox keeps the messages in heap fragments from being
+        // received and freed, while they are copied.  Senders lock the mailbox while holding the
+        // heap, so they are locked in the same order.
+        let _target_heap = target.acquire_heap();
+        let mailbox_guard = target.mailbox.lock();
+        let mailbox = mailbox_guard.borrow();
+        let mut vec = Vec::with_capacity(mailbox.len());
+
+        for message in mailbox.iter() {
+            vec.push(clone_to_fragment(process, *message.data())?);
+        }
+
+        vec
+    };
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+fn module_function_arity(
+    process: &Process,
+    module_function_arity: &ModuleFunctionArity,
+) -> exception::Result<Term> {
+    process
+        .tuple_from_slice(&[
+            module_function_arity.module.encode()?,
+            module_function_arity.function.encode()?,
+            module_function_arity.arity.into(),
+        ])
+        .map_err(|error| error.into())
+}
+
+fn monitored_by(process: &Process, target: &Process) -> exception::Result<Term> {
+    let pids: Vec<Pid> = target
+        .monitor_by_reference
+        .lock()
+        .values()
+        .map(|monitor| *monitor.monitoring_pid())
+        .collect();
+    let mut vec = Vec::with_capacity(pids.len());
+
+    for pid in pids {
+        vec.push(pid.encode()?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+fn monitors(process: &Process, target: &Process) -> exception::Result<Term> {
+    let pids: Vec<Pid> = target
+        .monitored_pid_by_reference
+        .lock()
+        .values()
+        .copied()
+        .collect();
+    let tag = atom!("process");
+    let mut vec = Vec::with_capacity(pids.len());
+
+    for pid in pids {
+        vec.push(process.tuple_from_slice(&[tag, pid.encode()?])?);
+    }
+
+    process.list_from_slice(&vec).map_err(|error| error.into())
+}
+
+fn registered_name(target: &Process) -> exception::Result<Term> {
+    match *target.registered_name.read() {
+        Some(registered_name) => registered_name.encode().map_err(|error| error.into()),
+        None => Ok(Term::NIL),
+    }
+}
+
+fn status(status: &Status) -> Term {
+    match status {
+        Status::Runnable => atom!("runnable"),
+        Status::Running => atom!("running"),
+        Status::Waiting => atom!("waiting"),
+        Status::Exiting(_) => atom!("exiting"),
+    }
+}
+
+/// The size in words of the young and old generations and the heap fragments
+fn total_heap_size(target: &Process) -> usize {
+    target.heap_size() + target.old_heap_size() + target.off_heap_size()
+}
@@ -0,0 +1,19 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::otp::erlang::process_info::{default_items, with_pid};
+
+#[native_implemented_function(process_info/1)]
+pub fn native(process: &Process, pid: Term) -> exception::Result<Term> {
+    with_pid(process, pid, |target| default_items(process, target))
+}
@@ -0,0 +1,76 @@
+use std::convert::TryInto;
+
+use proptest::prop_assert_eq;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::process_info_1::native;
+use crate::scheduler::{with_process, with_process_arc};
+use crate::test::strategy;
+
+#[test]
+fn without_local_pid_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term::is_not_local_pid(arc_process.clone()),
+                |pid| {
+                    prop_assert_eq!(native(&arc_process, pid), Err(badarg!().into()));
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn without_process_returns_undefined() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, Pid::next_term()),
+            Ok(Atom::str_to_term("undefined"))
+        );
+    });
+}
+
+#[test]
+fn with_process_returns_default_items() {
+    with_process(|process| {
+        let info = native(process, process.pid_term()).unwrap();
+        let cons: Boxed<Cons> = info.try_into().unwrap();
+        let items: Vec<Term> = cons
+            .into_iter()
+            .map(|result| {
+                let tuple: Boxed<Tuple> = result.unwrap().try_into().unwrap();
+
+                tuple[0]
+            })
+            .collect();
+
+        assert_eq!(
+            items,
+            [
+                "current_function",
+                "initial_call",
+                "status",
+                "message_queue_len",
+                "links",
+                "dictionary",
+                "trap_exit",
+                "error_handler",
+                "priority",
+                "total_heap_size",
+                "heap_size",
+                "stack_size",
+                "reductions",
+                "garbage_collection",
+            ]
+            .iter()
+            .map(|name| Atom::str_to_term(name))
+            .collect::<Vec<Term>>()
+        );
+    });
+}
@@ -1,4 +1,12 @@
+mod with_binary;
+mod with_dictionary;
+mod with_garbage_collection_info;
+mod with_item_list;
+mod with_links;
+mod with_message_queue_len;
+mod with_messages;
 mod with_registered_name;
+mod with_trap_exit;
 
 use super::*;
 
@@ -9,6 +17,8 @@ use proptest::strategy::{BoxedStrategy, Strategy};
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::{Atom, Pid, Term, TypedTerm};
 
+use crate::otp::erlang::process_info::ITEMS;
+
 #[test]
 fn without_supported_item_errors_badarg() {
     with_process_arc(|arc_process| {
@@ -27,10 +37,9 @@ fn unsupported_item(arc_process: Arc<Process>) -> BoxedStrategy<Term> {
     strategy::term(arc_process)
         .prop_filter("Item cannot be supported", |item| {
             match item.decode().unwrap() {
-                TypedTerm::Atom(atom) => match atom.name() {
-                    "registered_name" => false,
-                    _ => true,
-                },
+                TypedTerm::Atom(atom) => !ITEMS.contains(&atom.name()),
+                // An empty list of items returns an empty list
+                TypedTerm::Nil => false,
                 _ => true,
             }
         })
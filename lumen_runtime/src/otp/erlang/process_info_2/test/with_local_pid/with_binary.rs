@@ -0,0 +1,54 @@
+use super::*;
+
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::process;
+
+#[test]
+fn without_reference_counted_binaries_returns_empty_list() {
+    with_process_arc(|arc_process| {
+        // Binaries of 64 bytes or less are copied onto the heap instead of reference-counted
+        arc_process.binary_from_bytes(&[0; 64]).unwrap();
+
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item()),
+            Ok(arc_process.tuple_from_slice(&[item(), Term::NIL]).unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_other_returns_address_size_and_reference_count_of_binaries() {
+    with_process_arc(|parent_arc_process| {
+        let other_arc_process = process::test(&parent_arc_process);
+        let binary = other_arc_process.binary_from_bytes(&[1; 65]).unwrap();
+        let proc_bin: Boxed<ProcBin> = binary.try_into().unwrap();
+        let address = unsafe { proc_bin.as_byte_ptr() } as usize;
+
+        let parent_process = &parent_arc_process;
+
+        assert_eq!(
+            native(parent_process, other_arc_process.pid_term(), item()),
+            Ok(parent_process
+                .tuple_from_slice(&[
+                    item(),
+                    parent_process
+                        .list_from_slice(&[parent_process
+                            .tuple_from_slice(&[
+                                parent_process.integer(address).unwrap(),
+                                parent_process.integer(65).unwrap(),
+                                parent_process.integer(1).unwrap(),
+                            ])
+                            .unwrap()])
+                        .unwrap()
+                ])
+                .unwrap())
+        );
+    });
+}
+
+fn item() -> Term {
+    Atom::str_to_term("binary")
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+use crate::process;
+
+#[test]
+fn with_other_returns_copies_of_entries() {
+    with_process_arc(|parent_arc_process| {
+        let other_arc_process = process::test(&parent_arc_process);
+        let key = Atom::str_to_term("key");
+        let value = other_arc_process
+            .list_from_slice(&[Atom::str_to_term("value")])
+            .unwrap();
+
+        other_arc_process.put(key, value).unwrap();
+
+        let parent_process = &parent_arc_process;
+        let parent_value = parent_process
+            .list_from_slice(&[Atom::str_to_term("value")])
+            .unwrap();
+
+        assert_eq!(
+            native(parent_process, other_arc_process.pid_term(), item()),
+            Ok(parent_process
+                .tuple_from_slice(&[
+                    item(),
+                    parent_process
+                        .list_from_slice(&[parent_process
+                            .tuple_from_slice(&[key, parent_value])
+                            .unwrap()])
+                        .unwrap()
+                ])
+                .unwrap())
+        );
+    });
+}
+
+fn item() -> Term {
+    Atom::str_to_term("dictionary")
+}
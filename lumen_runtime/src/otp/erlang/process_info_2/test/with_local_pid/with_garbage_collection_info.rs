@@ -0,0 +1,37 @@
+use super::*;
+
+use std::convert::TryInto;
+
+use crate::otp::erlang::garbage_collect_2;
+use crate::process;
+use crate::scheduler::Scheduler;
+
+#[test]
+fn with_other_returns_number_of_collections() {
+    with_process_arc(|parent_arc_process| {
+        let other_arc_process = process::test(&parent_arc_process);
+        let other_pid = other_arc_process.pid_term();
+
+        assert!(contains(&parent_arc_process, other_pid, "major_gcs", 0));
+
+        assert!(garbage_collect_2::request(&parent_arc_process, other_pid, Term::NIL).is_ok());
+        assert!(Scheduler::current().run_through(&other_arc_process));
+
+        assert!(contains(&parent_arc_process, other_pid, "major_gcs", 1));
+        assert!(contains(&parent_arc_process, other_pid, "minor_gcs", 0));
+    });
+}
+
+fn contains(process: &Process, pid: Term, key: &str, value: usize) -> bool {
+    let info_tuple: Boxed<Tuple> = native(process, pid, item()).unwrap().try_into().unwrap();
+    let info: Boxed<Cons> = info_tuple[1].try_into().unwrap();
+    let entry = process
+        .tuple_from_slice(&[Atom::str_to_term(key), process.integer(value).unwrap()])
+        .unwrap();
+
+    info.into_iter().any(|result| result.unwrap() == entry)
+}
+
+fn item() -> Term {
+    Atom::str_to_term("garbage_collection_info")
+}
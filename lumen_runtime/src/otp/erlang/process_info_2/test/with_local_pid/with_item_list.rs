@@ -0,0 +1,54 @@
+use super::*;
+
+#[test]
+fn with_empty_list_returns_empty_list() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), Term::NIL),
+            Ok(Term::NIL)
+        );
+    });
+}
+
+#[test]
+fn with_unsupported_item_errors_badarg() {
+    with_process_arc(|arc_process| {
+        let items = arc_process
+            .list_from_slice(&[
+                Atom::str_to_term("trap_exit"),
+                Atom::str_to_term("unsupported"),
+            ])
+            .unwrap();
+
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), items),
+            Err(badarg!().into())
+        );
+    });
+}
+
+#[test]
+fn with_supported_items_returns_item_value_tuples_in_order() {
+    with_process_arc(|arc_process| {
+        let trap_exit = Atom::str_to_term("trap_exit");
+        let registered_name = Atom::str_to_term("registered_name");
+        let items = arc_process
+            .list_from_slice(&[trap_exit, registered_name])
+            .unwrap();
+
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), items),
+            Ok(arc_process
+                .list_from_slice(&[
+                    arc_process
+                        .tuple_from_slice(&[trap_exit, false.into()])
+                        .unwrap(),
+                    // unlike the single item form, the list form always returns a tuple
+                    arc_process
+                        .tuple_from_slice(&[registered_name, Term::NIL])
+                        .unwrap()
+                ])
+                .unwrap())
+        );
+    });
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+use crate::process;
+
+#[test]
+fn without_links_returns_empty_list() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item()),
+            Ok(arc_process.tuple_from_slice(&[item(), Term::NIL]).unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_link_returns_linked_pids() {
+    with_process_arc(|parent_arc_process| {
+        let other_arc_process = process::test(&parent_arc_process);
+
+        parent_arc_process.link(&other_arc_process);
+
+        let links = parent_arc_process
+            .list_from_slice(&[parent_arc_process.pid_term()])
+            .unwrap();
+
+        assert_eq!(
+            native(&parent_arc_process, other_arc_process.pid_term(), item()),
+            Ok(parent_arc_process
+                .tuple_from_slice(&[item(), links])
+                .unwrap())
+        );
+    });
+}
+
+fn item() -> Term {
+    Atom::str_to_term("links")
+}
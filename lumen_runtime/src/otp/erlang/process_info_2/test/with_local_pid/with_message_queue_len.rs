@@ -0,0 +1,47 @@
+use super::*;
+
+use crate::process;
+
+#[test]
+fn with_self_returns_number_of_messages() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item()),
+            Ok(arc_process
+                .tuple_from_slice(&[item(), arc_process.integer(0).unwrap()])
+                .unwrap())
+        );
+
+        arc_process.send_from_self(Atom::str_to_term("first"));
+        arc_process.send_from_self(Atom::str_to_term("second"));
+
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item()),
+            Ok(arc_process
+                .tuple_from_slice(&[item(), arc_process.integer(2).unwrap()])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_other_returns_number_of_messages() {
+    with_process_arc(|parent_arc_process| {
+        let other_arc_process = process::test(&parent_arc_process);
+
+        assert!(other_arc_process
+            .send_from_other(Atom::str_to_term("message"))
+            .is_ok());
+
+        assert_eq!(
+            native(&parent_arc_process, other_arc_process.pid_term(), item()),
+            Ok(parent_arc_process
+                .tuple_from_slice(&[item(), parent_arc_process.integer(1).unwrap()])
+                .unwrap())
+        );
+    });
+}
+
+fn item() -> Term {
+    Atom::str_to_term("message_queue_len")
+}
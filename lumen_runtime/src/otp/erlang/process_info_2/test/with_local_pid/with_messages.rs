@@ -0,0 +1,49 @@
+use super::*;
+
+use crate::process;
+
+#[test]
+fn with_self_returns_copies_of_messages() {
+    with_process_arc(|arc_process| {
+        let message = arc_process
+            .tuple_from_slice(&[
+                Atom::str_to_term("message"),
+                arc_process.integer(1).unwrap(),
+            ])
+            .unwrap();
+        arc_process.send_from_self(message);
+
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item()),
+            Ok(arc_process
+                .tuple_from_slice(&[item(), arc_process.list_from_slice(&[message]).unwrap()])
+                .unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_other_returns_copies_of_messages() {
+    with_process_arc(|parent_arc_process| {
+        let other_arc_process = process::test(&parent_arc_process);
+        let message = parent_arc_process
+            .list_from_slice(&[Atom::str_to_term("first"), Atom::str_to_term("second")])
+            .unwrap();
+
+        assert!(other_arc_process.send_from_other(message).is_ok());
+
+        assert_eq!(
+            native(&parent_arc_process, other_arc_process.pid_term(), item()),
+            Ok(parent_arc_process
+                .tuple_from_slice(&[
+                    item(),
+                    parent_arc_process.list_from_slice(&[message]).unwrap()
+                ])
+                .unwrap())
+        );
+    });
+}
+
+fn item() -> Term {
+    Atom::str_to_term("messages")
+}
@@ -0,0 +1,26 @@
+use super::*;
+
+#[test]
+fn returns_whether_process_traps_exits() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item()),
+            Ok(arc_process
+                .tuple_from_slice(&[item(), false.into()])
+                .unwrap())
+        );
+
+        arc_process.trap_exit(true);
+
+        assert_eq!(
+            native(&arc_process, arc_process.pid_term(), item()),
+            Ok(arc_process
+                .tuple_from_slice(&[item(), true.into()])
+                .unwrap())
+        );
+    });
+}
+
+fn item() -> Term {
+    Atom::str_to_term("trap_exit")
+}
@@ -1,5 +1,7 @@
 use super::*;
 
+use liblumen_alloc::erts::process::MessageQueueData;
+
 #[test]
 fn with_locked_adds_heap_message_to_mailbox_and_returns_message() {
     with_process_arc(|arc_process| {
@@ -34,3 +36,22 @@ fn without_locked_adds_process_message_to_mailbox_and_returns_message() {
             .unwrap();
     });
 }
+
+#[test]
+fn with_off_heap_message_queue_adds_heap_message_to_mailbox_and_returns_message() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(&strategy::term(arc_process.clone()), |message| {
+                let different_arc_process = process::test(&arc_process);
+                different_arc_process.set_message_queue_data(MessageQueueData::OffHeap);
+                let destination = different_arc_process.pid_term();
+
+                prop_assert_eq!(native(&arc_process, destination, message), Ok(message));
+
+                prop_assert!(has_heap_message(&different_arc_process, message));
+
+                Ok(())
+            })
+            .unwrap();
+    });
+}
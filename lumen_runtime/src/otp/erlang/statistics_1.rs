@@ -0,0 +1,134 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+use std::mem;
+
+use liblumen_core::locks::Mutex;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::gc;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, badarg};
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::scheduler::{self, statistics};
+use crate::system;
+use crate::time::monotonic;
+
+#[native_implemented_function(statistics/1)]
+pub fn native(process: &Process, item: Term) -> exception::Result<Term> {
+    let item_atom: Atom = item.try_into()?;
+
+    match item_atom.name() {
+        "active_tasks" => {
+            let vec = scheduler::registered()
+                .iter()
+                .map(|arc_scheduler| {
+                    let running = if arc_scheduler.statistics.is_running() {
+                        1
+                    } else {
+                        0
+                    };
+
+                    process.integer(arc_scheduler.runnable_len() + running)
+                })
+                .collect::<Result<Vec<Term>, _>>()?;
+
+            Ok(process.list_from_slice(&vec)?)
+        }
+        "context_switches" => {
+            let context_switches: u64 = scheduler::registered()
+                .iter()
+                .map(|arc_scheduler| arc_scheduler.statistics.context_switches())
+                .sum();
+
+            Ok(process
+                .tuple_from_slice(&[process.integer(context_switches)?, process.integer(0)?])?)
+        }
+        "garbage_collection" => Ok(process.tuple_from_slice(&[
+            process.integer(gc::collections())?,
+            process.integer(gc::words_reclaimed())?,
+            process.integer(0)?,
+        ])?),
+        "reductions" => {
+            let reductions: u64 = scheduler::registered()
+                .iter()
+                .map(|arc_scheduler| arc_scheduler.statistics.reductions())
+                .sum();
+
+            total_and_since_last_call(process, reductions, &LAST_REDUCTIONS)
+        }
+        "run_queue" => {
+            let run_queue: usize = scheduler::registered()
+                .iter()
+                .map(|arc_scheduler| arc_scheduler.runnable_len())
+                .sum();
+
+            Ok(process.integer(run_queue)?)
+        }
+        "run_queue_lengths" => {
+            let vec = scheduler::registered()
+                .iter()
+                .map(|arc_scheduler| process.integer(arc_scheduler.runnable_len()))
+                .collect::<Result<Vec<Term>, _>>()?;
+
+            Ok(process.list_from_slice(&vec)?)
+        }
+        "runtime" => {
+            let milliseconds = system::time::cpu_time().as_millis() as u64;
+
+            total_and_since_last_call(process, milliseconds, &LAST_RUNTIME)
+        }
+        "scheduler_wall_time" => match statistics::wall_time() {
+            Some(wall_times) => {
+                let vec = wall_times
+                    .iter()
+                    .map(|wall_time| {
+                        let id: u32 = wall_time.id.into();
+
+                        process.tuple_from_slice(&[
+                            process.integer(id as usize)?,
+                            process.integer(wall_time.active.as_micros() as u64)?,
+                            process.integer(wall_time.total.as_micros() as u64)?,
+                        ])
+                    })
+                    .collect::<Result<Vec<Term>, _>>()?;
+
+                Ok(process.list_from_slice(&vec)?)
+            }
+            None => Ok(atom!("undefined")),
+        },
+        "wall_clock" => {
+            let milliseconds = monotonic::time_in_milliseconds();
+
+            total_and_since_last_call(process, milliseconds, &LAST_WALL_CLOCK)
+        }
+        _ => Err(badarg!().into()),
+    }
+}
+
+// Private
+
+fn total_and_since_last_call(
+    process: &Process,
+    total: u64,
+    last_call: &Mutex<u64>,
+) -> exception::Result<Term> {
+    let last_call_total = mem::replace(&mut *last_call.lock(), total);
+    let since_last_call = total.saturating_sub(last_call_total);
+
+    Ok(process.tuple_from_slice(&[process.integer(total)?, process.integer(since_last_call)?])?)
+}
+
+lazy_static! {
+    static ref LAST_REDUCTIONS: Mutex<u64> = Mutex::new(0);
+    static ref LAST_RUNTIME: Mutex<u64> = Mutex::new(0);
+    static ref LAST_WALL_CLOCK: Mutex<u64> = Mutex::new(0);
+}
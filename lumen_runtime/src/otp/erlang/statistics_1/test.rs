@@ -0,0 +1,104 @@
+use std::convert::TryInto;
+
+use proptest::prop_assert_eq;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::statistics_1::native;
+use crate::otp::erlang::system_flag_2;
+use crate::scheduler::{self, with_process, with_process_arc};
+use crate::test::strategy;
+
+#[test]
+fn without_atom_item_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(&strategy::term::is_not_atom(arc_process.clone()), |item| {
+                prop_assert_eq!(native(&arc_process, item), Err(badarg!().into()));
+
+                Ok(())
+            })
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_unsupported_item_errors_badarg() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, Atom::str_to_term("unsupported")),
+            Err(badarg!().into())
+        );
+    });
+}
+
+#[test]
+fn with_run_queue_lengths_returns_length_per_scheduler() {
+    with_process(|process| {
+        let run_queue_lengths = native(process, Atom::str_to_term("run_queue_lengths")).unwrap();
+        let cons: Boxed<Cons> = run_queue_lengths.try_into().unwrap();
+
+        assert_eq!(cons.into_iter().count(), scheduler::registered().len());
+    });
+}
+
+#[test]
+fn with_run_queue_includes_runnable_process() {
+    with_process(|process| {
+        let run_queue = native(process, Atom::str_to_term("run_queue")).unwrap();
+        let run_queue_usize: usize = run_queue.try_into().unwrap();
+
+        assert!(1 <= run_queue_usize);
+    });
+}
+
+#[test]
+fn with_wall_clock_returns_total_and_since_last_call() {
+    with_process(|process| {
+        let wall_clock = native(process, Atom::str_to_term("wall_clock")).unwrap();
+        let tuple: Boxed<Tuple> = wall_clock.try_into().unwrap();
+
+        assert_eq!(tuple.len(), 2);
+
+        let total: u64 = tuple[0].try_into().unwrap();
+        let since_last_call: u64 = tuple[1].try_into().unwrap();
+
+        assert!(since_last_call <= total);
+    });
+}
+
+#[test]
+fn with_scheduler_wall_time_returns_undefined_until_enabled() {
+    with_process(|process| {
+        let item = Atom::str_to_term("scheduler_wall_time");
+
+        assert_eq!(native(process, item), Ok(Atom::str_to_term("undefined")));
+
+        assert_eq!(
+            system_flag_2::native(process, item, true.into()),
+            Ok(false.into())
+        );
+
+        let scheduler_wall_time = native(process, item).unwrap();
+        let cons: Boxed<Cons> = scheduler_wall_time.try_into().unwrap();
+
+        for result in cons.into_iter() {
+            let tuple: Boxed<Tuple> = result.unwrap().try_into().unwrap();
+
+            assert_eq!(tuple.len(), 3);
+
+            let active: u64 = tuple[1].try_into().unwrap();
+            let total: u64 = tuple[2].try_into().unwrap();
+
+            assert!(active <= total);
+        }
+
+        assert_eq!(
+            system_flag_2::native(process, item, false.into()),
+            Ok(true.into())
+        );
+        assert_eq!(native(process, item), Ok(Atom::str_to_term("undefined")));
+    });
+}
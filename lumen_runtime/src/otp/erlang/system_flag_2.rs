@@ -0,0 +1,37 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::gc;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::scheduler::statistics;
+
+#[native_implemented_function(system_flag/2)]
+pub fn native(process: &Process, flag: Term, value: Term) -> exception::Result<Term> {
+    let flag_atom: Atom = flag.try_into()?;
+
+    match flag_atom.name() {
+        "fullsweep_after" => {
+            let value_usize: usize = value.try_into()?;
+
+            Ok(process.integer(gc::set_default_max_gen_gcs(value_usize))?)
+        }
+        "scheduler_wall_time" => {
+            let value_bool: bool = value.try_into()?;
+
+            Ok(statistics::set_wall_time_enabled(value_bool).into())
+        }
+        _ => Err(badarg!().into()),
+    }
+}
@@ -0,0 +1,80 @@
+use proptest::prop_assert_eq;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::process::gc;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::system_flag_2::native;
+use crate::scheduler::with_process_arc;
+use crate::test::strategy;
+
+#[test]
+fn without_atom_flag_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &(
+                    strategy::term::is_not_atom(arc_process.clone()),
+                    strategy::term(arc_process.clone()),
+                ),
+                |(flag, value)| {
+                    prop_assert_eq!(native(&arc_process, flag, value), Err(badarg!().into()));
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_scheduler_wall_time_flag_without_boolean_value_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(
+                &strategy::term::is_not_boolean(arc_process.clone()),
+                |value| {
+                    prop_assert_eq!(
+                        native(
+                            &arc_process,
+                            Atom::str_to_term("scheduler_wall_time"),
+                            value
+                        ),
+                        Err(badarg!().into())
+                    );
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_fullsweep_after_flag_without_non_negative_integer_value_errors_badarg() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            native(
+                &arc_process,
+                Atom::str_to_term("fullsweep_after"),
+                arc_process.integer(-1).unwrap()
+            ),
+            Err(badarg!().into())
+        );
+    });
+}
+
+#[test]
+fn with_fullsweep_after_flag_returns_old_value() {
+    with_process_arc(|arc_process| {
+        let default_max_gen_gcs = gc::default_max_gen_gcs();
+        let value = arc_process.integer(default_max_gen_gcs).unwrap();
+
+        // set to the same value, so that processes spawned by other tests are not affected
+        assert_eq!(
+            native(&arc_process, Atom::str_to_term("fullsweep_after"), value),
+            Ok(value)
+        );
+    });
+}
@@ -0,0 +1,58 @@
+// wasm32 proptest cannot be compiled at the same time as non-wasm32 proptest, so disable tests that
+// use proptest completely for wasm32
+//
+// See https://github.com/rust-lang/cargo/issues/4866
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+use std::env::consts::{ARCH, OS};
+use std::mem;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::gc;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, badarg};
+
+use lumen_runtime_macros::native_implemented_function;
+
+use crate::registry;
+use crate::scheduler;
+use crate::system::host::cpus;
+
+/// The OTP release whose standard library the runtime is compatible with
+const OTP_RELEASE: &str = "22";
+
+#[native_implemented_function(system_info/1)]
+pub fn native(process: &Process, item: Term) -> exception::Result<Term> {
+    let item_atom: Atom = item.try_into()?;
+
+    match item_atom.name() {
+        "atom_count" => Ok(process.integer(Atom::count())?),
+        "atom_limit" => Ok(process.integer(Atom::LIMIT)?),
+        "build_type" => {
+            let build_type = if cfg!(debug_assertions) {
+                atom!("debug")
+            } else {
+                atom!("opt")
+            };
+
+            Ok(build_type)
+        }
+        "fullsweep_after" => {
+            Ok(process.tuple_from_slice(&[item, process.integer(gc::default_max_gen_gcs())?])?)
+        }
+        "logical_processors" => Ok(process.integer(cpus::num_logical())?),
+        "otp_release" => Ok(process.charlist_from_str(OTP_RELEASE)?),
+        "process_count" => Ok(process.integer(registry::processes().len())?),
+        "process_limit" => Ok(process.integer((Pid::NUMBER_MAX + 1) * (Pid::SERIAL_MAX + 1))?),
+        "schedulers" | "schedulers_online" => Ok(process.integer(scheduler::threads())?),
+        "system_architecture" => Ok(process.charlist_from_str(&format!("{}-{}", ARCH, OS))?),
+        // There is no async thread pool as I/O is not done on separate threads
+        "thread_pool_size" => Ok(process.integer(0)?),
+        "version" => Ok(process.charlist_from_str(env!("CARGO_PKG_VERSION"))?),
+        "wordsize" => Ok(process.integer(mem::size_of::<Term>())?),
+        _ => Err(badarg!().into()),
+    }
+}
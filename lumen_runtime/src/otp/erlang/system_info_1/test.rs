@@ -0,0 +1,93 @@
+use std::convert::TryInto;
+
+use proptest::prop_assert_eq;
+use proptest::test_runner::{Config, TestRunner};
+
+use liblumen_alloc::badarg;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::otp::erlang::system_info_1::native;
+use crate::scheduler::{with_process, with_process_arc};
+use crate::system::host::cpus;
+use crate::test::strategy;
+
+#[test]
+fn without_atom_item_errors_badarg() {
+    with_process_arc(|arc_process| {
+        TestRunner::new(Config::with_source_file(file!()))
+            .run(&strategy::term::is_not_atom(arc_process.clone()), |item| {
+                prop_assert_eq!(native(&arc_process, item), Err(badarg!().into()));
+
+                Ok(())
+            })
+            .unwrap();
+    });
+}
+
+#[test]
+fn with_unsupported_item_errors_badarg() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, Atom::str_to_term("unsupported")),
+            Err(badarg!().into())
+        );
+    });
+}
+
+#[test]
+fn with_atom_count_returns_at_most_atom_limit() {
+    with_process(|process| {
+        let atom_count: usize = native(process, Atom::str_to_term("atom_count"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let atom_limit: usize = native(process, Atom::str_to_term("atom_limit"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert!(0 < atom_count);
+        assert!(atom_count <= atom_limit);
+    });
+}
+
+#[test]
+fn with_process_count_includes_process() {
+    with_process(|process| {
+        let process_count: usize = native(process, Atom::str_to_term("process_count"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert!(1 <= process_count);
+    });
+}
+
+#[test]
+fn with_logical_processors_returns_number_of_logical_cpus() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, Atom::str_to_term("logical_processors")),
+            Ok(process.integer(cpus::num_logical()).unwrap())
+        );
+    });
+}
+
+#[test]
+fn with_otp_release_returns_charlist() {
+    with_process(|process| {
+        let otp_release = native(process, Atom::str_to_term("otp_release")).unwrap();
+
+        assert!(otp_release.is_list());
+    });
+}
+
+#[test]
+fn with_wordsize_returns_bytes_per_term() {
+    with_process(|process| {
+        assert_eq!(
+            native(process, Atom::str_to_term("wordsize")),
+            Ok(process.integer(std::mem::size_of::<Term>()).unwrap())
+        );
+    });
+}
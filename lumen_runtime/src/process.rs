@@ -1,4 +1,6 @@
+pub mod garbage_collection;
 pub mod monitor;
+pub mod priority;
 pub mod spawn;
 
 use alloc::sync::Arc;
@@ -12,6 +14,7 @@ use liblumen_core::locks::RwLockWriteGuard;
 use liblumen_alloc::erts::exception::{self, AllocResult, RuntimeException};
 use liblumen_alloc::erts::process::alloc::{Heap, TermAlloc};
 use liblumen_alloc::erts::process::code::stack::frame::Frame;
+use liblumen_alloc::erts::process::gc::GcError;
 use liblumen_alloc::erts::process::{self, Process, ProcessHeap};
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::ModuleFunctionArity;
@@ -21,12 +24,13 @@ use crate::code;
 #[cfg(test)]
 use crate::process::spawn::options::Options;
 use crate::registry::*;
-use crate::scheduler::Scheduler;
 #[cfg(test)]
 use crate::scheduler::Spawned;
+use crate::scheduler::{Scheduled, Scheduler};
 use crate::system;
 #[cfg(test)]
 use crate::test;
+use crate::time::monotonic;
 
 fn is_expected_exception(exception: &RuntimeException) -> bool {
     use exception::Class;
@@ -54,6 +58,39 @@ fn is_expected_exit_reason(reason: Term) -> bool {
     }
 }
 
+/// Collects garbage after `process` ran out of heap, doing a full sweep if a minor collection is
+/// not enough.  If the process would grow past its `max_heap_size` and the `max_heap_size` kills
+/// the process, it exits with `killed` instead.
+pub fn garbage_collect(process: &Process, need: usize, roots: &mut [Term]) {
+    if !garbage_collect_within_max_heap_size(process, need, roots) {
+        process.exit(atom!("killed"));
+    }
+}
+
+/// Same as `garbage_collect`, but returns `false` instead of exiting `process` when its
+/// `max_heap_size` kills it, for callers that hold a lock on the status of `process`.
+pub fn garbage_collect_within_max_heap_size(
+    process: &Process,
+    need: usize,
+    roots: &mut [Term],
+) -> bool {
+    let start = monotonic::time_since_start();
+
+    let within = loop {
+        match process.garbage_collect(need, roots) {
+            Ok(_reductions) => break true,
+            // `NeedFullSweep` is set, so the next collection will be a full sweep
+            Err(GcError::FullsweepRequired) => continue,
+            Err(GcError::MaxHeapSizeExceeded) => break false,
+            Err(gc_err) => panic!("fatal garbage collection error: {:?}", gc_err),
+        }
+    };
+
+    process.add_gc_time(monotonic::time_since_start() - start);
+
+    within
+}
+
 pub fn log_exit(process: &Process, exception: &RuntimeException) {
     use exception::Class;
     match exception.class() {
@@ -150,6 +187,52 @@ pub fn propagate_exit_to_links(process: &Process, exception: &RuntimeException)
     }
 }
 
+/// Sends an exit signal with `reason` from `from` to `to`, like `erlang:exit/2`.
+///
+/// * `kill` can't be trapped and exits `to` with `killed`.
+/// * Processes that trap exits receive `{'EXIT', From, Reason}` messages for all other reasons.
+/// * `normal` is ignored unless `to` is `from`, which exits `normal`.
+/// * All other reasons exit `to` with `reason`.
+pub fn exit_signal(from: &Process, to: &Process, reason: Term) -> AllocResult<()> {
+    let to_self = from.pid() == to.pid();
+
+    if reason == atom!("kill") {
+        to.exit(atom!("killed"));
+    } else if to.traps_exit() {
+        let tag = atom!("EXIT");
+        let message = from.tuple_from_slice(&[tag, from.pid_term(), reason])?;
+
+        if to_self {
+            from.send_from_self(message);
+        } else if to.send_from_other(message)? {
+            if let Some(arc_scheduler) = to.scheduler() {
+                arc_scheduler.stop_waiting(to);
+            }
+        }
+
+        return Ok(());
+    } else if reason == atom!("normal") {
+        if to_self {
+            to.exit_normal();
+        } else {
+            return Ok(());
+        }
+    } else if to_self {
+        to.exit(reason);
+    } else {
+        exit_in_heap_fragment(to, reason);
+    }
+
+    // Exiting processes are only cleaned up from the run queues
+    if !to_self {
+        if let Some(arc_scheduler) = to.scheduler() {
+            arc_scheduler.stop_waiting(to);
+        }
+    }
+
+    Ok(())
+}
+
 fn send_self_exit_message(
     process: &Process,
     heap: &mut ProcessHeap,
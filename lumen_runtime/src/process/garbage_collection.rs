@@ -0,0 +1,243 @@
+//! Garbage collections requested by `erlang:garbage_collect/0,1,2`.
+//!
+//! A process can only be collected when it is not running, so requests are queued and run by the
+//! scheduler that owns the process, either after the process's current run or, if the process is
+//! waiting, the next time the scheduler runs.
+
+use core::convert::TryInto;
+use core::ptr::{self, NonNull};
+
+use alloc::sync::Arc;
+
+use hashbrown::HashMap;
+
+use liblumen_core::locks::Mutex;
+
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::{Process, ProcessFlags, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, CloneToProcess, HeapFragment};
+
+use crate::registry::pid_to_process;
+use crate::scheduler::Scheduled;
+
+/// The `type` option of `erlang:garbage_collect/2`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    /// A full sweep of both the young and old generations
+    Major,
+    /// A minor collection of the young generation, unless a full sweep is already needed
+    Minor,
+}
+
+impl Default for Type {
+    fn default() -> Self {
+        Type::Major
+    }
+}
+
+/// Requests that `target` collect garbage.  If `request_id` is given, `requester` is sent
+/// `{garbage_collect, RequestId, Collected}` once the collection is done.
+///
+/// Returns `false` without requesting a collection if `target` is exiting.
+pub fn request(
+    requester: &Process,
+    target: Arc<Process>,
+    r#type: Type,
+    request_id: Option<Term>,
+) -> AllocResult<bool> {
+    if target.is_exiting() {
+        return Ok(false);
+    }
+
+    let reply = match request_id {
+        Some(request_id) => Some(Reply::new(requester, request_id)?),
+        None => None,
+    };
+
+    REQUESTS
+        .lock()
+        .entry(target.pid())
+        .or_insert_with(Default::default)
+        .push(Request { r#type, reply });
+    target.set_flags(ProcessFlags::ForceGC);
+
+    // A waiting process does not run again until it receives a message, so its scheduler needs to
+    // collect it between runs of other processes.
+    if let Some(arc_scheduler) = target.scheduler() {
+        arc_scheduler.request_garbage_collection(target);
+    }
+
+    Ok(true)
+}
+
+/// Runs the collections requested for `process` after it ran, and replies to any asynchronous
+/// requests.
+pub fn collect_requested(process: &Process) {
+    if !collect(process) {
+        process.exit(atom!("killed"));
+    }
+}
+
+/// Runs the collections requested for `process` if it is waiting, and replies to any asynchronous
+/// requests.  Runnable and running processes collect after they next run instead.
+pub fn collect_requested_if_waiting(process: &Process) {
+    let within_max_heap_size = {
+        // Holding the status keeps the process from being made runnable and run during the
+        // collection.
+        let status = process.status.read();
+
+        if *status != Status::Waiting {
+            return;
+        }
+
+        collect(process)
+    };
+
+    if !within_max_heap_size {
+        process.exit(atom!("killed"));
+
+        // Exiting processes are only cleaned up from the run queues
+        if let Some(arc_scheduler) = process.scheduler() {
+            arc_scheduler.stop_waiting(process);
+        }
+    }
+}
+
+/// Removes the `{garbage_collect, RequestId, Collected}` reply to the request with `request_id`
+/// from the mailbox of `process`, returning `Collected`.  If the reply has not arrived yet,
+/// `process` waits for it instead and `None` is returned.
+///
+/// The mailbox stays locked until `process` is waiting, so that a reply sent in between still
+/// makes `process` runnable again.
+pub fn receive_reply(process: &Process, request_id: Term) -> Option<bool> {
+    let mailbox_guard = process.mailbox.lock();
+    let mut mailbox = mailbox_guard.borrow_mut();
+
+    let found = mailbox.iter().enumerate().find_map(|(index, message)| {
+        reply_collected(message.data(), request_id).map(|collected| (index, collected))
+    });
+
+    match found {
+        Some((index, collected)) => {
+            mailbox.remove(index, process);
+
+            Some(collected)
+        }
+        None => {
+            process.wait();
+
+            None
+        }
+    }
+}
+
+// Private
+
+/// `Collected` if `message` is the `{garbage_collect, RequestId, Collected}` reply to the request
+/// with `request_id`.
+fn reply_collected(message: &Term, request_id: Term) -> Option<bool> {
+    let tuple: Boxed<Tuple> = (*message).try_into().ok()?;
+
+    if tuple.len() == 3 && tuple[0] == atom!("garbage_collect") && tuple[1] == request_id {
+        tuple[2].try_into().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns `false` if `process` needs to be killed for exceeding its `max_heap_size`.
+fn collect(process: &Process) -> bool {
+    process.clear_flags(ProcessFlags::ForceGC);
+
+    let requests = match REQUESTS.lock().remove(&process.pid()) {
+        Some(requests) => requests,
+        None => return true,
+    };
+
+    let collected = !process.is_exiting();
+
+    let within_max_heap_size = if collected {
+        if requests.iter().any(|request| request.r#type == Type::Major) {
+            process.set_flags(ProcessFlags::NeedFullSweep);
+        }
+
+        super::garbage_collect_within_max_heap_size(process, 0, &mut [])
+    } else {
+        true
+    };
+
+    for request in requests {
+        if let Some(reply) = request.reply {
+            reply.send(collected);
+        }
+    }
+
+    within_max_heap_size
+}
+
+struct Request {
+    r#type: Type,
+    reply: Option<Reply>,
+}
+
+/// `{garbage_collect, RequestId, true}` in a heap fragment, so that it can be sent to `requester`
+/// from the scheduler of the collected process.
+struct Reply {
+    requester: Pid,
+    message: Term,
+    heap_fragment: NonNull<HeapFragment>,
+}
+
+impl Reply {
+    fn new(requester: &Process, request_id: Term) -> AllocResult<Self> {
+        let tag = atom!("garbage_collect");
+        let process_message = requester.tuple_from_slice(&[tag, request_id, true.into()])?;
+        let (message, heap_fragment) = process_message.clone_to_fragment()?;
+
+        Ok(Self {
+            requester: requester.pid(),
+            message,
+            heap_fragment,
+        })
+    }
+
+    fn send(self, collected: bool) {
+        match pid_to_process(&self.requester) {
+            Some(requester_arc_process) => {
+                if !collected {
+                    let mut tuple: Boxed<Tuple> = self.message.try_into().unwrap();
+                    tuple.set_element(2, false.into()).unwrap();
+                }
+
+                requester_arc_process.send_heap_message(self.heap_fragment, self.message);
+
+                let stopped_waiting = {
+                    let mut writable_status = requester_arc_process.status.write();
+
+                    if *writable_status == Status::Waiting {
+                        *writable_status = Status::Runnable;
+
+                        true
+                    } else {
+                        false
+                    }
+                };
+
+                if stopped_waiting {
+                    if let Some(arc_scheduler) = requester_arc_process.scheduler() {
+                        arc_scheduler.stop_waiting(&requester_arc_process);
+                    }
+                }
+            }
+            None => unsafe { ptr::drop_in_place(self.heap_fragment.as_ptr()) },
+        }
+    }
+}
+
+// The heap fragment is only accessed by the scheduler that sends the reply
+unsafe impl Send for Reply {}
+
+lazy_static! {
+    static ref REQUESTS: Mutex<HashMap<Pid, Vec<Request>>> = Default::default();
+}
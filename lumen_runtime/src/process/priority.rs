@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use liblumen_alloc::erts::process::{Priority, Process};
+
+use crate::scheduler::Scheduled;
+
+/// Promotes a process to at least `priority` until the promotion is dropped.
+///
+/// This prevents priority inversion when a higher priority process is waiting on a lower priority
+/// process, such as one holding a lock-like resource: the lower priority process is promoted to
+/// the waiter's priority while holding the resource, so it is not starved by processes with a
+/// priority in between.  Promotions nest, so the process runs with the highest priority among its
+/// own priority and all outstanding promotions.
+///
+/// A process that is already queued to run is moved to the run queue for its new priority, both
+/// when it is promoted and when the promotion ends.
+#[must_use = "the promotion ends when it is dropped"]
+pub struct PriorityPromotion {
+    arc_process: Arc<Process>,
+    priority: Priority,
+}
+
+impl PriorityPromotion {
+    pub fn new(arc_process: Arc<Process>, priority: Priority) -> Self {
+        arc_process.promote_priority(priority);
+        reprioritize(&arc_process);
+
+        Self {
+            arc_process,
+            priority,
+        }
+    }
+}
+
+impl Drop for PriorityPromotion {
+    fn drop(&mut self) {
+        self.arc_process.demote_priority(self.priority);
+        reprioritize(&self.arc_process);
+    }
+}
+
+fn reprioritize(process: &Process) {
+    if let Some(arc_scheduler) = process.scheduler() {
+        arc_scheduler.reprioritize(process);
+    }
+}
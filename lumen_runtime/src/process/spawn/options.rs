@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use liblumen_alloc::erts::exception::{AllocResult, Exception};
 use liblumen_alloc::erts::process::alloc::{default_heap_size, heap, next_heap_size};
-use liblumen_alloc::erts::process::{Priority, Process};
+use liblumen_alloc::erts::process::{MaxHeapSize, MessageQueueData, Priority, Process};
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::{badarg, ModuleFunctionArity};
 
@@ -16,39 +16,6 @@ pub struct Connection {
     pub monitor_reference: Option<Term>,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct MaxHeapSize {
-    size: Option<usize>,
-    kill: Option<bool>,
-    error_logger: Option<bool>,
-}
-
-#[derive(Clone, Copy, Debug)]
-pub enum MessageQueueData {
-    OnHeap,
-    OffHeap,
-}
-
-impl Default for MessageQueueData {
-    fn default() -> Self {
-        MessageQueueData::OnHeap
-    }
-}
-
-impl TryFrom<Term> for MessageQueueData {
-    type Error = Exception;
-
-    fn try_from(term: Term) -> Result<Self, Self::Error> {
-        let atom: Atom = term.try_into()?;
-
-        match atom.name() {
-            "off_heap" => Ok(Self::OffHeap),
-            "on_heap" => Ok(Self::OnHeap),
-            _ => Err(badarg!().into()),
-        }
-    }
-}
-
 #[derive(Clone, Copy, Debug)]
 pub struct Options {
     pub link: bool,
@@ -118,6 +85,20 @@ impl Options {
             heap_size,
         );
 
+        if let Some(min_bin_vheap_size) = self.min_bin_vheap_size {
+            process.set_min_vheap_size(min_bin_vheap_size);
+        }
+
+        if let Some(fullsweep_after) = self.fullsweep_after {
+            process.set_max_gen_gcs(fullsweep_after);
+        }
+
+        if let Some(max_heap_size) = self.max_heap_size {
+            process.set_max_heap_size(max_heap_size);
+        }
+
+        process.set_message_queue_data(self.message_queue_data);
+
         Ok(process)
     }
 
@@ -127,7 +108,7 @@ impl Options {
         match self.priority {
             Some(priority) => priority,
             None => match parent_process {
-                Some(process) => process.priority,
+                Some(process) => process.priority(),
                 None => Default::default(),
             },
         }
@@ -184,7 +165,14 @@ impl Options {
                         }
                         Err(_) => false,
                     },
-                    "max_heap_size" => unimplemented!(),
+                    "max_heap_size" => match tuple[1].try_into() {
+                        Ok(max_heap_size) => {
+                            self.max_heap_size = Some(max_heap_size);
+
+                            true
+                        }
+                        Err(_) => false,
+                    },
                     "message_queue_data" => match tuple[1].try_into() {
                         Ok(message_queue_data) => {
                             self.message_queue_data = message_queue_data;
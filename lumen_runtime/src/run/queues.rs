@@ -1,3 +1,6 @@
+#[cfg(test)]
+mod test;
+
 use core::borrow::Borrow;
 use core::fmt::{self, Debug};
 use core::hash::Hash;
@@ -7,18 +10,18 @@ use std::sync::Arc;
 
 use liblumen_alloc::erts::process::{Priority, Process, Status};
 
-use crate::run::queues::delayed::Delayed;
 use crate::run::queues::immediate::Immediate;
+use crate::run::queues::normal_low::NormalLow;
 use crate::run::queues::Next::*;
 use crate::run::Run;
 
-mod delayed;
 mod immediate;
+mod normal_low;
 
 #[derive(Debug, Default)]
 pub struct Queues {
     waiting: Waiting,
-    normal_low: Delayed,
+    normal_low: NormalLow,
     high: Immediate,
     max: Immediate,
 }
@@ -35,7 +38,8 @@ impl Queues {
     #[cfg(test)]
     pub fn run_queue_len(&self, priority: Priority) -> usize {
         match priority {
-            Priority::Low | Priority::Normal => self.normal_low.len(),
+            Priority::Low => self.normal_low.low_len(),
+            Priority::Normal => self.normal_low.normal_len(),
             Priority::High => self.high.len(),
             Priority::Max => self.max.len(),
         }
@@ -54,15 +58,21 @@ impl Queues {
     }
 
     pub fn enqueue(&mut self, arc_process: Arc<Process>) {
-        match arc_process.priority {
-            Priority::Low | Priority::Normal => self.normal_low.enqueue(arc_process),
+        match arc_process.priority() {
+            Priority::Low => self.normal_low.enqueue_low(arc_process),
+            Priority::Normal => self.normal_low.enqueue_normal(arc_process),
             Priority::High => self.high.enqueue(arc_process),
             Priority::Max => self.max.enqueue(arc_process),
         }
     }
 
     pub fn len(&self) -> usize {
-        self.waiting.len() + self.normal_low.len() + self.high.len() + self.max.len()
+        self.waiting.len() + self.runnable_len()
+    }
+
+    /// The number of processes that are ready to run, so excludes waiting processes
+    pub fn runnable_len(&self) -> usize {
+        self.normal_low.len() + self.high.len() + self.max.len()
     }
 
     /// Returns the process is not pushed back because it is exiting
@@ -89,6 +99,32 @@ impl Queues {
         }
     }
 
+    /// Removes a runnable process, highest priority first, so that an idle scheduler can run it.
+    ///
+    /// Waiting processes are never stolen, so that they are woken by the scheduler they are
+    /// waiting on.
+    pub fn steal(&mut self) -> Option<Arc<Process>> {
+        self.max
+            .steal()
+            .or_else(|| self.high.steal())
+            .or_else(|| self.normal_low.steal())
+    }
+
+    /// Moves `process` to the run queue for its current priority if it is queued to run in another
+    /// run queue.  A process that is already in the run queue for its priority keeps its place.
+    pub fn reprioritize(&mut self, process: &Process) {
+        let priority = process.priority();
+
+        let option_arc_process = Priority::ALL
+            .iter()
+            .filter(|queued_priority| **queued_priority != priority)
+            .find_map(|queued_priority| self.remove(*queued_priority, process));
+
+        if let Some(arc_process) = option_arc_process {
+            self.enqueue(arc_process);
+        }
+    }
+
     pub fn stop_waiting(&mut self, process: &Process) {
         match self.waiting.get(process) {
             Some(arc_process) => {
@@ -104,6 +140,17 @@ impl Queues {
 
 // Private
 
+impl Queues {
+    fn remove(&mut self, priority: Priority, process: &Process) -> Option<Arc<Process>> {
+        match priority {
+            Priority::Low => self.normal_low.remove_low(process),
+            Priority::Normal => self.normal_low.remove_normal(process),
+            Priority::High => self.high.remove(process),
+            Priority::Max => self.max.remove(process),
+        }
+    }
+}
+
 enum Next {
     Wait,
     PushBack,
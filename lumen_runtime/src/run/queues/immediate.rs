@@ -29,4 +29,20 @@ impl Immediate {
     pub fn enqueue(&mut self, process: Arc<Process>) {
         self.0.push_back(process);
     }
+
+    /// Removes `process` if it is queued
+    pub fn remove(&mut self, process: &Process) -> Option<Arc<Process>> {
+        let index = self
+            .0
+            .iter()
+            .position(|arc_process| arc_process.pid() == process.pid())?;
+
+        self.0.remove(index)
+    }
+
+    /// Removes the most recently enqueued process, which is the one that would wait the longest
+    /// to run on this queue
+    pub fn steal(&mut self) -> Option<Arc<Process>> {
+        self.0.pop_back()
+    }
 }
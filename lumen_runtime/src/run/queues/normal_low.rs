@@ -0,0 +1,85 @@
+use alloc::sync::Arc;
+
+use liblumen_alloc::erts::process::Process;
+
+use crate::run::queues::immediate::Immediate;
+use crate::run::Run;
+
+/// The run queue for `Priority::Normal` and `Priority::Low` processes.
+///
+/// `Priority::Low` processes are kept separate from `Priority::Normal` processes, so that a
+/// `Priority::Low` process runs only after `NORMAL_PER_LOW` `Priority::Normal` processes have been
+/// selected or when there are no `Priority::Normal` processes to run.
+/// -- https://github.com/erlang/otp/blob/fe2b1323a3866ed0a9712e9d12e1f8f84793ec47/erts/emulator/beam/erl_process.c#L9601-L9606
+#[derive(Debug, Default)]
+pub struct NormalLow {
+    normal: Immediate,
+    low: Immediate,
+    // The number of `Priority::Normal` processes selected since the last `Priority::Low` process
+    normal_since_low: u8,
+}
+
+impl NormalLow {
+    // same as `RESCHEDULE_LOW` in BEAM
+    const NORMAL_PER_LOW: u8 = 8;
+
+    #[cfg(test)]
+    pub fn contains(&self, value: &Arc<Process>) -> bool {
+        self.normal.contains(value) || self.low.contains(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.normal.len() + self.low.len()
+    }
+
+    #[cfg(test)]
+    pub fn low_len(&self) -> usize {
+        self.low.len()
+    }
+
+    #[cfg(test)]
+    pub fn normal_len(&self) -> usize {
+        self.normal.len()
+    }
+
+    pub fn dequeue(&mut self) -> Run {
+        if 0 < self.low.len()
+            && (self.normal.len() == 0 || Self::NORMAL_PER_LOW <= self.normal_since_low)
+        {
+            self.normal_since_low = 0;
+
+            self.low.dequeue()
+        } else {
+            match self.normal.dequeue() {
+                Run::None => Run::None,
+                run => {
+                    self.normal_since_low = self.normal_since_low.saturating_add(1);
+
+                    run
+                }
+            }
+        }
+    }
+
+    pub fn enqueue_low(&mut self, arc_process: Arc<Process>) {
+        self.low.enqueue(arc_process);
+    }
+
+    pub fn enqueue_normal(&mut self, arc_process: Arc<Process>) {
+        self.normal.enqueue(arc_process);
+    }
+
+    pub fn remove_low(&mut self, process: &Process) -> Option<Arc<Process>> {
+        self.low.remove(process)
+    }
+
+    pub fn remove_normal(&mut self, process: &Process) -> Option<Arc<Process>> {
+        self.normal.remove(process)
+    }
+
+    /// Removes the most recently enqueued `Priority::Low` process, as it would wait the longest to
+    /// run, or if there are none, the most recently enqueued `Priority::Normal` process.
+    pub fn steal(&mut self) -> Option<Arc<Process>> {
+        self.low.steal().or_else(|| self.normal.steal())
+    }
+}
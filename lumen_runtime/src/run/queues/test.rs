@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use liblumen_alloc::erts::process::{Priority, Process};
+
+use crate::process;
+use crate::process::priority::PriorityPromotion;
+use crate::run::queues::Queues;
+use crate::run::Run;
+
+#[test]
+fn low_priority_process_runs_after_normal_processes() {
+    let parent_arc_process = process::test_init();
+    let low_arc_process = process::test(&parent_arc_process);
+    low_arc_process.set_priority(Priority::Low);
+
+    let mut queues = Queues::default();
+    queues.enqueue(Arc::clone(&low_arc_process));
+
+    let normal_arc_processes: Vec<Arc<Process>> =
+        (0..9).map(|_| process::test(&parent_arc_process)).collect();
+
+    for normal_arc_process in &normal_arc_processes {
+        queues.enqueue(Arc::clone(normal_arc_process));
+    }
+
+    for normal_arc_process in &normal_arc_processes[0..8] {
+        assert_dequeues(&mut queues, normal_arc_process);
+    }
+
+    assert_dequeues(&mut queues, &low_arc_process);
+    assert_dequeues(&mut queues, &normal_arc_processes[8]);
+    assert!(match queues.dequeue() {
+        Run::None => true,
+        _ => false,
+    });
+}
+
+#[test]
+fn low_priority_process_runs_when_no_normal_processes() {
+    let parent_arc_process = process::test_init();
+    let low_arc_process = process::test(&parent_arc_process);
+    low_arc_process.set_priority(Priority::Low);
+
+    let mut queues = Queues::default();
+    queues.enqueue(Arc::clone(&low_arc_process));
+
+    assert_dequeues(&mut queues, &low_arc_process);
+}
+
+#[test]
+fn promoted_process_is_queued_with_promoted_priority() {
+    let parent_arc_process = process::test_init();
+    let low_arc_process = process::test(&parent_arc_process);
+    low_arc_process.set_priority(Priority::Low);
+
+    let mut queues = Queues::default();
+
+    {
+        let _promotion = PriorityPromotion::new(Arc::clone(&low_arc_process), Priority::High);
+
+        assert_eq!(low_arc_process.priority(), Priority::High);
+
+        queues.enqueue(Arc::clone(&low_arc_process));
+
+        assert_eq!(queues.run_queue_len(Priority::High), 1);
+        assert_eq!(queues.run_queue_len(Priority::Low), 0);
+    }
+
+    assert_eq!(low_arc_process.priority(), Priority::Low);
+}
+
+#[test]
+fn reprioritize_moves_queued_process_to_run_queue_for_priority() {
+    let parent_arc_process = process::test_init();
+    let arc_process = process::test(&parent_arc_process);
+
+    let mut queues = Queues::default();
+    queues.enqueue(Arc::clone(&arc_process));
+
+    arc_process.promote_priority(Priority::High);
+    queues.reprioritize(&arc_process);
+
+    assert_eq!(queues.run_queue_len(Priority::High), 1);
+    assert_eq!(queues.run_queue_len(Priority::Normal), 0);
+
+    arc_process.demote_priority(Priority::High);
+    queues.reprioritize(&arc_process);
+
+    assert_eq!(queues.run_queue_len(Priority::High), 0);
+    assert_eq!(queues.run_queue_len(Priority::Normal), 1);
+}
+
+#[test]
+fn reprioritize_keeps_place_of_process_with_same_priority() {
+    let parent_arc_process = process::test_init();
+    let first_arc_process = process::test(&parent_arc_process);
+    let second_arc_process = process::test(&parent_arc_process);
+
+    let mut queues = Queues::default();
+    queues.enqueue(Arc::clone(&first_arc_process));
+    queues.enqueue(Arc::clone(&second_arc_process));
+
+    queues.reprioritize(&first_arc_process);
+
+    assert_dequeues(&mut queues, &first_arc_process);
+    assert_dequeues(&mut queues, &second_arc_process);
+}
+
+fn assert_dequeues(queues: &mut Queues, expected_arc_process: &Arc<Process>) {
+    match queues.dequeue() {
+        Run::Now(actual_arc_process) => {
+            assert!(Arc::ptr_eq(&actual_arc_process, expected_arc_process))
+        }
+        Run::None => panic!("Expected {:?} to be dequeued", expected_arc_process),
+    }
+}
@@ -1,11 +1,17 @@
+pub mod statistics;
 #[cfg(test)]
 pub mod test;
 
 use core::fmt::{self, Debug};
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::mem;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
 
 use alloc::sync::{Arc, Weak};
 
+use std::io;
+use std::thread::{self, JoinHandle, Thread};
+
 use hashbrown::HashMap;
 
 use liblumen_core::locks::{Mutex, RwLock};
@@ -14,7 +20,7 @@ use liblumen_alloc::erts::exception::{Result, SystemException};
 use liblumen_alloc::erts::process::code::Code;
 #[cfg(test)]
 use liblumen_alloc::erts::process::Priority;
-use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::process::{Process, ProcessFlags, Status};
 pub use liblumen_alloc::erts::scheduler::{id, ID};
 use liblumen_alloc::erts::term::prelude::*;
 
@@ -23,8 +29,11 @@ use crate::process::spawn;
 use crate::process::spawn::options::{Connection, Options};
 use crate::registry::put_pid_to_process;
 use crate::run::{self, Run};
+use crate::system::host::cpus;
 use crate::timer::Hierarchy;
 
+use self::statistics::Statistics;
+
 pub trait Scheduled {
     fn scheduler(&self) -> Option<Arc<Scheduler>>;
 }
@@ -48,6 +57,12 @@ pub struct Scheduler {
     // References are always 64-bits even on 32-bit platforms
     reference_count: AtomicU64,
     run_queues: RwLock<run::queues::Queues>,
+    // The thread running `run`, so that it can be unparked when there is work for it
+    thread: Mutex<Option<Thread>>,
+    parked: AtomicBool,
+    // Processes that were asked to collect garbage by another process, and may be waiting
+    garbage_collection_requests: Mutex<Vec<Arc<Process>>>,
+    pub statistics: Statistics,
     // Non-monotonic unique integers are scoped to the scheduler ID and then use this per-scheduler
     // `u64`.
     unique_integer: AtomicU64,
@@ -95,9 +110,51 @@ impl Scheduler {
     /// > 8. Pick a process to execute
     /// > -- [The Scheduler Loop](https://blog.stenmans.org/theBeamBook/#_the_scheduler_loop)
     pub fn run(&self) {
+        *self.thread.lock() = Some(thread::current());
+
         loop {
-            // TODO sleep or steal if nothing run
-            let _ = self.run_once();
+            if !self.run_once() && !self.steal() {
+                self.park();
+            }
+        }
+    }
+
+    /// Parks the scheduler thread until a process on this scheduler becomes runnable, work can be
+    /// stolen from another scheduler, or the next timer on this scheduler times out.
+    ///
+    /// Returns immediately if the scheduler was unparked since it last parked, so that a wakeup
+    /// between `run_once` finding nothing to run and parking is not lost.
+    pub fn park(&self) {
+        self.parked.store(true, Ordering::SeqCst);
+
+        // A process may have been enqueued after `run_once`, but before `parked` was set, in which
+        // case the enqueuer did not know to unpark this scheduler.
+        if self.runnable_len() == 0 {
+            let option_milliseconds = self.hierarchy.read().milliseconds_until_next_timeout();
+
+            self.statistics.start_parking();
+
+            match option_milliseconds {
+                Some(milliseconds) => thread::park_timeout(Duration::from_millis(milliseconds)),
+                None => thread::park(),
+            }
+
+            self.statistics.stop_parking();
+        }
+
+        self.parked.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the scheduler thread is parked, waiting for work.
+    pub fn is_parked(&self) -> bool {
+        self.parked.load(Ordering::SeqCst)
+    }
+
+    /// Unparks the scheduler thread for an external event.  If the thread is not parked, its next
+    /// `park` returns immediately.
+    pub fn unpark(&self) {
+        if let Some(thread) = self.thread.lock().as_ref() {
+            thread.unpark();
         }
     }
 
@@ -116,54 +173,67 @@ impl Scheduler {
     #[must_use]
     pub fn run_once(&self) -> bool {
         self.hierarchy.write().timeout();
-
-        loop {
-            // separate from `match` below so that WriteGuard temporary is not held while process
-            // runs.
-            let run = self.run_queues.write().dequeue();
-
-            match run {
-                Run::Now(arc_process) => {
-                    // Don't allow exiting processes to run again.
-                    //
-                    // Without this check, a process.exit() from outside the process during WAITING
-                    // will return to the Frame that called `process.wait()`
-                    if !arc_process.is_exiting() {
-                        match Process::run(&arc_process) {
-                            Ok(()) => (),
-                            Err(exception) => match exception {
-                                SystemException::Alloc(_) => {
-                                    match arc_process.garbage_collect(0, &mut []) {
-                                        Ok(_freed) => (),
-                                        Err(gc_err) => {
-                                            panic!("fatal garbage collection error: {:?}", gc_err)
-                                        }
-                                    }
-                                }
-                                err => panic!("system error: {}", err),
-                            },
+        self.collect_requested_garbage();
+
+        // separate from `match` below so that WriteGuard temporary is not held while process
+        // runs.
+        let run = self.run_queues.write().dequeue();
+
+        match run {
+            Run::Now(arc_process) => {
+                // Don't allow exiting processes to run again.
+                //
+                // Without this check, a process.exit() from outside the process during WAITING
+                // will return to the Frame that called `process.wait()`
+                if !arc_process.is_exiting() {
+                    let reductions_before = arc_process.total_reductions.load(Ordering::SeqCst);
+                    self.statistics.start_running();
+
+                    let result = Process::run(&arc_process);
+
+                    self.statistics.stop_running(
+                        arc_process.total_reductions.load(Ordering::SeqCst) - reductions_before,
+                    );
+
+                    match result {
+                        // Binaries are allocated off-heap, so a process can hold onto many large
+                        // binaries without ever filling its heap and collecting them.
+                        Ok(()) => {
+                            if !arc_process.is_exiting()
+                                && arc_process.should_collect_virtual_heap()
+                            {
+                                process::garbage_collect(&arc_process, 0, &mut [])
+                            }
                         }
-                    } else {
-                        arc_process.reduce()
-                    }
-
-                    match self.run_queues.write().requeue(arc_process) {
-                        Some(exiting_arc_process) => match *exiting_arc_process.status.read() {
-                            Status::Exiting(ref exception) => {
-                                process::log_exit(&exiting_arc_process, exception);
-                                process::propagate_exit(&exiting_arc_process, exception);
+                        Err(exception) => match exception {
+                            SystemException::Alloc(_) => {
+                                process::garbage_collect(&arc_process, 0, &mut [])
                             }
-                            _ => unreachable!(),
+                            err => panic!("system error: {}", err),
                         },
-                        None => (),
-                    };
+                    }
+                } else {
+                    arc_process.reduce()
+                }
 
-                    break true;
+                if arc_process.are_flags_set(ProcessFlags::ForceGC) {
+                    process::garbage_collection::collect_requested(&arc_process);
                 }
-                Run::Delayed => continue,
-                // TODO steal processes or sleep if nothing to steal
-                Run::None => break false,
+
+                match self.run_queues.write().requeue(arc_process) {
+                    Some(exiting_arc_process) => match *exiting_arc_process.status.read() {
+                        Status::Exiting(ref exception) => {
+                            process::log_exit(&exiting_arc_process, exception);
+                            process::propagate_exit(&exiting_arc_process, exception);
+                        }
+                        _ => unreachable!(),
+                    },
+                    None => (),
+                };
+
+                true
             }
+            Run::None => false,
         }
     }
 
@@ -171,6 +241,11 @@ impl Scheduler {
         self.run_queues.read().len()
     }
 
+    /// The number of processes that are ready to run on this scheduler
+    pub fn runnable_len(&self) -> usize {
+        self.run_queues.read().runnable_len()
+    }
+
     #[cfg(test)]
     pub fn run_queue_len(&self, priority: Priority) -> usize {
         self.run_queues.read().run_queue_len(priority)
@@ -209,6 +284,9 @@ impl Scheduler {
         let arc_process = Arc::new(process);
 
         writable_run_queues.enqueue(Arc::clone(&arc_process));
+        drop(writable_run_queues);
+
+        self.notify();
 
         arc_process
     }
@@ -282,24 +360,115 @@ impl Scheduler {
         let mut writable_run_queues = self.run_queues.write();
 
         writable_run_queues.enqueue(Arc::clone(&arc_process));
+        drop(writable_run_queues);
+
+        self.notify();
 
         put_pid_to_process(&arc_process);
 
         Ok(arc_process)
     }
 
+    /// Migrates a runnable process from the scheduler with the most runnable processes to this
+    /// scheduler.
+    ///
+    /// Returns `true` if a process was stolen.
+    #[must_use]
+    pub fn steal(&self) -> bool {
+        let registered = registered();
+        let busiest = registered
+            .iter()
+            .filter(|arc_scheduler| arc_scheduler.id != self.id)
+            .map(|arc_scheduler| (arc_scheduler.runnable_len(), arc_scheduler))
+            .filter(|(runnable_len, _)| 0 < *runnable_len)
+            .max_by_key(|(runnable_len, _)| *runnable_len);
+
+        match busiest {
+            Some((_, victim)) => self.steal_from(victim),
+            None => false,
+        }
+    }
+
+    /// Migrates a runnable process from `victim` to this scheduler.
+    ///
+    /// Returns `true` if a process was stolen.
+    #[must_use]
+    pub fn steal_from(&self, victim: &Scheduler) -> bool {
+        // separate from `match` below so that the victim's run queues are not locked at the same
+        // time as this scheduler's run queues.
+        let stolen = victim.run_queues.write().steal();
+
+        match stolen {
+            Some(arc_process) => {
+                // Wakeups and references use the scheduler ID, so it must be updated before the
+                // process can run or wait on this scheduler.
+                arc_process.schedule_with(self.id);
+                self.run_queues.write().enqueue(arc_process);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves `process` to the run queue for its current priority if it is queued to run, so that a
+    /// change in its priority takes effect before it next runs.
+    pub fn reprioritize(&self, process: &Process) {
+        self.run_queues.write().reprioritize(process);
+    }
+
     pub fn stop_waiting(&self, process: &Process) {
         self.run_queues.write().stop_waiting(process);
+        self.notify();
+    }
+
+    /// Collects garbage for `arc_process` on this scheduler if it is waiting.  Runnable and running
+    /// processes collect garbage after they next run instead.
+    pub fn request_garbage_collection(&self, arc_process: Arc<Process>) {
+        self.garbage_collection_requests.lock().push(arc_process);
+        self.unpark();
     }
 
     // Private
 
+    fn collect_requested_garbage(&self) {
+        let arc_processes = mem::replace(&mut *self.garbage_collection_requests.lock(), Vec::new());
+
+        for arc_process in arc_processes {
+            // A process stolen by another scheduler collects after it runs there, as only its
+            // current scheduler knows it is not running.
+            if arc_process.scheduler_id() == Some(self.id) {
+                process::garbage_collection::collect_requested_if_waiting(&arc_process);
+            }
+        }
+    }
+
+    /// Called after a process is made runnable on this scheduler.  If this scheduler is parked, it
+    /// is unparked to run the process; otherwise, it is busy, so a parked scheduler is unparked to
+    /// steal the extra work.
+    fn notify(&self) {
+        if self.is_parked() {
+            self.unpark();
+        } else if 1 < self.runnable_len() {
+            if let Some(parked) = registered()
+                .iter()
+                .find(|arc_scheduler| arc_scheduler.id != self.id && arc_scheduler.is_parked())
+            {
+                parked.unpark();
+            }
+        }
+    }
+
     fn new() -> Scheduler {
         Scheduler {
             id: id::next(),
             hierarchy: Default::default(),
             reference_count: AtomicU64::new(0),
             run_queues: Default::default(),
+            thread: Mutex::new(None),
+            parked: AtomicBool::new(false),
+            garbage_collection_requests: Default::default(),
+            statistics: Default::default(),
             unique_integer: AtomicU64::new(0),
         }
     }
@@ -370,10 +539,56 @@ impl Spawned {
     }
 }
 
+/// All schedulers, in `ID` order.
+pub fn registered() -> Vec<Arc<Scheduler>> {
+    // Collected, so that `SCHEDULER_BY_ID` is unlocked before any `Arc<Scheduler>` is dropped, as
+    // dropping the last reference to a scheduler locks it too.
+    let mut registered: Vec<Arc<Scheduler>> = SCHEDULER_BY_ID
+        .lock()
+        .values()
+        .filter_map(|weak_scheduler| weak_scheduler.upgrade())
+        .collect();
+    registered.sort_by_key(|arc_scheduler| arc_scheduler.id);
+
+    registered
+}
+
+/// The number of scheduler threads to start when none is configured: one per logical CPU.
+pub fn default_threads() -> usize {
+    cpus::num_logical()
+}
+
+/// The number of scheduler threads started with `spawn_threads`.  When no threads were started,
+/// the embedder runs the scheduler on its own thread, so there is `1`.
+pub fn threads() -> usize {
+    match THREADS.load(Ordering::SeqCst) {
+        0 => 1,
+        threads => threads,
+    }
+}
+
+/// Starts `count` threads that each run their own `Scheduler` forever.
+///
+/// Processes are scheduled on their parent's scheduler, so idle schedulers steal runnable
+/// processes from the busiest scheduler to spread the work across the threads.
+pub fn spawn_threads(count: usize) -> io::Result<Vec<JoinHandle<()>>> {
+    THREADS.fetch_add(count, Ordering::SeqCst);
+
+    (0..count)
+        .map(|index| {
+            thread::Builder::new()
+                .name(format!("scheduler {}", index))
+                .spawn(|| Scheduler::current().run())
+        })
+        .collect()
+}
+
 thread_local! {
   static SCHEDULER: Arc<Scheduler> = Scheduler::registered();
 }
 
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
 lazy_static! {
     static ref SCHEDULER_BY_ID: Mutex<HashMap<ID, Weak<Scheduler>>> =
         Mutex::new(Default::default());
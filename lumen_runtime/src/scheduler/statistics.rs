@@ -0,0 +1,147 @@
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::time::Duration;
+
+use std::time::Instant;
+
+use liblumen_core::locks::Mutex;
+
+use super::{registered, ID};
+
+/// Counters kept by each `Scheduler` for `erlang:statistics/1`
+#[derive(Debug, Default)]
+pub struct Statistics {
+    reductions: AtomicU64,
+    context_switches: AtomicU64,
+    running: AtomicBool,
+    parked_time: Mutex<ParkedTime>,
+}
+
+impl Statistics {
+    /// The number of reductions run by processes on this scheduler
+    pub fn reductions(&self) -> u64 {
+        self.reductions.load(Ordering::SeqCst)
+    }
+
+    /// The number of times a process was switched in to run on this scheduler
+    pub fn context_switches(&self) -> u64 {
+        self.context_switches.load(Ordering::SeqCst)
+    }
+
+    /// Whether a process is currently running on this scheduler
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    pub(super) fn start_running(&self) {
+        self.context_switches.fetch_add(1, Ordering::SeqCst);
+        self.running.store(true, Ordering::SeqCst);
+    }
+
+    pub(super) fn stop_running(&self, reductions: u64) {
+        self.reductions.fetch_add(reductions, Ordering::SeqCst);
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    pub(super) fn start_parking(&self) {
+        self.parked_time.lock().start(Instant::now());
+    }
+
+    pub(super) fn stop_parking(&self) {
+        self.parked_time.lock().stop(Instant::now());
+    }
+}
+
+#[derive(Debug, Default)]
+struct ParkedTime {
+    total: Duration,
+    since: Option<Instant>,
+}
+
+impl ParkedTime {
+    fn reset(&mut self, now: Instant) {
+        self.total = Duration::default();
+
+        if self.since.is_some() {
+            self.since = Some(now);
+        }
+    }
+
+    fn start(&mut self, now: Instant) {
+        self.since = Some(now);
+    }
+
+    fn stop(&mut self, now: Instant) {
+        if let Some(since) = self.since.take() {
+            self.total += now.saturating_duration_since(since);
+        }
+    }
+
+    fn total_at(&self, now: Instant) -> Duration {
+        match self.since {
+            Some(since) => self.total + now.saturating_duration_since(since),
+            None => self.total,
+        }
+    }
+}
+
+/// The time, per scheduler, that the scheduler was active, as opposed to parked, and the total
+/// time since `scheduler_wall_time` was enabled.
+pub struct WallTime {
+    pub id: ID,
+    pub active: Duration,
+    pub total: Duration,
+}
+
+/// Returns whether `scheduler_wall_time` is enabled.
+pub fn is_wall_time_enabled() -> bool {
+    WALL_TIME_ENABLED_AT.lock().is_some()
+}
+
+/// Enables or disables measuring `scheduler_wall_time`, returning whether it was enabled before.
+///
+/// Enabling when disabled restarts the measurements from `0`.
+pub fn set_wall_time_enabled(enabled: bool) -> bool {
+    let mut wall_time_enabled_at = WALL_TIME_ENABLED_AT.lock();
+    let was_enabled = wall_time_enabled_at.is_some();
+
+    if enabled && !was_enabled {
+        let now = Instant::now();
+
+        for scheduler in registered() {
+            scheduler.statistics.parked_time.lock().reset(now);
+        }
+
+        *wall_time_enabled_at = Some(now);
+    } else if !enabled {
+        *wall_time_enabled_at = None;
+    }
+
+    was_enabled
+}
+
+/// Returns `None` if `scheduler_wall_time` is not enabled.
+pub fn wall_time() -> Option<Vec<WallTime>> {
+    let option_enabled_at = *WALL_TIME_ENABLED_AT.lock();
+
+    option_enabled_at.map(|enabled_at| {
+        let now = Instant::now();
+        let total = now.saturating_duration_since(enabled_at);
+
+        registered()
+            .iter()
+            .map(|scheduler| {
+                let parked = scheduler.statistics.parked_time.lock().total_at(now);
+
+                WallTime {
+                    id: scheduler.id,
+                    active: total.checked_sub(parked).unwrap_or_default(),
+                    total,
+                }
+            })
+            .collect()
+    })
+}
+
+lazy_static! {
+    static ref WALL_TIME_ENABLED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+}
@@ -1,10 +1,17 @@
 mod spawn_apply_3;
 
+use std::sync::Arc;
+use std::thread;
+
 use liblumen_alloc::erts::process::code::stack::frame::Placement;
-use liblumen_alloc::erts::term::prelude::Atom;
+use liblumen_alloc::erts::process::{code, MaxHeapSize, Priority, Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
 
 use crate::otp::erlang::exit_1;
-use crate::scheduler::{with_process_arc, Scheduler};
+use crate::process::priority::PriorityPromotion;
+use crate::process::spawn::options::Options;
+use crate::scheduler::{with_process_arc, Scheduler, Spawned};
+use crate::test::has_message;
 
 #[test]
 fn scheduler_does_not_requeue_exiting_process() {
@@ -42,3 +49,135 @@ fn scheduler_does_run_exiting_process() {
         assert!(!scheduler.is_run_queued(&arc_process));
     })
 }
+
+#[test]
+fn scheduler_kills_process_exceeding_max_heap_size_and_propagates_exit_to_links() {
+    with_process_arc(|parent_arc_process| {
+        parent_arc_process.trap_exit(true);
+
+        let mut options: Options = Default::default();
+        options.link = true;
+        options.max_heap_size = Some(MaxHeapSize {
+            size: 1_000,
+            kill: true,
+            error_logger: false,
+        });
+
+        let Spawned {
+            arc_process: child_arc_process,
+            connection,
+        } = Scheduler::spawn_code(
+            &parent_arc_process,
+            options,
+            Atom::try_from_str("test").unwrap(),
+            Atom::try_from_str("grow").unwrap(),
+            &[Term::NIL],
+            grow,
+        )
+        .unwrap();
+        assert!(connection.linked);
+
+        let scheduler = Scheduler::current();
+        let mut runs = 0;
+
+        while !child_arc_process.is_exiting() {
+            assert!(scheduler.run_through(&child_arc_process));
+
+            runs += 1;
+            assert!(runs < 1_000, "process was not killed after {} runs", runs);
+        }
+
+        let killed = Atom::str_to_term("killed");
+
+        match *child_arc_process.status.read() {
+            Status::Exiting(ref exception) => assert_eq!(exception.reason(), Some(killed)),
+            ref status => panic!("process is {:?} instead of exiting", status),
+        }
+        assert!(!scheduler.is_run_queued(&child_arc_process));
+
+        let message = parent_arc_process
+            .tuple_from_slice(&[
+                Atom::str_to_term("EXIT"),
+                child_arc_process.pid_term(),
+                killed,
+            ])
+            .unwrap();
+        assert!(has_message(&parent_arc_process, message));
+    })
+}
+
+#[test]
+fn promoting_run_queued_process_moves_it_to_promoted_run_queue() {
+    with_process_arc(|arc_process| {
+        let scheduler = Scheduler::current();
+
+        assert!(scheduler.is_run_queued(&arc_process));
+
+        let normal_len = scheduler.run_queue_len(Priority::Normal);
+        let high_len = scheduler.run_queue_len(Priority::High);
+
+        {
+            let _promotion = PriorityPromotion::new(Arc::clone(&arc_process), Priority::High);
+
+            assert_eq!(scheduler.run_queue_len(Priority::Normal), normal_len - 1);
+            assert_eq!(scheduler.run_queue_len(Priority::High), high_len + 1);
+        }
+
+        assert_eq!(scheduler.run_queue_len(Priority::Normal), normal_len);
+        assert_eq!(scheduler.run_queue_len(Priority::High), high_len);
+        assert!(scheduler.is_run_queued(&arc_process));
+    })
+}
+
+#[test]
+fn idle_scheduler_steals_runnable_process() {
+    with_process_arc(|arc_process| {
+        let victim = Scheduler::current();
+
+        assert!(victim.is_run_queued(&arc_process));
+
+        let thief_arc_process = Arc::clone(&arc_process);
+        let thief_victim = Arc::clone(&victim);
+
+        let thief_id = thread::spawn(move || {
+            let thief = Scheduler::current();
+
+            assert!(thief.steal_from(&thief_victim));
+            assert!(thief.is_run_queued(&thief_arc_process));
+
+            thief.id
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(arc_process.scheduler_id(), Some(thief_id));
+        assert!(!victim.is_run_queued(&arc_process));
+    })
+}
+
+#[test]
+fn scheduler_does_not_park_with_runnable_process() {
+    with_process_arc(|arc_process| {
+        let scheduler = Scheduler::current();
+
+        assert!(scheduler.is_run_queued(&arc_process));
+
+        // would block forever if it parked as there are no timers and nothing to unpark it
+        scheduler.park();
+
+        assert!(!scheduler.is_parked());
+    })
+}
+
+/// Conses onto the list on top of its stack without bound.  The stack is a root, so the heap can
+/// only grow.
+fn grow(arc_process: &Arc<Process>) -> code::Result {
+    arc_process.reduce();
+
+    let tail = arc_process.stack_top().unwrap();
+    let list = arc_process.cons(Atom::str_to_term("garbage"), tail)?;
+    arc_process.stack_pop();
+    arc_process.stack_push(list)?;
+
+    Process::call_code(arc_process)
+}
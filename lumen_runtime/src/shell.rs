@@ -0,0 +1,27 @@
+//! The interactive shell started by `config::Command::Shell`.
+//!
+//! The runtime can't evaluate Erlang expressions on its own, so the shell is set by the crate
+//! that can, such as `liblumen_eir_interpreter`.
+
+use std::io;
+
+use liblumen_core::locks::RwLock;
+
+pub type Start = fn() -> io::Result<()>;
+
+pub fn set_start(start: Start) {
+    *RW_LOCK_START.write() = Some(start);
+}
+
+pub(crate) fn start() -> anyhow::Result<()> {
+    match *RW_LOCK_START.read() {
+        Some(start) => start().map_err(From::from),
+        None => Err(anyhow::anyhow!(
+            "No shell has been set with `lumen_runtime::shell::set_start`"
+        )),
+    }
+}
+
+lazy_static! {
+    static ref RW_LOCK_START: RwLock<Option<Start>> = RwLock::new(None);
+}
@@ -27,3 +27,26 @@ pub fn system_time() -> Duration {
         .duration_since(SystemTime::UNIX_EPOCH)
         .expect("Unable to get system time!")
 }
+
+/// The CPU time used by all threads of the runtime
+#[cfg(unix)]
+pub fn cpu_time() -> Duration {
+    let mut timespec = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+
+    if unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut timespec) } == 0 {
+        Duration::new(timespec.tv_sec as u64, timespec.tv_nsec as u32)
+    } else {
+        Duration::from_millis(crate::time::monotonic::time_in_milliseconds())
+    }
+}
+
+/// The CPU time used by all threads of the runtime
+///
+/// CPU time is not available on this platform, so the time the runtime has been running is used.
+#[cfg(not(unix))]
+pub fn cpu_time() -> Duration {
+    Duration::from_millis(crate::time::monotonic::time_in_milliseconds())
+}
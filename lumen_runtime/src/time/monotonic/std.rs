@@ -1,3 +1,5 @@
+use core::time::Duration;
+
 use std::time::Instant;
 
 use super::Milliseconds;
@@ -6,6 +8,11 @@ pub fn time_in_milliseconds() -> Milliseconds {
     START.elapsed().as_millis() as Milliseconds
 }
 
+/// The time since the monotonic clock started, at the finest resolution available
+pub fn time_since_start() -> Duration {
+    START.elapsed()
+}
+
 lazy_static! {
     static ref START: Instant = Instant::now();
 }
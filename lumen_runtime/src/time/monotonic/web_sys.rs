@@ -1,3 +1,5 @@
+use core::time::Duration;
+
 use super::Milliseconds;
 
 pub fn time_in_milliseconds() -> Milliseconds {
@@ -8,3 +10,13 @@ pub fn time_in_milliseconds() -> Milliseconds {
 
     performance.now() as Milliseconds
 }
+
+/// The time since the monotonic clock started, at the finest resolution available
+pub fn time_since_start() -> Duration {
+    let window = web_sys::window().expect("should have a window in this context");
+    let performance = window
+        .performance()
+        .expect("performance should be available");
+
+    Duration::from_secs_f64(performance.now() / 1_000.0)
+}
@@ -14,6 +14,7 @@ use hashbrown::HashMap;
 use liblumen_core::locks::Mutex;
 
 use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::Status;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::CloneToProcess;
 use liblumen_alloc::Process;
@@ -56,6 +57,23 @@ pub fn start(
     result
 }
 
+/// Starts a timer that wakes `process` at `monotonic_time_milliseconds` so that the `after`
+/// clause of its current receive runs if no message was received before then.
+pub fn start_receive_timeout(
+    monotonic_time_milliseconds: Milliseconds,
+    process: &Arc<Process>,
+) -> Reference {
+    let scheduler = Scheduler::current();
+
+    let result = scheduler.hierarchy.write().start_receive_timeout(
+        monotonic_time_milliseconds,
+        process,
+        &scheduler,
+    );
+
+    result
+}
+
 /// Times out the timers for the thread that have timed out since the last time `timeout` was
 /// called.
 #[cfg(all(not(target_arch = "wasm32"), test))]
@@ -106,6 +124,21 @@ impl Hierarchy {
             })
     }
 
+    /// Returns `true` if there are no timers that still need to time out.
+    pub fn is_empty(&self) -> bool {
+        self.timer_by_reference_number.is_empty()
+    }
+
+    /// The milliseconds until the next timer times out, so that the scheduler can sleep until
+    /// then.  Returns `None` if there are no timers.
+    pub fn milliseconds_until_next_timeout(&self) -> Option<Milliseconds> {
+        self.timer_by_reference_number
+            .values()
+            .filter_map(|weak_timer| weak_timer.upgrade())
+            .map(|arc_timer| arc_timer.milliseconds_remaining())
+            .min()
+    }
+
     fn position(&self, monotonic_time_milliseconds: Milliseconds) -> Position {
         if monotonic_time_milliseconds < self.soon.slot_monotonic_time_milliseconds {
             Position::AtOnce
@@ -152,16 +185,53 @@ impl Hierarchy {
                 process_tuple.clone_to_fragment()?
             }
         };
+        let event = Event::Message(message::HeapFragment {
+            heap_fragment,
+            term: heap_fragment_message,
+        });
+
+        self.start_timer(
+            reference_number,
+            monotonic_time_milliseconds,
+            destination,
+            event,
+        );
+
+        Ok(process_reference)
+    }
+
+    fn start_receive_timeout(
+        &mut self,
+        monotonic_time_milliseconds: Milliseconds,
+        process: &Arc<Process>,
+        scheduler: &Scheduler,
+    ) -> Reference {
+        let reference_number = scheduler.next_reference_number();
+
+        self.start_timer(
+            reference_number,
+            monotonic_time_milliseconds,
+            Destination::Process(Arc::downgrade(process)),
+            Event::ReceiveTimeout,
+        );
+
+        Reference::new(scheduler.id, reference_number)
+    }
+
+    fn start_timer(
+        &mut self,
+        reference_number: ReferenceNumber,
+        monotonic_time_milliseconds: Milliseconds,
+        destination: Destination,
+        event: Event,
+    ) {
         let position = self.position(monotonic_time_milliseconds);
 
         let timer = Timer {
             reference_number,
             monotonic_time_milliseconds,
             destination,
-            message_heap: Mutex::new(message::HeapFragment {
-                heap_fragment,
-                term: heap_fragment_message,
-            }),
+            event: Mutex::new(event),
             position: Mutex::new(position),
         };
 
@@ -178,8 +248,6 @@ impl Hierarchy {
 
         self.timer_by_reference_number
             .insert(reference_number, cancellable);
-
-        Ok(process_reference)
     }
 
     pub fn timeout(&mut self) {
@@ -351,6 +419,15 @@ pub enum Timeout {
     TimeoutTuple,
 }
 
+/// What happens to the `Destination` when a `Timer` times out.
+#[cfg_attr(debug_assertions, derive(Debug))]
+enum Event {
+    /// Sends the message in the `HeapFragment`.
+    Message(message::HeapFragment),
+    /// Marks the current receive as timed out, so that its `after` clause runs.
+    ReceiveTimeout,
+}
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 struct Timer {
     // Can't be a `Boxed` `LocalReference` `Term` because those are boxed and the original Process
@@ -358,7 +435,7 @@ struct Timer {
     reference_number: ReferenceNumber,
     monotonic_time_milliseconds: Milliseconds,
     destination: Destination,
-    message_heap: Mutex<message::HeapFragment>,
+    event: Mutex<Event>,
     position: Mutex<Position>,
 }
 
@@ -383,12 +460,45 @@ impl Timer {
         };
 
         if let Some(destination_arc_process) = option_destination_arc_process {
-            let message::HeapFragment {
-                heap_fragment,
-                term,
-            } = self.message_heap.into_inner();
+            let wake = match self.event.into_inner() {
+                Event::Message(message::HeapFragment {
+                    heap_fragment,
+                    term,
+                }) => {
+                    destination_arc_process.send_heap_message(heap_fragment, term);
+
+                    true
+                }
+                Event::ReceiveTimeout => destination_arc_process
+                    .mailbox
+                    .lock()
+                    .borrow_mut()
+                    .recv_timeout(self.reference_number),
+            };
+
+            if wake {
+                Self::stop_waiting(&destination_arc_process);
+            }
+        }
+    }
 
-            destination_arc_process.send_heap_message(heap_fragment, term);
+    fn stop_waiting(process: &Process) {
+        let stopped_waiting = {
+            let mut writable_status = process.status.write();
+
+            if *writable_status == Status::Waiting {
+                *writable_status = Status::Runnable;
+
+                true
+            } else {
+                false
+            }
+        };
+
+        if stopped_waiting {
+            if let Some(arc_scheduler) = process.scheduler() {
+                arc_scheduler.stop_waiting(process);
+            }
         }
     }
 }